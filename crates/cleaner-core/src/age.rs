@@ -0,0 +1,248 @@
+//! Age policies for the `--days` filter.
+//!
+//! A matched directory's own mtime only changes when entries are added,
+//! removed or renamed, so it says little about whether the tree below it is
+//! still in use. The subtree policies walk the match with
+//! [`fastwalk::read_dir_times`] and stop at the first entry that is too
+//! recent.
+
+use crate::fastwalk;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Which timestamp decides how old a match is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AgePolicy {
+    /// The matched path's own modification time.
+    #[default]
+    DirMtime,
+    /// The newest modification time of the match or anything below it.
+    NewestDescendant,
+    /// The newest access time of the files in the match. Directories count
+    /// by their modification time: listing one, as every scan does, sets
+    /// its access time on `relatime` and `strictatime` mounts.
+    Atime,
+}
+
+impl AgePolicy {
+    pub const ALL: [Self; 3] = [Self::DirMtime, Self::NewestDescendant, Self::Atime];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DirMtime => "dir-mtime",
+            Self::NewestDescendant => "newest-descendant",
+            Self::Atime => "atime",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == value)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|policy| policy.name()).collect();
+                format!(
+                    "unknown age policy '{value}' (expected {})",
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// True if `path` is older than `days` under this policy. Anything that
+    /// cannot be read counts as recent, so it is kept.
+    pub fn is_older_than(self, path: &Path, is_dir: bool, days: u64) -> bool {
+        let Some(cutoff) = cutoff(days) else {
+            return false;
+        };
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        let own = if self == Self::Atime && !is_dir {
+            metadata.accessed()
+        } else {
+            metadata.modified()
+        };
+        if own.ok().is_none_or(|time| seconds(time) >= cutoff) {
+            return false;
+        }
+        if !is_dir || self == Self::DirMtime {
+            return true;
+        }
+        self.subtree_is_older(path.to_path_buf(), cutoff)
+    }
+
+    /// Seconds since the epoch of the newest timestamp this policy weighs
+    /// for `path`, or `None` if any of them cannot be read.
+    pub fn newest(self, path: &Path, is_dir: bool) -> Option<i64> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let own = if self == Self::Atime && !is_dir {
+            metadata.accessed()
+        } else {
+            metadata.modified()
+        };
+        let mut newest = seconds(own.ok()?);
+        if !is_dir || self == Self::DirMtime {
+            return Some(newest);
+        }
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fastwalk::read_dir_times(&dir).ok()? {
+                let time = if self == Self::Atime && !entry.is_dir {
+                    entry.accessed
+                } else {
+                    entry.modified
+                };
+                newest = newest.max(time);
+                if entry.is_dir && !entry.is_symlink {
+                    pending.push(dir.join(&entry.name));
+                }
+            }
+        }
+        Some(newest)
+    }
+
+    fn subtree_is_older(self, root: PathBuf, cutoff: i64) -> bool {
+        let mut pending = vec![root];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fastwalk::read_dir_times(&dir) else {
+                return false;
+            };
+            for entry in entries {
+                let time = if self == Self::Atime && !entry.is_dir {
+                    entry.accessed
+                } else {
+                    entry.modified
+                };
+                if time >= cutoff {
+                    return false;
+                }
+                if entry.is_dir && !entry.is_symlink {
+                    pending.push(dir.join(&entry.name));
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Seconds since the epoch before which a timestamp is older than `days`,
+/// or `None` when no timestamp can be that old.
+pub(crate) fn cutoff(days: u64) -> Option<i64> {
+    let max_age = i64::try_from(days.checked_mul(24 * 60 * 60)?).ok()?;
+    seconds(SystemTime::now()).checked_sub(max_age)
+}
+
+/// Seconds since the epoch, negative before it.
+pub(crate) fn seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::fs::{File, FileTimes};
+    use std::time::Duration;
+
+    fn age(path: &Path, days: u64) {
+        let time = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        let times = FileTimes::new().set_modified(time).set_accessed(time);
+        File::options()
+            .write(!path.is_dir())
+            .read(path.is_dir())
+            .open(path)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+    }
+
+    #[test]
+    fn policies_parse_by_name() {
+        for policy in AgePolicy::ALL {
+            assert_eq!(AgePolicy::parse(policy.name()), Ok(policy));
+        }
+        assert!(AgePolicy::parse("ctime").unwrap_err().contains("dir-mtime"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn newest_descendant_keeps_directories_with_recent_contents() {
+        let temp = TempDir::new("age-descendant");
+        let fresh = temp.write("node_modules/dep/lib/index.js", b"");
+        let old = temp.write("node_modules/dep/package.json", b"");
+        age(&old, 90);
+        for dir in ["node_modules/dep/lib", "node_modules/dep", "node_modules"] {
+            age(&temp.join(dir), 90);
+        }
+        let root = temp.join("node_modules");
+        assert!(AgePolicy::DirMtime.is_older_than(&root, true, 30));
+        assert!(!AgePolicy::NewestDescendant.is_older_than(&root, true, 30));
+
+        age(&fresh, 60);
+        assert!(AgePolicy::NewestDescendant.is_older_than(&root, true, 30));
+        assert!(!AgePolicy::NewestDescendant.is_older_than(&root, true, 75));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atime_policy_reads_access_times() {
+        let temp = TempDir::new("age-atime");
+        let file = temp.write("cache/blob", b"");
+        age(&file, 40);
+        let dir = temp.join("cache");
+        age(&dir, 40);
+        assert!(AgePolicy::Atime.is_older_than(&dir, true, 30));
+        let times = FileTimes::new().set_accessed(SystemTime::now());
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+        assert!(!AgePolicy::Atime.is_older_than(&dir, true, 30));
+        assert!(AgePolicy::NewestDescendant.is_older_than(&dir, true, 30));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atime_policy_gives_the_same_answer_when_run_twice() {
+        let temp = TempDir::new("age-atime-twice");
+        let file = temp.write("cache/sub/blob", b"");
+        age(&file, 40);
+        for dir in ["cache/sub", "cache"] {
+            age(&temp.join(dir), 40);
+        }
+        let dir = temp.join("cache");
+        // The first run lists both directories, which may set their access
+        // times to now; the second must not see that as use.
+        assert!(AgePolicy::Atime.is_older_than(&dir, true, 30));
+        assert!(AgePolicy::Atime.is_older_than(&dir, true, 30));
+    }
+
+    #[test]
+    fn newest_follows_the_policy() {
+        let temp = TempDir::new("age-newest");
+        let file = temp.write("cache/data.bin", b"fresh");
+        let dir = temp.join("cache");
+        age(&dir, 30);
+        let day = |time: Option<i64>| time.map(|time| time.div_euclid(86_400));
+        let today = day(Some(seconds(SystemTime::now())));
+        assert!(day(AgePolicy::DirMtime.newest(&dir, true)) < today);
+        assert_eq!(day(AgePolicy::NewestDescendant.newest(&dir, true)), today);
+        assert_eq!(day(AgePolicy::Atime.newest(&file, false)), today);
+        assert_eq!(AgePolicy::DirMtime.newest(&temp.join("gone"), false), None);
+    }
+
+    #[test]
+    fn missing_paths_and_huge_ages_are_never_old() {
+        let temp = TempDir::new("age-missing");
+        let file = temp.write("file", b"");
+        assert!(!AgePolicy::DirMtime.is_older_than(&temp.join("missing"), false, 1));
+        assert!(!AgePolicy::NewestDescendant.is_older_than(&file, false, u64::MAX));
+    }
+}
@@ -1,7 +1,11 @@
-//! Configuration loading with priority: env vars > config file > defaults
+//! Configuration loading: defaults < system file < user file < `-c` file < env vars
 
+use crate::age::AgePolicy;
+use crate::patterns::{glob, PatternMatcher};
 use serde::Deserialize;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Default directories to delete
 pub const DEFAULT_DIRECTORIES: &[&str] = &[
@@ -43,20 +47,150 @@ pub const DEFAULT_FILES: &[&str] = &[
     "~",
 ];
 
+/// Directory patterns that only match next to a marker.
+///
+/// A `build` folder is only a build output when it sits beside the file that
+/// produces it; anywhere else it may well hold checked-in scripts. Markers are
+/// sibling names (files or directories, globs allowed); any one of them is
+/// enough. Patterns without an entry here match unconditionally.
+pub const DEFAULT_MARKERS: &[(&str, &[&str])] = &[
+    ("target", &["Cargo.toml", "pom.xml"]),
+    ("node_modules", &["package.json"]),
+    (
+        "build",
+        &[
+            "build.gradle",
+            "build.gradle.kts",
+            "CMakeLists.txt",
+            "setup.py",
+            "pyproject.toml",
+        ],
+    ),
+    ("dist", &["package.json", "setup.py", "pyproject.toml"]),
+    ("coverage", &["package.json"]),
+];
+
+/// System-wide config file, the lowest layer above the built-in defaults.
+#[cfg(unix)]
+pub const SYSTEM_CONFIG: &str = "/etc/cleaner.toml";
+
+/// Per-project config file name, picked up while walking.
+pub const PROJECT_CONFIG: &str = ".cleaner.toml";
+
 /// Configuration file structure
 #[derive(Debug, Deserialize, Default)]
 pub struct ConfigFile {
     #[serde(default)]
     pub patterns: PatternsConfig,
+    #[serde(default)]
+    pub exclude: ExcludeConfig,
+    #[serde(default)]
+    pub protect: ProtectConfig,
     pub days: Option<u64>,
+    pub age_policy: Option<AgePolicy>,
+    /// Audit journal path; `~` expands to the home directory.
+    pub journal: Option<String>,
+    pub quarantine_days: Option<u64>,
+}
+
+/// A list value in a config file: either a plain array, or a table that
+/// says how it combines with the list from the layers below:
+///
+/// ```toml
+/// directories = { extend = ["out"] }
+/// files = { replace = [".log"] }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ListValue {
+    Plain(Vec<String>),
+    Edit(ListEdit),
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ListEdit {
+    /// Start over from this list instead of the inherited one.
+    pub replace: Option<Vec<String>>,
+    /// Append these entries (skipping ones already present).
+    #[serde(default)]
+    pub extend: Vec<String>,
+}
+
+impl ListValue {
+    /// Apply this value on top of `list`. A plain array replaces the list
+    /// when `plain_replaces` (an empty one is ignored, as it always was) and
+    /// extends it otherwise.
+    pub fn apply(self, list: &mut Vec<String>, plain_replaces: bool) {
+        let edit = match self {
+            Self::Plain(values) if plain_replaces => ListEdit {
+                replace: (!values.is_empty()).then_some(values),
+                extend: Vec::new(),
+            },
+            Self::Plain(values) => ListEdit {
+                replace: None,
+                extend: values,
+            },
+            Self::Edit(edit) => edit,
+        };
+        if let Some(values) = edit.replace {
+            *list = values;
+        }
+        for value in edit.extend {
+            if !list.contains(&value) {
+                list.push(value);
+            }
+        }
+    }
+}
+
+/// `[protect]` in a config file; see [`ProtectRules`].
+#[derive(Debug, Deserialize, Default)]
+pub struct ProtectConfig {
+    pub add: Option<ListValue>,
+    pub remove: Option<ListValue>,
+}
+
+/// Directories to protect on top of the built-in list, and built-in
+/// protected directories (or parts of them) to open up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtectRules {
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+/// `[exclude]`: paths that are never cleaned, as path globs.
+#[derive(Debug, Deserialize, Default)]
+pub struct ExcludeConfig {
+    pub paths: Option<ListValue>,
 }
 
 #[derive(Debug, Deserialize, Default)]
 pub struct PatternsConfig {
+    pub directories: Option<ListValue>,
+    pub files: Option<ListValue>,
+    /// `[patterns.markers]`: directory pattern -> required sibling names.
+    /// Replaces the inherited rule for the same pattern; an empty list makes
+    /// the pattern unconditional.
     #[serde(default)]
-    pub directories: Vec<String>,
-    #[serde(default)]
-    pub files: Vec<String>,
+    pub markers: BTreeMap<String, Vec<String>>,
+}
+
+/// A directory pattern plus the sibling names that must exist for it to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerRule {
+    /// The directory pattern exactly as it appears in [`Config::directories`].
+    pub directory: String,
+    pub markers: Vec<String>,
+}
+
+impl MarkerRule {
+    pub fn new(directory: impl Into<String>, markers: &[&str]) -> Self {
+        Self {
+            directory: directory.into(),
+            markers: markers.iter().map(|marker| marker.to_string()).collect(),
+        }
+    }
 }
 
 /// Runtime configuration
@@ -64,75 +198,315 @@ pub struct PatternsConfig {
 pub struct Config {
     pub directories: Vec<String>,
     pub files: Vec<String>,
+    pub markers: Vec<MarkerRule>,
+    /// Path globs that are never cleaned, together with everything below
+    /// them. Unlike the pattern lists these accumulate: the config file,
+    /// `CLEANER_EXCLUDE` and `--exclude` all add rules.
+    pub exclude: Vec<String>,
+    pub protect: ProtectRules,
     pub days: Option<u64>,
+    /// How the age of a match is measured for [`Config::days`].
+    pub age_policy: AgePolicy,
     pub force: bool,
+    /// Only report matches that the enclosing git repository ignores and
+    /// does not track.
+    pub gitignored_only: bool,
+    /// Stay on the filesystem the scan starts on: other mounts are neither
+    /// walked nor cleaned.
+    pub one_file_system: bool,
+    /// Count a hard-linked file at every one of its links, like `du -l`,
+    /// instead of once.
+    pub count_links: bool,
+    /// Report file lengths, like `du --apparent-size`, instead of the space
+    /// files take on disk.
+    pub apparent_size: bool,
+    /// Live runs move matches to the trash instead of deleting them.
+    pub trash: bool,
+    /// Live runs move matches into cleaner's quarantine instead of deleting
+    /// them. Takes precedence over [`Config::trash`].
+    pub quarantine: bool,
+    /// Quarantined items older than this many days are deleted for good
+    /// when a run that quarantines starts. `None` keeps them until purged.
+    pub quarantine_days: Option<u64>,
+    /// Append a JSON Lines record of every deleted item here.
+    pub journal: Option<PathBuf>,
+    /// Config files that contributed to this config, lowest priority first.
+    pub sources: Vec<PathBuf>,
 }
 
 impl Config {
+    /// No patterns, no marker rules, every option off.
+    ///
+    /// The starting point for callers that assemble a config by hand rather
+    /// than loading one.
+    pub fn empty() -> Self {
+        Self {
+            directories: Vec::new(),
+            files: Vec::new(),
+            markers: Vec::new(),
+            exclude: Vec::new(),
+            protect: ProtectRules::default(),
+            days: None,
+            age_policy: AgePolicy::DirMtime,
+            force: false,
+            gitignored_only: false,
+            one_file_system: false,
+            count_links: false,
+            apparent_size: false,
+            trash: false,
+            quarantine: false,
+            quarantine_days: None,
+            journal: None,
+            sources: Vec::new(),
+        }
+    }
+
+    /// The device a walk from `root` has to stay on: `Some` when
+    /// [`Config::one_file_system`] is set and the platform has device IDs.
+    pub fn root_device(&self, root: &Path) -> Option<u64> {
+        if self.one_file_system {
+            crate::fastwalk::device_of(root)
+        } else {
+            None
+        }
+    }
+
+    /// Like [`Config::load`], but fails on a config file that cannot be
+    /// read or parsed, and on an invalid pattern.
     pub fn try_load(config_path: Option<&Path>) -> Result<Self, String> {
-        if let Some(path) = config_path {
-            let content = std::fs::read_to_string(path)
-                .map_err(|error| format!("Cannot read config {}: {error}", path.display()))?;
-            toml::from_str::<ConfigFile>(&content)
-                .map_err(|error| format!("Invalid config {}: {error}", path.display()))?;
+        Self::try_load_with_env(config_path, system_config(), |name| {
+            std::env::var(name).ok()
+        })
+    }
+
+    fn try_load_with_env<F>(
+        config_path: Option<&Path>,
+        system: Option<&Path>,
+        mut env: F,
+    ) -> Result<Self, String>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let paths = Self::discover(config_path, system, &mut env);
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            files.push((read_config_file(&path)?, path));
         }
-        Ok(Self::load(config_path))
+        let config = Self::from_layers(files, &mut env);
+        PatternMatcher::try_new(Arc::new(config.clone()))
+            .map_err(|error| format!("Invalid pattern: {error}"))?;
+        Ok(config)
     }
 
-    /// Load configuration with priority: env vars > config file > defaults
+    /// Load configuration. Layers, lowest priority first: built-in defaults,
+    /// [`SYSTEM_CONFIG`], `$XDG_CONFIG_HOME/cleaner/config.toml`,
+    /// `config_path`, then environment variables. Unreadable files are
+    /// skipped.
     pub fn load(config_path: Option<&Path>) -> Self {
-        Self::load_with_env(config_path, |name| std::env::var(name).ok())
+        Self::load_with_env(config_path, system_config(), |name| {
+            std::env::var(name).ok()
+        })
+    }
+
+    fn load_with_env<F>(config_path: Option<&Path>, system: Option<&Path>, mut env: F) -> Self
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let files = Self::discover(config_path, system, &mut env)
+            .into_iter()
+            .filter_map(|path| Some((read_config_file(&path).ok()?, path)))
+            .collect();
+        Self::from_layers(files, &mut env)
     }
 
-    fn load_with_env<F>(config_path: Option<&Path>, mut env: F) -> Self
+    /// Config files to layer, lowest priority first, starting with the
+    /// `system` file. Discovered files are only listed when they exist;
+    /// `explicit` always is.
+    fn discover<F>(explicit: Option<&Path>, system: Option<&Path>, env: &mut F) -> Vec<PathBuf>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let mut paths = Vec::with_capacity(3);
+        paths.extend(system.map(Path::to_path_buf));
+        if let Some(user) = user_config_path(env) {
+            paths.push(user);
+        }
+        paths.retain(|path| path.is_file());
+        if let Some(path) = explicit {
+            paths.push(path.to_path_buf());
+        }
+        paths
+    }
+
+    fn from_layers<F>(files: Vec<(ConfigFile, PathBuf)>, env: &mut F) -> Self
     where
         F: FnMut(&str) -> Option<String>,
     {
         // Start with defaults
-        let mut directories: Vec<String> =
-            DEFAULT_DIRECTORIES.iter().map(|s| s.to_string()).collect();
-        let mut files: Vec<String> = DEFAULT_FILES.iter().map(|s| s.to_string()).collect();
-        let mut days = None;
-
-        // Override with config file if provided
-        if let Some(path) = config_path {
-            if let Ok(content) = std::fs::read_to_string(path) {
-                if let Ok(config) = toml::from_str::<ConfigFile>(&content) {
-                    if !config.patterns.directories.is_empty() {
-                        directories = config.patterns.directories;
-                    }
-                    if !config.patterns.files.is_empty() {
-                        files = config.patterns.files;
-                    }
-                    if config.days.is_some() {
-                        days = config.days;
-                    }
-                }
+        let mut config = Self {
+            directories: DEFAULT_DIRECTORIES.iter().map(|s| s.to_string()).collect(),
+            files: DEFAULT_FILES.iter().map(|s| s.to_string()).collect(),
+            markers: DEFAULT_MARKERS
+                .iter()
+                .map(|(directory, names)| MarkerRule::new(*directory, names))
+                .collect(),
+            ..Self::empty()
+        };
+
+        for (file, path) in files {
+            if let Some(days) = file.days {
+                config.days = Some(days);
+            }
+            if let Some(policy) = file.age_policy {
+                config.age_policy = policy;
+            }
+            if let Some(days) = file.quarantine_days {
+                config.quarantine_days = Some(days);
             }
+            if let Some(journal) = file.journal {
+                config.journal = Some(crate::protected::expand_home(&journal));
+            }
+            if let Some(add) = file.protect.add {
+                add.apply(&mut config.protect.add, false);
+            }
+            if let Some(remove) = file.protect.remove {
+                remove.apply(&mut config.protect.remove, false);
+            }
+            config.apply_patterns(file.patterns, file.exclude);
+            config.sources.push(path);
         }
 
         // Override with environment variables (highest priority)
         if let Some(env_dirs) = env("CLEANER_DIRS") {
-            directories = env_dirs.split(',').map(|s| s.trim().to_string()).collect();
+            config.directories = env_dirs.split(',').map(|s| s.trim().to_string()).collect();
         }
         if let Some(env_files) = env("CLEANER_FILES") {
-            files = env_files.split(',').map(|s| s.trim().to_string()).collect();
+            config.files = env_files.split(',').map(|s| s.trim().to_string()).collect();
+        }
+        if let Some(env_exclude) = env("CLEANER_EXCLUDE") {
+            config.exclude.extend(
+                env_exclude
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|rule| !rule.is_empty())
+                    .map(str::to_string),
+            );
         }
         if let Some(env_days) = env("CLEANER_DAYS") {
             if let Ok(d) = env_days.parse() {
-                days = Some(d);
+                config.days = Some(d);
             }
         }
 
-        Self {
-            directories,
-            files,
-            days,
-            force: false,
+        config
+    }
+
+    /// The config for the subtree at `dir`, which holds a [`PROJECT_CONFIG`].
+    ///
+    /// Project files may only change `[patterns]` and add to `[exclude]`;
+    /// relative exclude rules are anchored at `dir`, and a `replace` of them
+    /// is an error. Other keys are ignored so a checked-out repository cannot
+    /// lift protections or excludes, or change the age filter and its policy.
+    pub fn with_project_file(&self, dir: &Path) -> Result<Self, String> {
+        let path = dir.join(PROJECT_CONFIG);
+        let mut file = read_config_file(&path)?;
+        let mut config = self.clone();
+        match &mut file.exclude.paths {
+            Some(ListValue::Plain(paths)) => anchor_excludes(paths, dir),
+            Some(ListValue::Edit(edit)) if edit.replace.is_some() => {
+                return Err(format!(
+                    "Invalid config {}: [exclude] paths can only be extended here",
+                    path.display()
+                ));
+            }
+            Some(ListValue::Edit(edit)) => anchor_excludes(&mut edit.extend, dir),
+            None => {}
         }
+        config.apply_patterns(file.patterns, file.exclude);
+        config.sources.push(path);
+        Ok(config)
+    }
+
+    fn apply_patterns(&mut self, patterns: PatternsConfig, exclude: ExcludeConfig) {
+        if let Some(directories) = patterns.directories {
+            directories.apply(&mut self.directories, true);
+        }
+        if let Some(files) = patterns.files {
+            files.apply(&mut self.files, true);
+        }
+        for (directory, names) in patterns.markers {
+            self.markers.retain(|rule| rule.directory != directory);
+            if !names.is_empty() {
+                self.markers.push(MarkerRule {
+                    directory,
+                    markers: names,
+                });
+            }
+        }
+        if let Some(paths) = exclude.paths {
+            paths.apply(&mut self.exclude, false);
+        }
+    }
+
+    /// Marker names required for directory pattern `directory`, if any.
+    pub fn markers_for(&self, directory: &str) -> Option<&[String]> {
+        self.markers
+            .iter()
+            .find(|rule| rule.directory == directory)
+            .map(|rule| rule.markers.as_slice())
     }
 }
 
+/// `$XDG_CONFIG_HOME/cleaner/config.toml`, falling back to `~/.config`.
+fn user_config_path<F>(env: &mut F) -> Option<PathBuf>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let base = env("XDG_CONFIG_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("cleaner").join("config.toml"))
+}
+
+/// [`SYSTEM_CONFIG`] where the platform has one.
+fn system_config() -> Option<&'static Path> {
+    #[cfg(unix)]
+    {
+        Some(Path::new(SYSTEM_CONFIG))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|error| format!("Cannot read config {}: {error}", path.display()))?;
+    toml::from_str(&content).map_err(|error| format!("Invalid config {}: {error}", path.display()))
+}
+
+/// Make relative exclude rules from a project file absolute under `dir`.
+fn anchor_excludes(rules: &mut [String], dir: &Path) {
+    for rule in rules {
+        *rule = anchor_exclude(rule, dir);
+    }
+}
+
+/// `rule` made absolute under `dir` if it is relative, with any leading
+/// `./` dropped.
+pub fn anchor_exclude(rule: &str, dir: &Path) -> String {
+    if rule.starts_with('/') || rule.starts_with('~') {
+        return rule.to_string();
+    }
+    let mut relative = rule;
+    while let Some(rest) = relative.strip_prefix("./") {
+        relative = rest.trim_start_matches('/');
+    }
+    format!("{}/{relative}", glob::escape(&dir.to_string_lossy()))
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self::load(None)
@@ -150,13 +524,38 @@ mod tests {
 
     #[test]
     fn defaults_contain_expected_patterns() {
-        let config = Config::load_with_env(None, no_env);
+        let config = Config::load_with_env(None, None, no_env);
         assert_eq!(config.directories.len(), DEFAULT_DIRECTORIES.len());
         assert_eq!(config.files.len(), DEFAULT_FILES.len());
         assert!(config.directories.iter().any(|pattern| pattern == "target"));
         assert!(config.files.iter().any(|pattern| pattern == ".pyc"));
         assert_eq!(config.days, None);
         assert!(!config.force);
+        assert_eq!(
+            config.markers_for("target"),
+            Some(&["Cargo.toml".to_string(), "pom.xml".to_string()][..])
+        );
+        assert_eq!(config.markers_for("__pycache__"), None);
+    }
+
+    #[test]
+    fn file_markers_replace_or_clear_builtin_rules() {
+        let temp = TempDir::new("config-markers");
+        let path = temp.write(
+            "cleaner.toml",
+            b"[patterns.markers]\nbuild = ['meson.build']\ntarget = []\nout = ['*.csproj']\n",
+        );
+        let config = Config::load_with_env(Some(&path), None, no_env);
+        assert_eq!(
+            config.markers_for("build"),
+            Some(&["meson.build".to_string()][..])
+        );
+        assert_eq!(config.markers_for("target"), None);
+        assert_eq!(
+            config.markers_for("out"),
+            Some(&["*.csproj".to_string()][..])
+        );
+        assert!(config.markers_for("node_modules").is_some());
     }
 
     #[test]
@@ -166,7 +565,7 @@ mod tests {
             "cleaner.toml",
             b"days = 7\n[patterns]\ndirectories = ['cache-dir']\nfiles = ['.tmp']\n",
         );
-        let config = Config::load_with_env(Some(&path), no_env);
+        let config = Config::load_with_env(Some(&path), None, no_env);
         assert_eq!(config.directories, ["cache-dir"]);
         assert_eq!(config.files, [".tmp"]);
         assert_eq!(config.days, Some(7));
@@ -176,11 +575,11 @@ mod tests {
     fn empty_or_invalid_file_values_fall_back_to_defaults() {
         let temp = TempDir::new("config-fallback");
         let empty = temp.write("empty.toml", b"[patterns]\ndirectories = []\nfiles = []\n");
-        let config = Config::load_with_env(Some(&empty), no_env);
+        let config = Config::load_with_env(Some(&empty), None, no_env);
         assert_eq!(config.directories.len(), DEFAULT_DIRECTORIES.len());
 
         let invalid = temp.write("invalid.toml", b"this is not = valid toml [");
-        let config = Config::load_with_env(Some(&invalid), no_env);
+        let config = Config::load_with_env(Some(&invalid), None, no_env);
         assert_eq!(config.files.len(), DEFAULT_FILES.len());
     }
 
@@ -191,7 +590,7 @@ mod tests {
             "cleaner.toml",
             b"days = 7\n[patterns]\ndirectories = ['from-file']\nfiles = ['.file']\n",
         );
-        let config = Config::load_with_env(Some(&path), |name| match name {
+        let config = Config::load_with_env(Some(&path), None, |name| match name {
             "CLEANER_DIRS" => Some(" one, two ".into()),
             "CLEANER_FILES" => Some(".log, ~".into()),
             "CLEANER_DAYS" => Some("30".into()),
@@ -202,11 +601,195 @@ mod tests {
         assert_eq!(config.days, Some(30));
     }
 
+    #[test]
+    fn exclude_rules_from_file_and_environment_accumulate() {
+        let temp = TempDir::new("config-exclude");
+        let path = temp.write(
+            "cleaner.toml",
+            b"[exclude]\npaths = ['~/src/legacy-app/build']\n",
+        );
+        let config = Config::load_with_env(Some(&path), None, |name| {
+            (name == "CLEANER_EXCLUDE").then(|| " /opt/keep, ,**/vendor ".into())
+        });
+        assert_eq!(
+            config.exclude,
+            ["~/src/legacy-app/build", "/opt/keep", "**/vendor"]
+        );
+        assert!(Config::load_with_env(None, None, no_env).exclude.is_empty());
+    }
+
+    #[test]
+    fn protect_rules_are_read_from_the_file() {
+        let temp = TempDir::new("config-protect");
+        let path = temp.write(
+            "cleaner.toml",
+            b"[protect]\nadd = ['/srv/data']\nremove = ['/opt/ci']\n",
+        );
+        let config = Config::load_with_env(Some(&path), None, no_env);
+        assert_eq!(config.protect.add, ["/srv/data"]);
+        assert_eq!(config.protect.remove, ["/opt/ci"]);
+        assert_eq!(
+            Config::load_with_env(None, None, no_env).protect,
+            ProtectRules::default()
+        );
+    }
+
+    #[test]
+    fn user_and_explicit_files_layer_with_extend_and_replace() {
+        let temp = TempDir::new("config-layers");
+        let system = temp.write(
+            "system.toml",
+            b"days = 9\njournal = '/var/log/cleaner.jsonl'\n[protect]\nadd = ['/srv/system']\n",
+        );
+        temp.write(
+            "xdg/cleaner/config.toml",
+            b"days = 3\n[patterns]\ndirectories = { extend = ['out', 'target'] }\n\
+              [protect]\nadd = ['/srv/data']\n",
+        );
+        let explicit = temp.write(
+            "explicit.toml",
+            b"[patterns]\nfiles = { replace = ['.log'], extend = ['.tmp'] }\n\
+              [protect]\nadd = ['/srv/more']\n",
+        );
+        let xdg = temp.join("xdg").to_string_lossy().into_owned();
+        let config = Config::load_with_env(Some(&explicit), Some(&system), |name| {
+            (name == "XDG_CONFIG_HOME").then(|| xdg.clone())
+        });
+        assert_eq!(config.days, Some(3));
+        assert_eq!(
+            config.journal,
+            Some(PathBuf::from("/var/log/cleaner.jsonl"))
+        );
+        assert_eq!(config.directories.len(), DEFAULT_DIRECTORIES.len() + 1);
+        assert_eq!(config.directories.last().unwrap(), "out");
+        assert_eq!(config.files, [".log", ".tmp"]);
+        assert_eq!(
+            config.protect.add,
+            ["/srv/system", "/srv/data", "/srv/more"]
+        );
+        assert_eq!(config.sources.first().unwrap(), &system);
+        assert_eq!(config.sources.last().unwrap(), &explicit);
+        assert!(config
+            .sources
+            .contains(&temp.join("xdg/cleaner/config.toml")));
+
+        let home = temp.path().to_string_lossy().into_owned();
+        assert_eq!(
+            user_config_path(&mut |name: &str| (name == "HOME").then(|| home.clone())),
+            Some(temp.join(".config/cleaner/config.toml"))
+        );
+    }
+
+    #[test]
+    fn broken_discovered_file_fails_try_load() {
+        let temp = TempDir::new("config-layers-invalid");
+        temp.write(
+            "cleaner/config.toml",
+            b"[patterns]\ndirectories = { add = ['x'] }\n",
+        );
+        let xdg = temp.path().to_string_lossy().into_owned();
+        let error = Config::try_load_with_env(None, None, |name| {
+            (name == "XDG_CONFIG_HOME").then(|| xdg.clone())
+        })
+        .unwrap_err();
+        assert!(error.starts_with("Invalid config"));
+    }
+
+    #[test]
+    fn relative_exclude_rules_are_anchored_without_their_dot() {
+        let dir = Path::new("/scan/[root]");
+        assert_eq!(anchor_exclude("legacy", dir), "/scan/\\[root\\]/legacy");
+        assert_eq!(
+            anchor_exclude("./legacy/*", dir),
+            "/scan/\\[root\\]/legacy/*"
+        );
+        assert_eq!(
+            anchor_exclude(".//./legacy", dir),
+            "/scan/\\[root\\]/legacy"
+        );
+        assert_eq!(anchor_exclude("/abs", dir), "/abs");
+        assert_eq!(anchor_exclude("~/keep", dir), "~/keep");
+    }
+
+    #[test]
+    fn project_files_change_patterns_and_anchor_excludes_only() {
+        let temp = TempDir::new("config-project");
+        temp.write(
+            ".cleaner.toml",
+            b"days = 1\n[patterns]\ndirectories = ['out']\n[patterns.markers]\nout = ['Makefile']\n\
+              [exclude]\npaths = ['./keep', '/abs']\n[protect]\nremove = ['/']\n",
+        );
+        let base = Config::load_with_env(None, None, no_env);
+        let project = base.with_project_file(temp.path()).unwrap();
+        assert_eq!(project.directories, ["out"]);
+        assert_eq!(
+            project.markers_for("out"),
+            Some(&["Makefile".to_string()][..])
+        );
+        assert_eq!(
+            project.exclude,
+            [
+                format!("{}/keep", temp.path().display()),
+                "/abs".to_string()
+            ]
+        );
+        assert_eq!(project.days, None);
+        assert!(project.protect.remove.is_empty());
+        assert!(base.with_project_file(&temp.join("missing")).is_err());
+
+        temp.write(".cleaner.toml", b"[exclude]\npaths = { replace = [] }\n");
+        assert!(base.with_project_file(temp.path()).is_err());
+    }
+
+    #[test]
+    fn age_policy_is_read_from_the_file_and_validated() {
+        let temp = TempDir::new("config-age-policy");
+        let path = temp.write(
+            "cleaner.toml",
+            b"days = 30\nage_policy = 'newest-descendant'\n",
+        );
+        let config = Config::load_with_env(Some(&path), None, no_env);
+        assert_eq!(config.age_policy, AgePolicy::NewestDescendant);
+        assert_eq!(
+            Config::load_with_env(None, None, no_env).age_policy,
+            AgePolicy::DirMtime
+        );
+        let invalid = temp.write("invalid.toml", b"age_policy = 'ctime'\n");
+        assert!(Config::try_load_with_env(Some(&invalid), None, no_env)
+            .unwrap_err()
+            .starts_with("Invalid config"));
+    }
+
+    #[test]
+    fn journal_path_is_read_from_the_file() {
+        let temp = TempDir::new("config-journal");
+        let path = temp.write("cleaner.toml", b"journal = '/var/log/cleaner.jsonl'\n");
+        assert_eq!(
+            Config::load_with_env(Some(&path), None, no_env).journal,
+            Some(PathBuf::from("/var/log/cleaner.jsonl"))
+        );
+        assert_eq!(Config::load_with_env(None, None, no_env).journal, None);
+    }
+
+    #[test]
+    fn root_device_is_only_pinned_in_one_file_system_mode() {
+        let temp = TempDir::new("config-device");
+        let mut config = Config::empty();
+        assert_eq!(config.root_device(temp.path()), None);
+        config.one_file_system = true;
+        assert_eq!(
+            config.root_device(temp.path()),
+            crate::fastwalk::device_of(temp.path())
+        );
+        #[cfg(unix)]
+        assert!(config.root_device(temp.path()).is_some());
+    }
+
     #[test]
     fn invalid_environment_days_does_not_replace_file_value() {
         let temp = TempDir::new("config-env-invalid");
         let path = temp.write("cleaner.toml", b"days = 4\n");
-        let config = Config::load_with_env(Some(&path), |name| {
+        let config = Config::load_with_env(Some(&path), None, |name| {
             (name == "CLEANER_DAYS").then(|| "not-a-number".into())
         });
         assert_eq!(config.days, Some(4));
@@ -222,5 +805,9 @@ mod tests {
         assert!(Config::try_load(Some(&invalid))
             .unwrap_err()
             .contains("Invalid config"));
+        let bad_pattern = temp.write("pattern.toml", b"[patterns]\ndirectories = ['re:(']\n");
+        assert!(Config::try_load(Some(&bad_pattern))
+            .unwrap_err()
+            .contains("Invalid pattern"));
     }
 }
@@ -1,15 +1,23 @@
 //! Parallel deletion engine
 //! Uses rayon for parallel file/directory removal with streaming processing
 
+use crate::config::Config;
+use crate::disk_usage::get_disk_usage;
+use crate::fastwalk::HardLink;
+use crate::journal::{Journal, Record};
 #[cfg(test)]
 use crate::pool::build_worker_pool;
+use crate::quarantine::Quarantine;
+use crate::remove;
 use crate::scanner::ScanResult;
 use crate::stats::Stats;
+use crate::trash::Trash;
 use crossbeam_channel::Receiver;
+use foldhash::{HashMap, HashMapExt};
 use rayon::prelude::*;
 use rayon::ThreadPool;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 /// Where a `Deleter` sends its per-item error and verbose output.
@@ -19,11 +27,126 @@ use std::sync::{Arc, Mutex};
 /// passes a collector instead and renders the lines itself.
 pub type MessageSink = Arc<Mutex<Vec<String>>>;
 
+/// What a live `Deleter` does with each match.
+#[derive(Debug, Clone, Default)]
+pub enum Disposal {
+    /// Remove it permanently.
+    #[default]
+    Delete,
+    /// Move it to the trash. `Stats` still counts what was moved.
+    Trash(Arc<Trash>),
+    /// Move it into cleaner's quarantine, from where it can be restored
+    /// until it is purged. `Stats` still counts what was moved.
+    Quarantine(Arc<Quarantine>),
+}
+
+impl Disposal {
+    /// The disposal `config` asks for.
+    pub fn for_config(config: &Config) -> Result<Self, String> {
+        if config.quarantine {
+            Ok(Self::Quarantine(Arc::new(Quarantine::from_env()?)))
+        } else if config.trash {
+            Ok(Self::Trash(Arc::new(Trash::from_env()?)))
+        } else {
+            Ok(Self::Delete)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Trash(_) => "trash",
+            Self::Quarantine(_) => "quarantine",
+        }
+    }
+
+    /// Where matches go, for messages; `None` when they are deleted.
+    pub fn destination(&self) -> Option<&'static str> {
+        match self {
+            Self::Delete => None,
+            Self::Trash(_) => Some("the trash"),
+            Self::Quarantine(_) => Some("quarantine"),
+        }
+    }
+
+    /// Dispose of `path`, which takes up `size` bytes, without counting what
+    /// it contained.
+    pub fn dispose(&self, path: &Path, size: u64) -> Result<(), String> {
+        match self {
+            Self::Delete => match fs::symlink_metadata(path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    remove::remove_tree(path, false, None).into_result()
+                }
+                Ok(_) => fs::remove_file(path)
+                    .map_err(|error| format!("Error deleting {}: {error}", path.display())),
+                Err(error) => Err(format!("Error deleting {}: {error}", path.display())),
+            },
+            Self::Trash(trash) => trash.put(path).map(drop),
+            Self::Quarantine(quarantine) => quarantine.put(path, size).map(drop),
+        }
+    }
+}
+
+/// How much free space a `Deleter` should stop at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeGoal {
+    /// Any path on the filesystem being cleaned.
+    pub path: PathBuf,
+    /// Free bytes when the deleter started.
+    pub initial_free: u64,
+    /// Free bytes at which the deleter stops.
+    pub target_free: u64,
+}
+
+impl FreeGoal {
+    /// Stop once the filesystem holding `path` has `bytes` free.
+    pub fn target_free(path: &Path, bytes: u64) -> Result<Self, String> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            initial_free: Self::free_on(path)?,
+            target_free: bytes,
+        })
+    }
+
+    /// Stop once `bytes` more than now are free on the filesystem holding
+    /// `path`.
+    pub fn free_bytes(path: &Path, bytes: u64) -> Result<Self, String> {
+        let initial_free = Self::free_on(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            initial_free,
+            target_free: initial_free.saturating_add(bytes),
+        })
+    }
+
+    fn free_on(path: &Path) -> Result<u64, String> {
+        get_disk_usage(path)
+            .map(|(_, free)| free)
+            .ok_or_else(|| format!("Cannot read free space for {}", path.display()))
+    }
+
+    /// Free bytes now. A dry run frees nothing, so it adds the bytes it
+    /// would have freed to the starting figure instead of asking the disk.
+    /// A live run that cannot read the free space fails rather than guess.
+    pub fn current_free(&self, dry_run: bool, freed: u64) -> Result<u64, String> {
+        if dry_run {
+            return Ok(self.initial_free.saturating_add(freed));
+        }
+        Self::free_on(&self.path)
+    }
+
+    pub fn is_met(&self, dry_run: bool, freed: u64) -> Result<bool, String> {
+        Ok(self.current_free(dry_run, freed)? >= self.target_free)
+    }
+}
+
 #[derive(Default)]
 struct DeleteOutcome {
     directories: usize,
     files: usize,
     bytes: u64,
+    /// Hard-linked files and their sizes, not yet in `bytes`.
+    links: Vec<(HardLink, u64)>,
     errors: Vec<String>,
     verbose: Option<String>,
     verbose_lines: Vec<String>,
@@ -34,6 +157,7 @@ impl DeleteOutcome {
         self.directories = self.directories.saturating_add(other.directories);
         self.files = self.files.saturating_add(other.files);
         self.bytes = self.bytes.saturating_add(other.bytes);
+        self.links.extend(other.links);
         self.errors.extend(other.errors);
         if let Some(line) = other.verbose {
             self.verbose_lines.push(line);
@@ -50,6 +174,14 @@ pub struct Deleter {
     pool: Arc<ThreadPool>,
     batch_size: usize,
     sink: Option<MessageSink>,
+    disposal: Disposal,
+    journal: Option<(Arc<Journal>, &'static str)>,
+    goal: Option<FreeGoal>,
+    device: Option<u64>,
+    count_links: bool,
+    apparent: bool,
+    /// How many links of each hard-linked file have been counted so far.
+    seen_links: Mutex<HashMap<(u64, u64), u64>>,
 }
 
 impl Deleter {
@@ -109,9 +241,61 @@ impl Deleter {
             pool,
             batch_size,
             sink,
+            disposal: Disposal::Delete,
+            journal: None,
+            goal: None,
+            device: None,
+            count_links: false,
+            apparent: false,
+            seen_links: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Use `disposal` for live runs instead of deleting permanently.
+    pub fn disposal(mut self, disposal: Disposal) -> Self {
+        self.disposal = disposal;
+        self
+    }
+
+    /// Record every item in `journal`, attributed to `source` (see
+    /// [`Record::source`]).
+    pub fn journal(mut self, journal: Arc<Journal>, source: &'static str) -> Self {
+        self.journal = Some((journal, source));
+        self
+    }
+
+    /// Stop taking items once `goal` is met, or once free space cannot be
+    /// read. Items go one per worker thread at a time, with the goal
+    /// checked before each batch, so a run overshoots by at most a batch.
+    /// Items should arrive in the order they are worth deleting, see
+    /// [`crate::scanner::Scanner::scan_ranked`].
+    pub fn until_free(mut self, goal: FreeGoal) -> Self {
+        self.goal = Some(goal);
+        self.batch_size = self.pool.current_num_threads().max(1);
+        self
+    }
+
+    /// Refuse items that are not on filesystem `device`, the root's device
+    /// under [`crate::config::Config::one_file_system`].
+    pub fn one_file_system(mut self, device: u64) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Count a hard-linked file's bytes at every link, see
+    /// [`crate::config::Config::count_links`].
+    pub fn count_links(mut self, count_links: bool) -> Self {
+        self.count_links = count_links;
+        self
+    }
+
+    /// Count files by length rather than by the space they take on disk,
+    /// see [`crate::fastwalk::RawEntry::apparent_size`].
+    pub fn apparent_size(mut self, apparent: bool) -> Self {
+        self.apparent = apparent;
+        self
+    }
+
     /// Route a line to the sink when there is one, otherwise to the terminal.
     fn emit(&self, line: &str, is_error: bool) {
         if let Some(sink) = &self.sink {
@@ -133,6 +317,9 @@ impl Deleter {
         let mut batch = Vec::with_capacity(self.batch_size);
 
         for item in rx {
+            if batch.is_empty() && self.goal_reached() {
+                return;
+            }
             batch.push(item);
 
             // Process batch when full
@@ -148,6 +335,22 @@ impl Deleter {
         }
     }
 
+    /// Whether a free-space goal says to stop: it is met, or free space
+    /// can no longer be read, which is reported as an error.
+    fn goal_reached(&self) -> bool {
+        let Some(goal) = &self.goal else {
+            return false;
+        };
+        match goal.is_met(self.dry_run, self.stats.bytes()) {
+            Ok(met) => met,
+            Err(error) => {
+                self.emit(&format!("{error}; stopping"), true);
+                self.stats.add_errors(1);
+                true
+            }
+        }
+    }
+
     /// Process a batch of items in parallel
     #[inline]
     fn process_batch(&self, batch: &[ScanResult]) {
@@ -157,6 +360,9 @@ impl Deleter {
                 .map(|item| self.delete_item(item))
                 .collect()
         });
+        if let Some((journal, source)) = &self.journal {
+            self.record(journal, source, batch, &outcomes);
+        }
         let mut batch_outcome = DeleteOutcome::default();
         for outcome in outcomes {
             batch_outcome.merge(outcome);
@@ -175,30 +381,69 @@ impl Deleter {
         );
     }
 
+    fn record(
+        &self,
+        journal: &Journal,
+        source: &str,
+        batch: &[ScanResult],
+        outcomes: &[DeleteOutcome],
+    ) {
+        let errors: Vec<Option<String>> = outcomes
+            .iter()
+            .map(|outcome| (!outcome.errors.is_empty()).then(|| outcome.errors.join("; ")))
+            .collect();
+        let records = batch
+            .iter()
+            .zip(outcomes)
+            .zip(&errors)
+            .map(|((item, outcome), error)| Record {
+                path: &item.path,
+                kind: if item.is_dir { "dir" } else { "file" },
+                bytes: outcome.bytes,
+                rule: item.rule.as_deref(),
+                dry_run: self.dry_run,
+                disposal: self.disposal.name(),
+                source,
+                error: error.as_deref(),
+            });
+        if let Err(error) = journal.write(records) {
+            self.emit(&error, true);
+        }
+    }
+
     fn delete_item(&self, item: &ScanResult) -> DeleteOutcome {
+        if let Some(device) = self.device {
+            if crate::fastwalk::device_of(&item.path) != Some(device) {
+                return DeleteOutcome {
+                    errors: vec![format!(
+                        "Refusing to delete {}: on a different filesystem",
+                        item.path.display()
+                    )],
+                    ..DeleteOutcome::default()
+                };
+            }
+        }
+
         let mut outcome = if self.dry_run && item.is_dir {
-            Self::count_dir_contents(&item.path)
+            Self::count_dir_contents(&item.path, self.apparent, self.device)
         } else if self.dry_run {
-            DeleteOutcome {
-                files: 1,
-                bytes: item.size,
-                ..DeleteOutcome::default()
-            }
+            Self::file_counted(item, self.apparent)
+        } else if self.disposal.destination().is_some() {
+            self.move_counted(item, |bytes| self.disposal.dispose(&item.path, bytes))
         } else if item.is_dir {
-            Self::remove_dir_counted(&item.path)
+            Self::remove_dir_counted(&item.path, self.apparent, item.identity)
         } else {
+            let counted = Self::file_counted(item, self.apparent);
             match fs::remove_file(&item.path) {
-                Ok(()) => DeleteOutcome {
-                    files: 1,
-                    bytes: item.size,
-                    ..DeleteOutcome::default()
-                },
+                Ok(()) => counted,
                 Err(error) => DeleteOutcome {
                     errors: vec![format!("Error deleting {}: {error}", item.path.display())],
                     ..DeleteOutcome::default()
                 },
             }
         };
+        let links = std::mem::take(&mut outcome.links);
+        outcome.bytes = outcome.bytes.saturating_add(self.linked_bytes(&links));
 
         if self.verbose {
             let type_str = if item.is_dir { "DIR " } else { "FILE" };
@@ -212,50 +457,99 @@ impl Deleter {
         outcome
     }
 
-    fn remove_dir_counted(root: &Path) -> DeleteOutcome {
-        let mut outcome = DeleteOutcome::default();
-        let mut stack = vec![(root.to_path_buf(), false, true)];
-        while let Some((path, visited, is_root)) = stack.pop() {
-            if visited {
-                match fs::remove_dir(&path) {
-                    Ok(()) if is_root => outcome.directories = 1,
-                    Ok(()) => {}
-                    Err(error) => outcome
-                        .errors
-                        .push(format!("Error deleting {}: {error}", path.display())),
-                }
-                continue;
+    /// Count `item`, then hand its byte count to `put` to move it away.
+    fn move_counted(
+        &self,
+        item: &ScanResult,
+        put: impl FnOnce(u64) -> Result<(), String>,
+    ) -> DeleteOutcome {
+        let mut outcome = if item.is_dir {
+            // Unreadable subdirectories only make the count low; they do not
+            // stop the move, so they are not errors here.
+            DeleteOutcome {
+                errors: Vec::new(),
+                ..Self::count_dir_contents(&item.path, self.apparent, self.device)
             }
-
-            stack.push((path.clone(), true, is_root));
-            match crate::fastwalk::read_dir_fast(&path) {
-                Ok(entries) => {
-                    for entry in entries {
-                        let child = path.join(&entry.name);
-                        if entry.is_dir && !entry.is_symlink {
-                            stack.push((child, false, false));
-                        } else {
-                            match fs::remove_file(&child) {
-                                Ok(()) => {
-                                    outcome.files = outcome.files.saturating_add(1);
-                                    outcome.bytes = outcome.bytes.saturating_add(entry.size);
-                                }
-                                Err(error) => outcome
-                                    .errors
-                                    .push(format!("Error deleting {}: {error}", child.display())),
-                            }
-                        }
-                    }
-                }
-                Err(error) => outcome
-                    .errors
-                    .push(format!("Error reading {}: {error}", path.display())),
+        } else {
+            Self::file_counted(item, self.apparent)
+        };
+        let size = outcome.links.iter().map(|(_, size)| size).sum::<u64>();
+        match put(outcome.bytes.saturating_add(size)) {
+            Ok(()) => {
+                outcome.directories = usize::from(item.is_dir);
+                outcome
             }
+            Err(error) => DeleteOutcome {
+                errors: vec![error],
+                ..DeleteOutcome::default()
+            },
+        }
+    }
+
+    fn remove_dir_counted(
+        root: &Path,
+        apparent: bool,
+        identity: Option<(u64, u64)>,
+    ) -> DeleteOutcome {
+        let removal = remove::remove_tree(root, apparent, identity);
+        DeleteOutcome {
+            directories: usize::from(removal.removed),
+            files: removal.files,
+            bytes: removal.bytes,
+            links: removal.links,
+            errors: removal.errors,
+            ..DeleteOutcome::default()
+        }
+    }
+
+    /// The single file `item`, with its size held back if it is hard-linked.
+    fn file_counted(item: &ScanResult, apparent: bool) -> DeleteOutcome {
+        let metadata = fs::symlink_metadata(&item.path).ok();
+        let size = match &metadata {
+            Some(metadata) if apparent => metadata.len(),
+            _ => item.size,
+        };
+        match metadata.as_ref().and_then(HardLink::from_metadata) {
+            Some(link) => DeleteOutcome {
+                files: 1,
+                links: vec![(link, size)],
+                ..DeleteOutcome::default()
+            },
+            None => DeleteOutcome {
+                files: 1,
+                bytes: size,
+                ..DeleteOutcome::default()
+            },
         }
-        outcome
     }
 
-    fn count_dir_contents(path: &Path) -> DeleteOutcome {
+    /// The bytes `links` free: a file counts once every one of its links
+    /// has been seen. A live delete reads each link count just before
+    /// unlinking, so a name unlinked after the others arrives without a
+    /// link and is already in the bytes; names unlinked together, each
+    /// still seeing the others, are counted here once the last is seen.
+    fn linked_bytes(&self, links: &[(HardLink, u64)]) -> u64 {
+        if self.count_links {
+            return links.iter().map(|(_, size)| size).sum();
+        }
+        let Ok(mut seen) = self.seen_links.lock() else {
+            return 0;
+        };
+        links
+            .iter()
+            .filter(|(link, _)| {
+                let count = seen.entry((link.device, link.inode)).or_insert(0);
+                *count += 1;
+                *count == link.links
+            })
+            .map(|(_, size)| size)
+            .sum()
+    }
+
+    /// What deleting the directory at `path` would free. With `device`, as
+    /// under [`Deleter::one_file_system`], subdirectories on other devices
+    /// are left out, just as removing the directory leaves them alone.
+    fn count_dir_contents(path: &Path, apparent: bool, device: Option<u64>) -> DeleteOutcome {
         let mut outcome = DeleteOutcome::default();
         let mut stack = vec![path.to_path_buf()];
 
@@ -264,10 +558,22 @@ impl Deleter {
                 Ok(entries) => {
                     for entry in entries {
                         if entry.is_dir && !entry.is_symlink {
-                            stack.push(current_path.join(&entry.name));
+                            let subdir = current_path.join(&entry.name);
+                            if device.is_none_or(|device| entry.device_at(&subdir) == Some(device))
+                            {
+                                stack.push(subdir);
+                            }
                         } else {
+                            let size = if apparent {
+                                entry.apparent_size
+                            } else {
+                                entry.size
+                            };
                             outcome.files = outcome.files.saturating_add(1);
-                            outcome.bytes = outcome.bytes.saturating_add(entry.size);
+                            match entry.hard_link {
+                                Some(link) => outcome.links.push((link, size)),
+                                None => outcome.bytes = outcome.bytes.saturating_add(size),
+                            }
                         }
                     }
                 }
@@ -299,6 +605,8 @@ mod tests {
             path: directory.clone(),
             is_dir: true,
             size: 0,
+            rule: None,
+            identity: None,
         })
         .unwrap();
         drop(tx);
@@ -327,12 +635,16 @@ mod tests {
             path: file.clone(),
             is_dir: false,
             size: 4,
+            rule: None,
+            identity: None,
         })
         .unwrap();
         tx.send(ScanResult {
             path: directory.clone(),
             is_dir: true,
             size: 0,
+            rule: None,
+            identity: None,
         })
         .unwrap();
         drop(tx);
@@ -354,6 +666,8 @@ mod tests {
             path: temp.join("missing"),
             is_dir: false,
             size: 99,
+            rule: None,
+            identity: None,
         })
         .unwrap();
         drop(tx);
@@ -373,6 +687,8 @@ mod tests {
                 path: temp.join(format!("{i}.tmp")),
                 is_dir: false,
                 size: 1,
+                rule: None,
+                identity: None,
             })
             .unwrap();
         }
@@ -382,6 +698,300 @@ mod tests {
         assert_eq!(stats.bytes(), 70);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn trash_disposal_moves_items_and_keeps_the_counts() {
+        let temp = TempDir::new("deleter-trash");
+        let file = temp.write("work/cache.pyc", b"1234");
+        let directory = temp.mkdir("work/target");
+        temp.write("work/target/content", b"123456");
+        let trash = Arc::new(Trash::new(temp.join("Trash")));
+        let stats = Arc::new(Stats::new());
+        let (tx, rx) = unbounded();
+        for (path, is_dir, size) in [(&file, false, 4), (&directory, true, 0)] {
+            tx.send(ScanResult {
+                path: path.clone(),
+                is_dir,
+                size,
+                rule: None,
+                identity: None,
+            })
+            .unwrap();
+        }
+        tx.send(ScanResult {
+            path: temp.join("work/missing"),
+            is_dir: false,
+            size: 7,
+            rule: None,
+            identity: None,
+        })
+        .unwrap();
+        drop(tx);
+        Deleter::with_threads(Arc::clone(&stats), false, false, 2)
+            .disposal(Disposal::Trash(trash))
+            .process(rx);
+        assert!(!file.exists());
+        assert!(!directory.exists());
+        assert!(temp.join("Trash/files/target/content").is_file());
+        assert!(temp.join("Trash/info/cache.pyc.trashinfo").is_file());
+        assert_eq!(
+            (
+                stats.directories(),
+                stats.files(),
+                stats.bytes(),
+                stats.error_count()
+            ),
+            (1, 2, 10, 1)
+        );
+    }
+
+    #[test]
+    fn quarantine_disposal_records_sizes_for_restore() {
+        let temp = TempDir::new("deleter-quarantine");
+        let directory = temp.mkdir("work/target");
+        temp.write("work/target/a", b"123");
+        temp.write("work/target/b/c", b"4567");
+        let quarantine = Arc::new(Quarantine::new(temp.join("store")));
+        let stats = Arc::new(Stats::new());
+        let (tx, rx) = unbounded();
+        tx.send(ScanResult {
+            path: directory.clone(),
+            is_dir: true,
+            size: 0,
+            rule: None,
+            identity: None,
+        })
+        .unwrap();
+        drop(tx);
+        Deleter::with_threads(Arc::clone(&stats), false, false, 2)
+            .disposal(Disposal::Quarantine(Arc::clone(&quarantine)))
+            .process(rx);
+        assert!(!directory.exists());
+        assert_eq!(
+            (stats.directories(), stats.files(), stats.bytes()),
+            (1, 2, 7)
+        );
+        let entries = quarantine.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].size, 7);
+        quarantine.restore(&entries[0]).unwrap();
+        assert!(directory.join("b/c").is_file());
+    }
+
+    #[test]
+    fn journal_records_each_item_with_its_rule_and_outcome() {
+        let temp = TempDir::new("deleter-journal");
+        let directory = temp.mkdir("target");
+        temp.write("target/a.bin", b"123");
+        let journal = Arc::new(Journal::open(&temp.join("audit.jsonl")).unwrap());
+        let (tx, rx) = unbounded();
+        tx.send(ScanResult {
+            path: directory.clone(),
+            is_dir: true,
+            size: 0,
+            rule: Some("target".to_string()),
+            identity: None,
+        })
+        .unwrap();
+        tx.send(ScanResult {
+            path: temp.join("missing.pyc"),
+            is_dir: false,
+            size: 5,
+            rule: Some(".pyc".to_string()),
+            identity: None,
+        })
+        .unwrap();
+        drop(tx);
+        Deleter::with_threads(Arc::new(Stats::new()), false, false, 2)
+            .journal(Arc::clone(&journal), "scan")
+            .process(rx);
+        assert!(!directory.exists());
+
+        let content = std::fs::read_to_string(journal.path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"type\":\"dir\",\"bytes\":3,\"rule\":\"target\""));
+        assert!(lines[0].ends_with(
+            "\"dry_run\":false,\"disposal\":\"delete\",\"source\":\"scan\",\"error\":null}"
+        ));
+        assert!(lines[1].contains("\"type\":\"file\",\"bytes\":0,\"rule\":\".pyc\""));
+        assert!(lines[1].contains("\"error\":\"Error deleting "));
+    }
+
+    #[test]
+    fn free_goal_stops_the_deleter_once_met() {
+        let temp = TempDir::new("deleter-goal");
+        let stats = Arc::new(Stats::new());
+        let (tx, rx) = unbounded();
+        for i in 0..3 {
+            tx.send(ScanResult {
+                path: temp.write(format!("{i}.tmp"), b"1234"),
+                is_dir: false,
+                size: 4,
+                rule: None,
+                identity: None,
+            })
+            .unwrap();
+        }
+        drop(tx);
+        let goal = FreeGoal {
+            path: temp.path().to_path_buf(),
+            initial_free: 100,
+            target_free: 105,
+        };
+        Deleter::with_threads(Arc::clone(&stats), true, false, 2)
+            .until_free(goal.clone())
+            .process(rx);
+        assert_eq!((stats.files(), stats.bytes()), (2, 8));
+        assert_eq!(goal.is_met(true, stats.bytes()), Ok(true));
+        assert_eq!(goal.is_met(true, 4), Ok(false));
+
+        let goal = FreeGoal::free_bytes(temp.path(), 1).unwrap();
+        assert_eq!(goal.target_free, goal.initial_free + 1);
+    }
+
+    #[test]
+    fn free_goal_stops_when_free_space_cannot_be_read() {
+        let temp = TempDir::new("deleter-goal-unreadable");
+        let file = temp.write("a.tmp", b"1234");
+        let stats = Arc::new(Stats::new());
+        let (tx, rx) = unbounded();
+        tx.send(ScanResult {
+            path: file.clone(),
+            is_dir: false,
+            size: 4,
+            rule: None,
+            identity: None,
+        })
+        .unwrap();
+        drop(tx);
+        let goal = FreeGoal {
+            path: temp.join("gone"),
+            initial_free: 0,
+            target_free: u64::MAX,
+        };
+        assert!(goal.current_free(false, 0).is_err());
+        let sink = MessageSink::default();
+        Deleter::with_sink(
+            Arc::clone(&stats),
+            false,
+            false,
+            build_worker_pool(2, "cleaner-worker"),
+            sink.clone(),
+        )
+        .until_free(goal)
+        .process(rx);
+        assert!(file.exists());
+        assert_eq!((stats.files(), stats.error_count()), (0, 1));
+        assert!(sink.lock().unwrap()[0].contains("stopping"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn one_file_system_refuses_items_on_other_devices() {
+        let temp = TempDir::new("deleter-device");
+        let file = temp.write("other.tmp", b"1234");
+        let device = crate::fastwalk::device_of(temp.path()).unwrap();
+        let item = ScanResult {
+            path: file.clone(),
+            is_dir: false,
+            size: 4,
+            rule: None,
+            identity: None,
+        };
+
+        let stats = Arc::new(Stats::new());
+        let sink = MessageSink::default();
+        Deleter::with_sink(
+            Arc::clone(&stats),
+            false,
+            false,
+            build_worker_pool(1, "test"),
+            Arc::clone(&sink),
+        )
+        .one_file_system(device.wrapping_add(1))
+        .process_batch(std::slice::from_ref(&item));
+        assert!(file.exists());
+        assert_eq!((stats.files(), stats.error_count()), (0, 1));
+        assert!(sink.lock().unwrap()[0].contains("different filesystem"));
+
+        Deleter::with_threads(Arc::clone(&stats), false, false, 1)
+            .one_file_system(device)
+            .process_batch(&[item]);
+        assert!(!file.exists());
+        assert_eq!(stats.files(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn one_file_system_counts_leave_other_mounts_out() {
+        // /dev/shm is a tmpfs of its own wherever it is mounted at all.
+        let root = Path::new("/dev");
+        let Some(device) = crate::fastwalk::device_of(root) else {
+            return;
+        };
+        if crate::fastwalk::device_of(&root.join("shm")) == Some(device) {
+            return;
+        }
+        // A file of our own keeps the mount from being empty.
+        let marker = root.join(format!("shm/cleaner-deleter-{}", std::process::id()));
+        let _ = fs::write(&marker, b"1");
+        let shm = Deleter::count_dir_contents(&root.join("shm"), false, None);
+        let all = Deleter::count_dir_contents(root, false, None);
+        let one = Deleter::count_dir_contents(root, false, Some(device));
+        let _ = fs::remove_file(&marker);
+        assert!(one.files + shm.files <= all.files);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_counted_once_their_last_link_goes() {
+        let temp = TempDir::new("deleter-links");
+        let shared = temp.write("target/shared.bin", b"1234");
+        fs::hard_link(&shared, temp.join("target/again.bin")).unwrap();
+        let kept = temp.write("target/kept.bin", b"12");
+        fs::hard_link(&kept, temp.join("outside.bin")).unwrap();
+        let sized = |path: &Path| {
+            use std::os::unix::fs::MetadataExt;
+            let metadata = fs::metadata(path).unwrap();
+            metadata.len().min(metadata.blocks() * 512)
+        };
+        let (shared_size, kept_size) = (sized(&shared), sized(&kept));
+        let run = |dry_run, count_links| {
+            let stats = Arc::new(Stats::new());
+            let (tx, rx) = unbounded();
+            tx.send(ScanResult {
+                path: temp.join("target"),
+                is_dir: true,
+                size: 0,
+                rule: None,
+                identity: None,
+            })
+            .unwrap();
+            drop(tx);
+            Deleter::with_threads(Arc::clone(&stats), dry_run, false, 1)
+                .count_links(count_links)
+                .process(rx);
+            (stats.files(), stats.bytes())
+        };
+
+        assert_eq!(run(true, false), (3, shared_size));
+        assert_eq!(run(true, true), (3, 2 * shared_size + kept_size));
+        assert_eq!(run(false, false), (3, shared_size));
+        assert!(temp.join("outside.bin").exists());
+    }
+
+    #[test]
+    fn names_unlinked_together_free_their_file_once() {
+        // Two workers can both read a count of two before either unlinks.
+        let deleter = Deleter::with_threads(Arc::new(Stats::new()), false, false, 1);
+        let link = HardLink::new(7, 42, 2).unwrap();
+        assert_eq!(deleter.linked_bytes(&[(link, 100)]), 0);
+        assert_eq!(deleter.linked_bytes(&[(link, 100)]), 100);
+        let other = HardLink::new(7, 43, 3).unwrap();
+        assert_eq!(deleter.linked_bytes(&[(other, 50), (other, 50)]), 0);
+    }
+
     #[test]
     fn deleter_uses_requested_thread_count() {
         let deleter = Deleter::with_threads(Arc::new(Stats::new()), true, false, 3);
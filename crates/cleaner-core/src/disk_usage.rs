@@ -1,4 +1,4 @@
-//! Platform disk free/total helpers.
+//! Platform disk free/total helpers, in bytes and in inodes.
 
 use std::path::Path;
 
@@ -7,6 +7,13 @@ pub fn get_disk_usage(path: &Path) -> Option<(u64, u64)> {
     get_disk_usage_inner(path)
 }
 
+/// Returns `(total_inodes, free_inodes)` for the filesystem containing
+/// `path`. `None` where the filesystem has no fixed inode table (NTFS, btrfs)
+/// and so cannot run out of them.
+pub fn get_inode_usage(path: &Path) -> Option<(u64, u64)> {
+    get_inode_usage_inner(path).filter(|&(total, _)| total > 0)
+}
+
 #[cfg(any(target_os = "macos", target_os = "linux"))]
 fn get_disk_usage_inner(path: &Path) -> Option<(u64, u64)> {
     use std::ffi::CString;
@@ -29,6 +36,21 @@ fn get_disk_usage_inner(path: &Path) -> Option<(u64, u64)> {
     }
 }
 
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn get_inode_usage_inner(path: &Path) -> Option<(u64, u64)> {
+    use std::ffi::CString;
+    let path_str = path.to_str()?;
+    let c_path = CString::new(path_str).ok()?;
+    unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut stat) == 0 {
+            Some((stat.f_files as u64, stat.f_favail as u64))
+        } else {
+            None
+        }
+    }
+}
+
 #[cfg(target_os = "freebsd")]
 fn get_disk_usage_inner(path: &Path) -> Option<(u64, u64)> {
     use std::ffi::CString;
@@ -47,6 +69,26 @@ fn get_disk_usage_inner(path: &Path) -> Option<(u64, u64)> {
     }
 }
 
+#[cfg(target_os = "freebsd")]
+fn get_inode_usage_inner(path: &Path) -> Option<(u64, u64)> {
+    use std::ffi::CString;
+    let path_str = path.to_str()?;
+    let c_path = CString::new(path_str).ok()?;
+    unsafe {
+        let mut stat: libc::statfs = std::mem::zeroed();
+        if libc::statfs(c_path.as_ptr(), &mut stat) == 0 {
+            Some((stat.f_files, stat.f_ffree.max(0) as u64))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "freebsd")))]
+fn get_inode_usage_inner(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
 #[cfg(windows)]
 fn get_disk_usage_inner(path: &Path) -> Option<(u64, u64)> {
     use std::ffi::OsStr;
@@ -101,6 +143,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn inode_usage_is_consistent_when_reported() {
+        if let Some((total, free)) = get_inode_usage(&std::env::temp_dir()) {
+            assert!(total > 0);
+            assert!(free <= total);
+        }
+        assert_eq!(
+            get_inode_usage(Path::new("/path/that/does/not/exist/ever/12345")),
+            None
+        );
+    }
+
     #[test]
     fn disk_usage_handles_nonexistent_path() {
         let missing = Path::new("/path/that/does/not/exist/ever/12345");
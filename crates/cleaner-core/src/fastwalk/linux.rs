@@ -1,4 +1,4 @@
-use super::{MetadataMode, RawEntry, INITIAL_DIRECTORY_CAPACITY};
+use super::{HardLink, MetadataMode, RawEntry, INITIAL_DIRECTORY_CAPACITY};
 use rustix::fd::AsFd;
 use rustix::fs::{AtFlags, Dir, Mode, OFlags};
 use std::os::unix::ffi::OsStringExt;
@@ -32,10 +32,13 @@ pub fn read_dir_fstatat(
 
         let mut file_type = entry.file_type();
         let needs_type = file_type == rustix::fs::FileType::Unknown;
-        let needs_size = metadata_mode == MetadataMode::WithSizes
+        let needs_size = metadata_mode.wants_size()
             && file_type != rustix::fs::FileType::Directory
             && file_type != rustix::fs::FileType::Symlink;
-        let stat = if needs_type || needs_size {
+        let needs_times = metadata_mode == MetadataMode::WithTimes;
+        let needs_device = metadata_mode == MetadataMode::WithDevices
+            && file_type == rustix::fs::FileType::Directory;
+        let stat = if needs_type || needs_size || needs_times || needs_device {
             rustix::fs::statat(dir_fd.as_fd(), name_cstr, AtFlags::SYMLINK_NOFOLLOW).ok()
         } else {
             None
@@ -47,20 +50,45 @@ pub fn read_dir_fstatat(
         }
         let is_dir = file_type == rustix::fs::FileType::Directory;
         let is_symlink = file_type == rustix::fs::FileType::Symlink;
-        let size = stat
-            .filter(|_| !is_dir && !is_symlink && metadata_mode == MetadataMode::WithSizes)
-            .map_or(0, |stat| {
+        // `time_t` is narrower than i64 on some 32-bit targets.
+        #[allow(clippy::unnecessary_cast)]
+        let (modified, accessed) = stat
+            .as_ref()
+            .filter(|_| needs_times)
+            .map_or((0, 0), |stat| (stat.st_mtime as i64, stat.st_atime as i64));
+        // `dev_t` and `nlink_t` are narrower than u64 on some targets.
+        #[allow(clippy::unnecessary_cast)]
+        let hard_link = stat
+            .as_ref()
+            .filter(|_| {
+                file_type == rustix::fs::FileType::RegularFile && metadata_mode.wants_size()
+            })
+            .and_then(|stat| {
+                HardLink::new(stat.st_dev as u64, stat.st_ino as u64, stat.st_nlink as u64)
+            });
+        let uid = stat.as_ref().map(|stat| stat.st_uid);
+        #[allow(clippy::unnecessary_cast)]
+        let device = stat.as_ref().map(|stat| stat.st_dev as u64);
+        let (size, apparent_size) = stat
+            .filter(|_| !is_dir && !is_symlink && metadata_mode.wants_size())
+            .map_or((0, 0), |stat| {
                 let logical_size = stat.st_size as u64;
                 let physical_size = stat.st_blocks as u64 * 512;
-                std::cmp::min(logical_size, physical_size)
+                (std::cmp::min(logical_size, physical_size), logical_size)
             });
 
         let name = std::ffi::OsString::from_vec(name_bytes.to_vec());
         result.push(RawEntry {
             name,
             size,
+            apparent_size,
             is_dir,
             is_symlink,
+            modified,
+            accessed,
+            uid,
+            hard_link,
+            device,
         });
     }
 
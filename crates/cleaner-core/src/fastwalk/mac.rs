@@ -2,7 +2,7 @@
 // Copyright (c) 2023 quivent
 // Licensed under MIT or Apache-2.0
 
-use super::{MetadataMode, RawEntry, INITIAL_DIRECTORY_CAPACITY};
+use super::{HardLink, MetadataMode, RawEntry, INITIAL_DIRECTORY_CAPACITY};
 use std::cell::RefCell;
 use std::ffi::CString;
 use std::os::raw::{c_int, c_void};
@@ -14,7 +14,9 @@ use std::sync::LazyLock;
 const ATTR_BIT_MAP_COUNT: u16 = 5;
 const ATTR_CMN_NAME: u32 = 0x00000001;
 const ATTR_CMN_OBJTYPE: u32 = 0x00000008;
+const ATTR_CMN_FILEID: u32 = 0x02000000;
 const ATTR_CMN_RETURNED_ATTRS: u32 = 0x80000000;
+const ATTR_FILE_LINKCOUNT: u32 = 0x00000001;
 const ATTR_FILE_ALLOCSIZE: u32 = 0x00000004;
 const ATTR_FILE_DATALENGTH: u32 = 0x00000200;
 
@@ -126,16 +128,29 @@ pub(super) fn read_open_directory(
     let mut attr_list = libc::attrlist {
         bitmapcount: ATTR_BIT_MAP_COUNT,
         reserved: 0,
-        commonattr: ATTR_CMN_RETURNED_ATTRS | ATTR_CMN_NAME | ATTR_CMN_OBJTYPE,
+        commonattr: if metadata_mode.wants_size() {
+            ATTR_CMN_RETURNED_ATTRS | ATTR_CMN_NAME | ATTR_CMN_OBJTYPE | ATTR_CMN_FILEID
+        } else {
+            ATTR_CMN_RETURNED_ATTRS | ATTR_CMN_NAME | ATTR_CMN_OBJTYPE
+        },
         volattr: 0,
         dirattr: 0,
-        fileattr: if metadata_mode == MetadataMode::WithSizes {
-            ATTR_FILE_ALLOCSIZE | ATTR_FILE_DATALENGTH
+        fileattr: if metadata_mode.wants_size() {
+            ATTR_FILE_LINKCOUNT | ATTR_FILE_ALLOCSIZE | ATTR_FILE_DATALENGTH
         } else {
             0
         },
         forkattr: 0,
     };
+    // Hard links are files, so they share the device of the directory
+    // that lists them.
+    let device = if metadata_mode.wants_size() {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        (unsafe { libc::fstat(directory.0, stat.as_mut_ptr()) } == 0)
+            .then(|| unsafe { stat.assume_init() }.st_dev as u64)
+    } else {
+        None
+    };
 
     BULK_BUFFER.with(|buffer| {
         let mut buffer = buffer.borrow_mut();
@@ -203,12 +218,43 @@ pub(super) fn read_open_directory(
                 if name != "." && name != ".." {
                     let is_dir = header.obj_type == VDIR;
                     let is_symlink = header.obj_type == VLNK;
-                    let size = if !is_dir && !is_symlink && metadata_mode == MetadataMode::WithSizes
+                    // Attributes follow the prefix in bit order: the file
+                    // ID closes the common ones, then come the file ones.
+                    let mut offset = std::mem::size_of::<EntryPrefix>();
+                    let mut file_id = None;
+                    if (header.returned.commonattr & ATTR_CMN_FILEID) != 0 {
+                        if record_len < offset + std::mem::size_of::<u64>() {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                "truncated getattrlistbulk file id",
+                            ));
+                        }
+                        let val =
+                            unsafe { std::ptr::read_unaligned(ptr.add(offset).cast::<u64>()) };
+                        file_id = Some(val);
+                        offset += std::mem::size_of::<u64>();
+                    }
+                    let mut link_count = None;
+                    let (size, apparent_size) = if !is_dir
+                        && !is_symlink
+                        && metadata_mode.wants_size()
                     {
-                        let mut offset = std::mem::size_of::<EntryPrefix>();
                         let mut alloc_size = None;
                         let mut data_length = None;
 
+                        if (header.returned.fileattr & ATTR_FILE_LINKCOUNT) != 0 {
+                            if record_len < offset + std::mem::size_of::<u32>() {
+                                return Err(std::io::Error::new(
+                                    std::io::ErrorKind::InvalidData,
+                                    "truncated getattrlistbulk file link count",
+                                ));
+                            }
+                            let val =
+                                unsafe { std::ptr::read_unaligned(ptr.add(offset).cast::<u32>()) };
+                            link_count = Some(val);
+                            offset += std::mem::size_of::<u32>();
+                        }
+
                         if (header.returned.fileattr & ATTR_FILE_ALLOCSIZE) != 0 {
                             if record_len < offset + std::mem::size_of::<u64>() {
                                 return Err(std::io::Error::new(
@@ -235,19 +281,31 @@ pub(super) fn read_open_directory(
                         }
 
                         match (alloc_size, data_length) {
-                            (Some(alloc), Some(len)) => std::cmp::min(alloc, len),
-                            (Some(alloc), None) => alloc,
-                            (None, Some(len)) => len,
-                            (None, None) => 0,
+                            (Some(alloc), Some(len)) => (std::cmp::min(alloc, len), len),
+                            (Some(alloc), None) => (alloc, alloc),
+                            (None, Some(len)) => (len, len),
+                            (None, None) => (0, 0),
                         }
                     } else {
-                        0
+                        (0, 0)
+                    };
+                    let hard_link = match (device, file_id, link_count) {
+                        (Some(device), Some(inode), Some(links)) => {
+                            HardLink::new(device, inode, u64::from(links))
+                        }
+                        _ => None,
                     };
                     result_entries.push(RawEntry {
                         name,
                         size,
+                        apparent_size,
                         is_dir,
                         is_symlink,
+                        modified: 0,
+                        accessed: 0,
+                        uid: None,
+                        hard_link,
+                        device: None,
                     });
                 }
 
@@ -10,11 +10,27 @@ use std::sync::Arc;
 // for every empty/near-empty directory; Vec grows geometrically for wide ones.
 pub(super) const INITIAL_DIRECTORY_CAPACITY: usize = 8;
 type ProgressCallback = Arc<dyn Fn(usize, usize, u64) + Send + Sync>;
+/// True for a subdirectory, given its path and entry, that the walk leaves
+/// out.
+pub type SkipCheck = dyn Fn(&Path, &RawEntry) -> bool + Send + Sync;
+/// Returns a directory's listing from somewhere other than disk.
+pub type ReuseListing<'a> = &'a (dyn Fn(&Path) -> Option<Vec<RawEntry>> + Sync);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MetadataMode {
     TypesOnly,
     WithSizes,
+    /// Sizes plus modification and access times, for every entry type.
+    WithTimes,
+    /// Types, plus the device of every directory, for walks that stay on
+    /// one filesystem.
+    WithDevices,
+}
+
+impl MetadataMode {
+    fn wants_size(self) -> bool {
+        matches!(self, Self::WithSizes | Self::WithTimes)
+    }
 }
 
 pub struct WalkOutput<V> {
@@ -30,9 +46,110 @@ mod mac;
 #[derive(Debug, Clone)]
 pub struct RawEntry {
     pub name: OsString,
+    /// Space taken on disk: the smaller of the length and the allocated
+    /// blocks, so sparse and compressed files count what they occupy.
     pub size: u64,
+    /// Length in bytes, as `du --apparent-size` and `ls -l` report it.
+    pub apparent_size: u64,
     pub is_dir: bool,
     pub is_symlink: bool,
+    /// Modification time in seconds since the Unix epoch; 0 unless read
+    /// with [`MetadataMode::WithTimes`].
+    pub modified: i64,
+    /// Access time in seconds since the Unix epoch; 0 unless read with
+    /// [`MetadataMode::WithTimes`].
+    pub accessed: i64,
+    /// Owner's user ID, when the reader stat'ed the entry: always with
+    /// [`MetadataMode::WithTimes`], files only with
+    /// [`MetadataMode::WithSizes`]. The macOS bulk reader leaves it unset.
+    pub uid: Option<u32>,
+    /// Set for a sized regular file with more than one link.
+    pub hard_link: Option<HardLink>,
+    /// Device ID of the filesystem holding the entry, when the reader
+    /// stat'ed it: always with [`MetadataMode::WithTimes`], files only
+    /// with [`MetadataMode::WithSizes`], directories only with
+    /// [`MetadataMode::WithDevices`]. The macOS bulk reader leaves it unset.
+    pub device: Option<u64>,
+}
+
+impl RawEntry {
+    /// [`RawEntry::device`], or the device read from `path`, where the entry
+    /// is, when the reader did not stat it.
+    pub fn device_at(&self, path: &Path) -> Option<u64> {
+        self.device.or_else(|| device_of(path))
+    }
+}
+
+/// A regular file that more than one directory entry points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HardLink {
+    pub device: u64,
+    pub inode: u64,
+    /// Link count when the file was read.
+    pub links: u64,
+}
+
+impl HardLink {
+    /// `Some` when `links` says the file has other names.
+    pub fn new(device: u64, inode: u64, links: u64) -> Option<Self> {
+        (links > 1).then_some(Self {
+            device,
+            inode,
+            links,
+        })
+    }
+
+    /// The hard link `metadata` describes, if it is a regular file with
+    /// more than one link.
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Option<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            metadata
+                .is_file()
+                .then(|| Self::new(metadata.dev(), metadata.ino(), metadata.nlink()))
+                .flatten()
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            None
+        }
+    }
+}
+
+/// Device ID of the filesystem holding `path`, without following a final
+/// symlink. `None` where the platform has no device IDs.
+pub fn device_of(path: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::symlink_metadata(path)
+            .ok()
+            .map(|metadata| metadata.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Device and inode of `path`, without following a final symlink. `None`
+/// where the platform has no inodes.
+pub fn identity_of(path: &Path) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::symlink_metadata(path)
+            .ok()
+            .map(|metadata| (metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
 }
 
 pub fn read_dir_fast(path: &Path) -> std::io::Result<Vec<RawEntry>> {
@@ -43,10 +160,22 @@ pub fn read_dir_types(path: &Path) -> std::io::Result<Vec<RawEntry>> {
     read_dir(path, MetadataMode::TypesOnly)
 }
 
-fn read_dir(path: &Path, metadata_mode: MetadataMode) -> std::io::Result<Vec<RawEntry>> {
+pub fn read_dir_times(path: &Path) -> std::io::Result<Vec<RawEntry>> {
+    read_dir(path, MetadataMode::WithTimes)
+}
+
+pub(crate) fn read_dir(path: &Path, metadata_mode: MetadataMode) -> std::io::Result<Vec<RawEntry>> {
     #[cfg(target_os = "macos")]
     {
-        mac::read_dir_bulk(path, metadata_mode)
+        // getattrlistbulk is only wired up for names, types and sizes.
+        if matches!(
+            metadata_mode,
+            MetadataMode::WithTimes | MetadataMode::WithDevices
+        ) {
+            read_dir_std(path, metadata_mode)
+        } else {
+            mac::read_dir_bulk(path, metadata_mode)
+        }
     }
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     {
@@ -54,137 +183,245 @@ fn read_dir(path: &Path, metadata_mode: MetadataMode) -> std::io::Result<Vec<Raw
     }
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "freebsd")))]
     {
-        let read_dir = std::fs::read_dir(path)?;
-        let mut result = Vec::with_capacity(INITIAL_DIRECTORY_CAPACITY);
-        for entry in read_dir {
-            let entry = entry?;
-            let file_type = entry.file_type()?;
-            let size = if file_type.is_file() && metadata_mode == MetadataMode::WithSizes {
-                let metadata = entry.metadata()?;
+        read_dir_std(path, metadata_mode)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn read_dir_std(path: &Path, metadata_mode: MetadataMode) -> std::io::Result<Vec<RawEntry>> {
+    let read_dir = std::fs::read_dir(path)?;
+    let mut result = Vec::with_capacity(INITIAL_DIRECTORY_CAPACITY);
+    for entry in read_dir {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let metadata = if metadata_mode == MetadataMode::WithTimes
+            || (file_type.is_file() && metadata_mode.wants_size())
+            || (file_type.is_dir() && metadata_mode == MetadataMode::WithDevices)
+        {
+            Some(entry.metadata()?)
+        } else {
+            None
+        };
+        let (size, apparent_size) = match &metadata {
+            Some(metadata) if file_type.is_file() => {
                 #[cfg(unix)]
                 {
                     use std::os::unix::fs::MetadataExt;
-                    std::cmp::min(metadata.len(), metadata.blocks() * 512)
+                    (
+                        std::cmp::min(metadata.len(), metadata.blocks() * 512),
+                        metadata.len(),
+                    )
                 }
                 #[cfg(not(unix))]
-                metadata.len()
-            } else {
-                0
-            };
-            result.push(RawEntry {
-                name: entry.file_name(),
-                size,
-                is_dir: file_type.is_dir(),
-                is_symlink: file_type.is_symlink(),
-            });
-        }
-        Ok(result)
+                (metadata.len(), metadata.len())
+            }
+            _ => (0, 0),
+        };
+        let seconds = |time: std::io::Result<std::time::SystemTime>| {
+            time.ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |elapsed| elapsed.as_secs() as i64)
+        };
+        let (modified, accessed) = metadata.as_ref().map_or((0, 0), |metadata| {
+            (seconds(metadata.modified()), seconds(metadata.accessed()))
+        });
+        let hard_link = metadata
+            .as_ref()
+            .filter(|_| metadata_mode.wants_size())
+            .and_then(HardLink::from_metadata);
+        #[cfg(unix)]
+        let uid = metadata.as_ref().map(|metadata| {
+            use std::os::unix::fs::MetadataExt;
+            metadata.uid()
+        });
+        #[cfg(unix)]
+        let device = metadata.as_ref().map(|metadata| {
+            use std::os::unix::fs::MetadataExt;
+            metadata.dev()
+        });
+        #[cfg(not(unix))]
+        let (uid, device) = (None, None);
+        result.push(RawEntry {
+            name: entry.file_name(),
+            size,
+            apparent_size,
+            is_dir: file_type.is_dir(),
+            is_symlink: file_type.is_symlink(),
+            modified,
+            accessed,
+            uid,
+            hard_link,
+            device,
+        });
     }
+    Ok(result)
 }
 
 #[cfg(test)]
 pub fn walk_parallel(
     root: PathBuf,
     pool: &ThreadPool,
-    skip_check: Arc<dyn Fn(&Path) -> bool + Send + Sync>,
+    skip_check: Arc<SkipCheck>,
     progress_callback: Option<ProgressCallback>,
 ) -> HashMap<PathBuf, Vec<RawEntry>> {
-    walk_parallel_mapped(root, pool, skip_check, progress_callback, &|_, entries| {
-        entries
-    })
+    walk_parallel_mapped(
+        root,
+        pool,
+        MetadataMode::WithSizes,
+        skip_check,
+        progress_callback,
+        &|_, entries| entries,
+    )
     .entries
 }
 
+/// Walk `root` in parallel, reading every directory with `metadata_mode`
+/// and handing its listing to `mapper`.
 pub fn walk_parallel_mapped<V, F>(
     root: PathBuf,
     pool: &ThreadPool,
-    skip_check: Arc<dyn Fn(&Path) -> bool + Send + Sync>,
+    metadata_mode: MetadataMode,
+    skip_check: Arc<SkipCheck>,
     progress_callback: Option<ProgressCallback>,
     mapper: &F,
 ) -> WalkOutput<V>
+where
+    V: Send + 'static,
+    F: Fn(&Path, Vec<RawEntry>) -> V + Sync,
+{
+    let mut entries = HashMap::with_capacity(16_384);
+    let errors = walk(
+        root,
+        pool,
+        metadata_mode,
+        skip_check,
+        progress_callback,
+        None,
+        mapper,
+        &mut |path, value| {
+            entries.insert(path, value);
+        },
+    );
+    WalkOutput { entries, errors }
+}
+
+/// [`walk_parallel_mapped`], except that each mapped listing goes to `sink`
+/// as it arrives instead of into a map, always a directory's before its
+/// subdirectories'. `sink` runs on one thread at a time. A directory
+/// `reuse` has a listing for is not read from disk; its subdirectories are
+/// still walked. Returns the number of directories that could not be read.
+#[allow(clippy::too_many_arguments)]
+pub fn walk_parallel_each<V, F>(
+    root: PathBuf,
+    pool: &ThreadPool,
+    metadata_mode: MetadataMode,
+    skip_check: Arc<SkipCheck>,
+    progress_callback: Option<ProgressCallback>,
+    reuse: Option<ReuseListing<'_>>,
+    mapper: &F,
+    sink: &mut (dyn FnMut(PathBuf, V) + Send),
+) -> usize
+where
+    V: Send + 'static,
+    F: Fn(&Path, Vec<RawEntry>) -> V + Sync,
+{
+    walk(
+        root,
+        pool,
+        metadata_mode,
+        skip_check,
+        progress_callback,
+        reuse,
+        mapper,
+        sink,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk<V, F>(
+    root: PathBuf,
+    pool: &ThreadPool,
+    metadata_mode: MetadataMode,
+    skip_check: Arc<SkipCheck>,
+    progress_callback: Option<ProgressCallback>,
+    reuse: Option<ReuseListing<'_>>,
+    mapper: &F,
+    sink: &mut (dyn FnMut(PathBuf, V) + Send),
+) -> usize
 where
     V: Send + 'static,
     F: Fn(&Path, Vec<RawEntry>) -> V + Sync,
 {
     let (results_tx, results_rx) = crossbeam_channel::bounded(1024);
     let errors = AtomicUsize::new(0);
-    let collector = std::thread::spawn(move || {
-        let mut results = HashMap::with_capacity(16_384);
-        for (path, entries) in results_rx {
-            results.insert(path, entries);
-        }
-        results
-    });
+    std::thread::scope(|threads| {
+        threads.spawn(move || {
+            for (path, value) in results_rx {
+                sink(path, value);
+            }
+        });
 
-    #[cfg(target_os = "macos")]
-    let mac_context = MacWalkContext {
-        results: &results_tx,
-        skip_check: skip_check.as_ref(),
-        progress_callback: progress_callback.as_deref(),
-        mapper,
-        errors: &errors,
-    };
+        let context = WalkContext {
+            results: &results_tx,
+            metadata_mode,
+            skip_check: skip_check.as_ref(),
+            progress_callback: progress_callback.as_deref(),
+            reuse,
+            mapper,
+            errors: &errors,
+        };
 
-    pool.scope(|scope| {
-        #[cfg(target_os = "macos")]
-        if root == Path::new("/") {
-            match mac::open_directory(&root) {
-                Ok(directory) => walk_recursive_macos(scope, root, directory, &mac_context),
-                Err(_) => {
-                    errors.fetch_add(1, Ordering::Relaxed);
+        pool.scope(|scope| {
+            // The descriptor-based walker reads every directory it opens, so
+            // it only runs when nothing can be reused.
+            #[cfg(target_os = "macos")]
+            if root == Path::new("/") && reuse.is_none() {
+                match mac::open_directory(&root) {
+                    Ok(directory) => walk_recursive_macos(scope, root, directory, &context),
+                    Err(_) => {
+                        errors.fetch_add(1, Ordering::Relaxed);
+                    }
                 }
+            } else {
+                walk_recursive(scope, root, &context);
             }
-        } else {
-            walk_recursive(
-                scope,
-                root,
-                &results_tx,
-                skip_check.as_ref(),
-                progress_callback.as_deref(),
-                mapper,
-                &errors,
-            );
-        }
-        #[cfg(not(target_os = "macos"))]
-        walk_recursive(
-            scope,
-            root,
-            &results_tx,
-            skip_check.as_ref(),
-            progress_callback.as_deref(),
-            mapper,
-            &errors,
-        );
+            #[cfg(not(target_os = "macos"))]
+            walk_recursive(scope, root, &context);
+        });
+        drop(results_tx);
     });
-    drop(results_tx);
-
-    WalkOutput {
-        entries: collector.join().expect("directory collector panicked"),
-        errors: errors.into_inner(),
-    }
+    errors.into_inner()
 }
 
-fn walk_recursive<'scope, V, F>(
-    scope: &rayon::Scope<'scope>,
-    dir: PathBuf,
+/// What every directory of one walk shares.
+struct WalkContext<'scope, V, F> {
     results: &'scope Sender<(PathBuf, V)>,
-    skip_check: &'scope (dyn Fn(&Path) -> bool + Send + Sync),
+    metadata_mode: MetadataMode,
+    skip_check: &'scope SkipCheck,
     progress_callback: Option<&'scope (dyn Fn(usize, usize, u64) + Send + Sync)>,
+    reuse: Option<ReuseListing<'scope>>,
     mapper: &'scope F,
     errors: &'scope AtomicUsize,
+}
+
+fn walk_recursive<'scope, V, F>(
+    scope: &rayon::Scope<'scope>,
+    dir: PathBuf,
+    context: &'scope WalkContext<'scope, V, F>,
 ) where
     V: Send + 'static,
     F: Fn(&Path, Vec<RawEntry>) -> V + Sync,
 {
-    let entries = match read_dir_fast(&dir) {
+    let reused = context.reuse.and_then(|reuse| reuse(&dir));
+    let entries = match reused.map_or_else(|| read_dir(&dir, context.metadata_mode), Ok) {
         Ok(e) => e,
         Err(_) => {
-            errors.fetch_add(1, Ordering::Relaxed);
+            context.errors.fetch_add(1, Ordering::Relaxed);
             return;
         }
     };
 
-    if let Some(ref cb) = progress_callback {
+    if let Some(cb) = context.progress_callback {
         let (dirs, files, bytes) = entries.iter().filter(|entry| !entry.is_symlink).fold(
             (0usize, 0usize, 0u64),
             |(dirs, files, bytes), entry| {
@@ -201,50 +438,33 @@ fn walk_recursive<'scope, V, F>(
     let subdirs: Vec<PathBuf> = entries
         .iter()
         .filter(|e| e.is_dir && !e.is_symlink)
-        .map(|e| dir.join(&e.name))
-        .filter(|p| !skip_check(p))
+        .filter_map(|e| {
+            let path = dir.join(&e.name);
+            (!(context.skip_check)(&path, e)).then_some(path)
+        })
         .collect();
 
-    let mapped_entries = mapper(&dir, entries);
-    if results.send((dir, mapped_entries)).is_err() {
+    let mapped_entries = (context.mapper)(&dir, entries);
+    if context.results.send((dir, mapped_entries)).is_err() {
         return;
     }
 
     for subdir in subdirs {
-        scope.spawn(move |s| {
-            walk_recursive(
-                s,
-                subdir,
-                results,
-                skip_check,
-                progress_callback,
-                mapper,
-                errors,
-            );
-        });
+        scope.spawn(move |s| walk_recursive(s, subdir, context));
     }
 }
 
-#[cfg(target_os = "macos")]
-struct MacWalkContext<'scope, V, F> {
-    results: &'scope Sender<(PathBuf, V)>,
-    skip_check: &'scope (dyn Fn(&Path) -> bool + Send + Sync),
-    progress_callback: Option<&'scope (dyn Fn(usize, usize, u64) + Send + Sync)>,
-    mapper: &'scope F,
-    errors: &'scope AtomicUsize,
-}
-
 #[cfg(target_os = "macos")]
 fn walk_recursive_macos<'scope, V, F>(
     scope: &rayon::Scope<'scope>,
     dir: PathBuf,
     directory: Arc<mac::Directory>,
-    context: &'scope MacWalkContext<'scope, V, F>,
+    context: &'scope WalkContext<'scope, V, F>,
 ) where
     V: Send + 'static,
     F: Fn(&Path, Vec<RawEntry>) -> V + Sync,
 {
-    let entries = match mac::read_open_directory(&directory, MetadataMode::WithSizes) {
+    let entries = match mac::read_open_directory(&directory, context.metadata_mode) {
         Ok(entries) => entries,
         Err(_) => {
             context.errors.fetch_add(1, Ordering::Relaxed);
@@ -271,7 +491,7 @@ fn walk_recursive_macos<'scope, V, F>(
         .filter(|entry| entry.is_dir && !entry.is_symlink)
         .filter_map(|entry| {
             let path = dir.join(&entry.name);
-            (!(context.skip_check)(&path)).then(|| (path, entry.name.clone()))
+            (!(context.skip_check)(&path, entry)).then(|| (path, entry.name.clone()))
         })
         .collect();
 
@@ -123,7 +123,7 @@ fn parallel_walk_honors_skip_and_reports_progress() {
     let tree = walk_parallel(
         temp.path().to_path_buf(),
         &pool,
-        Arc::new(move |path| path == skip),
+        Arc::new(move |path, _| path == skip),
         Some(callback),
     );
     assert!(tree.contains_key(temp.path()));
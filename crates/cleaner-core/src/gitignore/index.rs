@@ -0,0 +1,141 @@
+//! Minimal `.git/index` reader: only the tracked path names are kept.
+//!
+//! Supports index versions 2, 3 and 4 (prefix-compressed paths). Extensions
+//! after the entry table are ignored.
+
+use std::path::Path;
+
+const SIGNATURE: &[u8; 4] = b"DIRC";
+/// ctime, mtime, dev, ino, mode, uid, gid, size, object id, flags.
+const FIXED_ENTRY_LEN: usize = 62;
+const EXTENDED_FLAG: u16 = 0x4000;
+
+/// Repository-relative paths recorded in the index, sorted bytewise.
+#[derive(Debug, Default)]
+pub struct TrackedPaths {
+    paths: Vec<Box<[u8]>>,
+}
+
+impl TrackedPaths {
+    /// Read `index_path`. A missing index (fresh repository) tracks nothing.
+    pub fn read(index_path: &Path) -> Result<Self, String> {
+        match std::fs::read(index_path) {
+            Ok(bytes) => {
+                Self::parse(&bytes).map_err(|error| format!("{}: {error}", index_path.display()))
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(format!("Cannot read {}: {error}", index_path.display())),
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 12 || &bytes[..4] != SIGNATURE {
+            return Err("not a git index".to_string());
+        }
+        let version = read_u32(bytes, 4).unwrap_or(0);
+        if !(2..=4).contains(&version) {
+            return Err(format!("unsupported index version {version}"));
+        }
+        let count = read_u32(bytes, 8).unwrap_or(0) as usize;
+        let truncated = || "truncated index".to_string();
+
+        let mut paths: Vec<Box<[u8]>> = Vec::with_capacity(count.min(1 << 20));
+        let mut previous: Vec<u8> = Vec::new();
+        let mut offset = 12;
+        for _ in 0..count {
+            let start = offset;
+            let flags = read_u16(bytes, start + FIXED_ENTRY_LEN - 2).ok_or_else(truncated)?;
+            offset += FIXED_ENTRY_LEN;
+            if version >= 3 && flags & EXTENDED_FLAG != 0 {
+                offset += 2;
+            }
+
+            let path = if version == 4 {
+                let (strip, used) = read_offset_varint(bytes.get(offset..).ok_or_else(truncated)?)
+                    .ok_or_else(truncated)?;
+                offset += used;
+                let suffix_len = nul_position(bytes, offset).ok_or_else(truncated)?;
+                let keep = previous
+                    .len()
+                    .checked_sub(strip)
+                    .ok_or_else(|| "corrupt path compression".to_string())?;
+                previous.truncate(keep);
+                previous.extend_from_slice(&bytes[offset..offset + suffix_len]);
+                offset += suffix_len + 1;
+                previous.clone()
+            } else {
+                let len = nul_position(bytes, offset).ok_or_else(truncated)?;
+                let path = bytes[offset..offset + len].to_vec();
+                // Entries are NUL-padded to a multiple of eight bytes.
+                let entry_len = offset + len - start;
+                offset = start + (entry_len + 8) / 8 * 8;
+                path
+            };
+            paths.push(path.into_boxed_slice());
+        }
+
+        // Stages of a conflicted path repeat its name.
+        paths.sort_unstable();
+        paths.dedup();
+        Ok(Self { paths })
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// True if `path` is tracked, or (for directories) contains a tracked path.
+    pub fn contains(&self, path: &[u8], is_dir: bool) -> bool {
+        if self
+            .paths
+            .binary_search_by(|tracked| (**tracked).cmp(path))
+            .is_ok()
+        {
+            return true;
+        }
+        if !is_dir {
+            return false;
+        }
+        let mut prefix = Vec::with_capacity(path.len() + 1);
+        prefix.extend_from_slice(path);
+        prefix.push(b'/');
+        let first = self
+            .paths
+            .partition_point(|tracked| **tracked < *prefix.as_slice());
+        self.paths
+            .get(first)
+            .is_some_and(|tracked| tracked.starts_with(&prefix))
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let raw = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(raw.try_into().ok()?))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let raw = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes(raw.try_into().ok()?))
+}
+
+fn nul_position(bytes: &[u8], offset: usize) -> Option<usize> {
+    bytes.get(offset..)?.iter().position(|byte| *byte == 0)
+}
+
+/// Git's "offset" varint (see `decode_varint` in git's varint.c).
+fn read_offset_varint(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut used = 0;
+    let mut byte = *bytes.get(used)?;
+    used += 1;
+    let mut value = usize::from(byte & 0x7f);
+    while byte & 0x80 != 0 {
+        byte = *bytes.get(used)?;
+        used += 1;
+        value = value.checked_add(1)?.checked_mul(128)? | usize::from(byte & 0x7f);
+    }
+    Some((value, used))
+}
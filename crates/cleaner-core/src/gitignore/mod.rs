@@ -0,0 +1,281 @@
+//! Git ignore rules for `--gitignored-only` scans
+//!
+//! The scanner carries a [`GitDir`] down the parallel walk. Each directory's
+//! `.gitignore` is parsed once, when the walk reaches it, and pushed onto a
+//! shared parent chain, so sibling subtrees reuse their ancestors' rules.
+//! Precedence follows git: deeper files win over shallower ones, the last
+//! matching line within a file wins, `.git/info/exclude` sits below every
+//! `.gitignore`, and nothing below an ignored directory can be re-included.
+//!
+//! Tracked paths come from the repository's index ([`index`]); a candidate
+//! that is tracked, or contains a tracked path, is never ignored.
+
+pub mod index;
+
+use crate::fastwalk::RawEntry;
+use crate::patterns::glob;
+use index::TrackedPaths;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One repository: its work tree root and tracked paths.
+pub struct Repository {
+    root: PathBuf,
+    /// `None` when the index exists but could not be parsed; every path is
+    /// then treated as tracked so nothing in the repository is reported.
+    tracked: Option<TrackedPaths>,
+}
+
+impl Repository {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+struct Rule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+    /// Patterns with a slash match the path relative to the ignore file;
+    /// the rest match the name at any depth.
+    anchored: bool,
+}
+
+/// The rules of one ignore file, linked to the files above it.
+struct RuleFile {
+    /// Directory of the file relative to the repository root, with a
+    /// trailing `/` (empty at the root).
+    base: String,
+    rules: Vec<Rule>,
+    parent: Option<Arc<RuleFile>>,
+}
+
+/// Ignore state of one directory during a walk.
+#[derive(Clone)]
+pub struct GitDir {
+    repo: Arc<Repository>,
+    rules: Option<Arc<RuleFile>>,
+    /// Path relative to the repository root, with a trailing `/` (empty at
+    /// the root).
+    relative: String,
+    /// The directory itself is ignored, so everything below it is too.
+    ignored: bool,
+}
+
+impl GitDir {
+    /// State for `dir`, or `None` outside any git work tree. Ignore files
+    /// between the repository root and `dir` are loaded; `dir`'s own
+    /// `.gitignore` is left to [`GitDir::enter`].
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let root = dir
+            .ancestors()
+            .find(|candidate| candidate.join(".git").exists())?;
+        let mut state = Self::open(root);
+        let Ok(rest) = dir.strip_prefix(root) else {
+            return Some(state);
+        };
+        let mut current = root.to_path_buf();
+        for component in rest.components() {
+            let name = component.as_os_str().to_string_lossy();
+            state.load_gitignore(&current);
+            state = state.child(&name);
+            current.push(component);
+        }
+        Some(state)
+    }
+
+    /// State for the root of the work tree at `root`.
+    pub fn open(root: &Path) -> Self {
+        let git_dir = resolve_git_dir(root);
+        let tracked = TrackedPaths::read(&git_dir.join("index")).ok();
+        let exclude = std::fs::read_to_string(git_dir.join("info").join("exclude"))
+            .map(|content| parse_rules(&content))
+            .unwrap_or_default();
+        Self {
+            repo: Arc::new(Repository {
+                root: root.to_path_buf(),
+                tracked,
+            }),
+            rules: (!exclude.is_empty()).then(|| {
+                Arc::new(RuleFile {
+                    base: String::new(),
+                    rules: exclude,
+                    parent: None,
+                })
+            }),
+            relative: String::new(),
+            ignored: false,
+        }
+    }
+
+    /// Update the state for `dir` once its listing is known: a `.git` entry
+    /// starts a nested repository (or submodule), and a `.gitignore` entry
+    /// adds its rules.
+    pub fn enter(state: Option<Self>, dir: &Path, entries: &[RawEntry]) -> Option<Self> {
+        let has = |name: &str| entries.iter().any(|entry| entry.name == name);
+        let mut state = match state {
+            Some(state) if state.repo.root == dir || !has(".git") => state,
+            _ if has(".git") => Self::open(dir),
+            _ => return None,
+        };
+        if has(".gitignore") {
+            state.load_gitignore(dir);
+        }
+        Some(state)
+    }
+
+    pub fn repository(&self) -> &Repository {
+        &self.repo
+    }
+
+    /// True if `name` in this directory is ignored by git.
+    pub fn is_ignored(&self, name: &str, is_dir: bool) -> bool {
+        if self.ignored {
+            return true;
+        }
+        let path = format!("{}{name}", self.relative);
+        let mut file = self.rules.as_deref();
+        while let Some(rules) = file {
+            if let Some(relative) = path.strip_prefix(rules.base.as_str()) {
+                let hit = rules.rules.iter().rev().find(|rule| {
+                    (!rule.dir_only || is_dir)
+                        && rule
+                            .regex
+                            .is_match(if rule.anchored { relative } else { name })
+                });
+                if let Some(rule) = hit {
+                    return !rule.negated;
+                }
+            }
+            file = rules.parent.as_deref();
+        }
+        false
+    }
+
+    /// True if `name` in this directory is tracked or contains tracked paths.
+    pub fn is_tracked(&self, name: &str, is_dir: bool) -> bool {
+        let Some(tracked) = &self.repo.tracked else {
+            return true;
+        };
+        let path = format!("{}{name}", self.relative);
+        tracked.contains(path.as_bytes(), is_dir)
+    }
+
+    /// True if `name` should be reported in a `--gitignored-only` scan.
+    pub fn allows(&self, name: &str, is_dir: bool) -> bool {
+        self.is_ignored(name, is_dir) && !self.is_tracked(name, is_dir)
+    }
+
+    /// State for the subdirectory `name`.
+    pub fn child(&self, name: &str) -> Self {
+        Self {
+            repo: Arc::clone(&self.repo),
+            rules: self.rules.clone(),
+            relative: format!("{}{name}/", self.relative),
+            ignored: self.is_ignored(name, true),
+        }
+    }
+
+    fn load_gitignore(&mut self, dir: &Path) {
+        let Ok(content) = std::fs::read_to_string(dir.join(".gitignore")) else {
+            return;
+        };
+        let rules = parse_rules(&content);
+        if rules.is_empty() {
+            return;
+        }
+        self.rules = Some(Arc::new(RuleFile {
+            base: self.relative.clone(),
+            rules,
+            parent: self.rules.take(),
+        }));
+    }
+}
+
+/// The git directory of the work tree at `root`, following the `gitdir:`
+/// file used by worktrees and submodules.
+fn resolve_git_dir(root: &Path) -> PathBuf {
+    let dot_git = root.join(".git");
+    if dot_git.is_file() {
+        if let Some(target) = std::fs::read_to_string(&dot_git)
+            .ok()
+            .as_deref()
+            .and_then(|content| content.trim().strip_prefix("gitdir:"))
+        {
+            return root.join(target.trim());
+        }
+    }
+    dot_git
+}
+
+/// Parse one ignore file. Lines that do not form a valid pattern are
+/// skipped, as git does, and a line's trailing `\r` is dropped, so files
+/// saved with CRLF endings match too.
+fn parse_rules(content: &str) -> Vec<Rule> {
+    content
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter_map(parse_rule)
+        .collect()
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let mut pattern = trim_unescaped_spaces(line);
+    let negated = pattern.starts_with('!');
+    if negated {
+        pattern = &pattern[1..];
+    }
+    let dir_only = pattern.ends_with('/') && !pattern.ends_with("\\/");
+    if dir_only {
+        pattern = &pattern[..pattern.len() - 1];
+    }
+    if pattern.is_empty() {
+        return None;
+    }
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    let regex = Regex::new(&glob::to_regex(&escape_braces(pattern), true).ok()?).ok()?;
+    Some(Rule {
+        regex,
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+fn trim_unescaped_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while line[..end].ends_with(' ') && !line[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Git's wildmatch has no `{a,b}` alternation; keep braces literal.
+fn escape_braces(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len() + 4);
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                out.push(ch);
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            }
+            '{' | '}' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests;
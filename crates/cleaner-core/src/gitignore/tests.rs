@@ -0,0 +1,161 @@
+use super::*;
+use crate::fastwalk::read_dir_types;
+use crate::test_support::TempDir;
+
+fn index_v2(paths: &[&str]) -> Vec<u8> {
+    let mut out = b"DIRC".to_vec();
+    out.extend(2u32.to_be_bytes());
+    out.extend((paths.len() as u32).to_be_bytes());
+    for path in paths {
+        let start = out.len();
+        out.extend([0u8; 60]);
+        out.extend((path.len() as u16).to_be_bytes());
+        out.extend(path.as_bytes());
+        let padded = (out.len() - start + 8) / 8 * 8;
+        out.resize(start + padded, 0);
+    }
+    out.extend([0u8; 20]);
+    out
+}
+
+fn repo(temp: &TempDir, tracked: &[&str]) {
+    temp.write(".git/index", &index_v2(tracked));
+}
+
+fn state_for(temp: &TempDir, relative: &str) -> GitDir {
+    let dir = temp.join(relative);
+    let entries = read_dir_types(&dir).unwrap();
+    GitDir::enter(GitDir::discover(&dir), &dir, &entries).unwrap()
+}
+
+#[test]
+fn parses_index_versions_two_and_four() {
+    let tracked = TrackedPaths::parse(&index_v2(&["b/file.rs", "a.txt", "b/c/d"])).unwrap();
+    assert_eq!(tracked.len(), 3);
+    assert!(tracked.contains(b"a.txt", false));
+    assert!(tracked.contains(b"b", true));
+    assert!(tracked.contains(b"b/c", true));
+    assert!(!tracked.contains(b"b", false));
+    assert!(!tracked.contains(b"a", true));
+
+    // Version 4 stores each path as "drop N bytes of the previous one, then
+    // append this suffix".
+    let mut v4 = b"DIRC".to_vec();
+    v4.extend(4u32.to_be_bytes());
+    v4.extend(2u32.to_be_bytes());
+    for (strip, suffix) in [(0u8, "src/lib.rs"), (6, "main.rs")] {
+        v4.extend([0u8; 60]);
+        v4.extend((suffix.len() as u16).to_be_bytes());
+        v4.push(strip);
+        v4.extend(suffix.as_bytes());
+        v4.push(0);
+    }
+    let tracked = TrackedPaths::parse(&v4).unwrap();
+    assert!(tracked.contains(b"src/main.rs", false));
+    assert!(tracked.contains(b"src/lib.rs", false));
+
+    assert!(TrackedPaths::parse(b"not an index").is_err());
+    assert!(TrackedPaths::parse(&index_v2(&["a"])[..40]).is_err());
+}
+
+#[test]
+fn gitignore_rules_follow_git_precedence() {
+    let temp = TempDir::new("gitignore-rules");
+    repo(&temp, &[]);
+    temp.write(
+        ".gitignore",
+        b"# build outputs\n/dist\nbuild/\n*.log\n!keep.log\nout/**/cache  \n",
+    );
+    temp.write("pkg/.gitignore", b"!debug.log\n");
+    temp.mkdir("pkg/dist");
+    temp.write("build", b"a file, not a directory");
+
+    let root = state_for(&temp, "");
+    assert!(root.is_ignored("dist", true));
+    assert!(root.is_ignored("build", true));
+    assert!(!root.is_ignored("build", false));
+    assert!(root.is_ignored("server.log", false));
+    assert!(!root.is_ignored("keep.log", false));
+    assert!(root
+        .child("out")
+        .child("a")
+        .child("b")
+        .is_ignored("cache", true));
+
+    let pkg = state_for(&temp, "pkg");
+    // `/dist` is anchored at the root directory.
+    assert!(!pkg.is_ignored("dist", true));
+    assert!(pkg.is_ignored("trace.log", false));
+    assert!(!pkg.is_ignored("debug.log", false));
+}
+
+#[test]
+fn crlf_ignore_files_match_like_lf_ones() {
+    let temp = TempDir::new("gitignore-crlf");
+    repo(&temp, &[]);
+    temp.write(
+        ".gitignore",
+        b"# outputs\r\nbuild/\r\n*.log  \r\n!keep.log\r\ndist\r",
+    );
+    let root = state_for(&temp, "");
+    assert!(root.is_ignored("build", true));
+    assert!(root.is_ignored("server.log", false));
+    assert!(!root.is_ignored("keep.log", false));
+    assert!(root.is_ignored("dist", true));
+}
+
+#[test]
+fn nothing_below_an_ignored_directory_can_be_re_included() {
+    let temp = TempDir::new("gitignore-parent");
+    repo(&temp, &[]);
+    temp.write(".gitignore", b"vendor/\n!vendor/keep\n");
+    temp.write(".git/info/exclude", b"scratch\n");
+    temp.mkdir("vendor");
+    let vendor = state_for(&temp, "vendor");
+    assert!(vendor.is_ignored("keep", false));
+    assert!(state_for(&temp, "").is_ignored("scratch", true));
+}
+
+#[test]
+fn tracked_paths_are_never_allowed() {
+    let temp = TempDir::new("gitignore-tracked");
+    repo(&temp, &["dist/checked-in.js", "notes.log"]);
+    temp.write(".gitignore", b"dist/\nbuild/\n*.log\n");
+    let root = state_for(&temp, "");
+    assert!(!root.allows("dist", true));
+    assert!(!root.allows("notes.log", false));
+    assert!(root.allows("build", true));
+    assert!(root.allows("other.log", false));
+    assert!(!root.allows("src", true));
+}
+
+#[test]
+fn nested_repositories_and_plain_directories() {
+    let temp = TempDir::new("gitignore-nested");
+    repo(&temp, &[]);
+    temp.write(".gitignore", b"*.tmp\n");
+    temp.write("sub/.git", b"gitdir: ../.git/modules/sub\n");
+    temp.write(".git/modules/sub/info/exclude", b"target\n");
+
+    let sub = state_for(&temp, "sub");
+    assert_eq!(sub.repository().root(), temp.join("sub"));
+    assert!(sub.is_ignored("target", true));
+    assert!(!sub.is_ignored("a.tmp", false));
+
+    let outside = TempDir::new("gitignore-outside");
+    outside.mkdir("plain");
+    let entries = read_dir_types(outside.path()).unwrap();
+    // The temp directory may itself sit inside a checkout; only assert when
+    // it does not.
+    if GitDir::discover(outside.path()).is_none() {
+        assert!(GitDir::enter(None, outside.path(), &entries).is_none());
+    }
+}
+
+#[test]
+fn unreadable_index_protects_the_whole_repository() {
+    let temp = TempDir::new("gitignore-bad-index");
+    temp.write(".git/index", b"DIRC\0\0\0\x09");
+    temp.write(".gitignore", b"build/\n");
+    assert!(!state_for(&temp, "").allows("build", true));
+}
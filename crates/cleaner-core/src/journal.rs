@@ -0,0 +1,222 @@
+//! Append-only audit journal in JSON Lines
+//!
+//! Every item a deleter handles becomes one line, dry runs included:
+//!
+//! ```json
+//! {"timestamp":"2026-01-31T12:00:00Z","path":"/src/app/target","type":"dir","bytes":1024,"rule":"target","uid":1000,"dry_run":false,"disposal":"delete","source":"scan","error":null}
+//! ```
+//!
+//! `type` is `dir`, `file` or `command` (a Deep Clean tool run, whose `path`
+//! is the program). `rule` is the config pattern or Deep Clean target that
+//! selected the item, or `null` for a manual delete. `uid` is the user that
+//! ran cleaner (`null` where there is none). `error` is `null` on success.
+//!
+//! Lines from one batch are written with a single `write`, and the file is
+//! opened in append mode, so concurrent cleaner processes never interleave
+//! within a line.
+
+use crate::config::Config;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One journal line, before the timestamp and uid are filled in.
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    pub path: &'a Path,
+    /// `dir`, `file` or `command`.
+    pub kind: &'static str,
+    pub bytes: u64,
+    pub rule: Option<&'a str>,
+    pub dry_run: bool,
+    /// `delete`, `trash` or `quarantine`.
+    pub disposal: &'a str,
+    /// What did the deleting: `scan`, `tui-delete`, `tui-clean` or
+    /// `deep-clean`.
+    pub source: &'a str,
+    pub error: Option<&'a str>,
+}
+
+/// An open journal file.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// Open `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|error| format!("Cannot open journal {}: {error}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// The journal `config` asks for, if any.
+    pub fn for_config(config: &Config) -> Result<Option<Arc<Self>>, String> {
+        config
+            .journal
+            .as_deref()
+            .map(|path| Self::open(path).map(Arc::new))
+            .transpose()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `records`, stamped with the current time and user.
+    pub fn write<'a>(&self, records: impl IntoIterator<Item = Record<'a>>) -> Result<(), String> {
+        let timestamp = utc_timestamp(SystemTime::now());
+        let uid = current_uid().map_or_else(|| "null".to_string(), |uid| uid.to_string());
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&format!(
+                "{{\"timestamp\":\"{timestamp}\",\"path\":{},\"type\":\"{}\",\"bytes\":{},\"rule\":{},\"uid\":{uid},\"dry_run\":{},\"disposal\":{},\"source\":{},\"error\":{}}}\n",
+                json_string(&record.path.to_string_lossy()),
+                record.kind,
+                record.bytes,
+                record.rule.map_or_else(|| "null".to_string(), json_string),
+                record.dry_run,
+                json_string(record.disposal),
+                json_string(record.source),
+                record.error.map_or_else(|| "null".to_string(), json_string),
+            ));
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| format!("Journal {} is poisoned", self.path.display()))?;
+        file.write_all(lines.as_bytes())
+            .map_err(|error| format!("Cannot write journal {}: {error}", self.path.display()))
+    }
+}
+
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    Some(crate::trash::current_uid())
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+/// `value` as a quoted JSON string.
+fn json_string(value: &str) -> String {
+    format!("\"{}\"", json_escape(value))
+}
+
+/// `text` as the inside of a JSON string: quotes, backslashes and control
+/// characters escaped. The `--json` reports use it too, so they and the
+/// journal escape alike.
+pub fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `time` as RFC 3339 UTC, to the second.
+fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    // Howard Hinnant's days-to-civil algorithm, for days since 1970-01-01.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_are_rfc3339_utc() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400 + 3_723);
+        assert_eq!(utc_timestamp(leap_day), "2000-02-29T01:02:03Z");
+        let later = UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+        assert_eq!(utc_timestamp(later), "2026-09-21T14:13:20Z");
+    }
+
+    #[test]
+    fn records_append_one_escaped_line_each() {
+        let temp = TempDir::new("journal");
+        let path = temp.join("audit.jsonl");
+        temp.write("audit.jsonl", b"{\"existing\":true}\n");
+        let journal = Journal::open(&path).unwrap();
+        let record = Record {
+            path: Path::new("/src/\"odd\"\tname"),
+            kind: "file",
+            bytes: 12,
+            rule: Some(".pyc"),
+            dry_run: true,
+            disposal: "delete",
+            source: "scan",
+            error: None,
+        };
+        journal
+            .write([
+                record,
+                Record {
+                    kind: "dir",
+                    rule: None,
+                    error: Some("Permission denied"),
+                    ..record
+                },
+            ])
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "{\"existing\":true}");
+        assert!(lines[1].starts_with("{\"timestamp\":\""));
+        assert!(lines[1].contains(
+            "\"path\":\"/src/\\\"odd\\\"\\tname\",\"type\":\"file\",\"bytes\":12,\"rule\":\".pyc\""
+        ));
+        assert!(lines[1].contains(
+            "\"dry_run\":true,\"disposal\":\"delete\",\"source\":\"scan\",\"error\":null}"
+        ));
+        assert!(lines[2].contains("\"rule\":null"));
+        assert!(lines[2].ends_with("\"error\":\"Permission denied\"}"));
+        assert!(Journal::open(&temp.join("missing/audit.jsonl")).is_err());
+    }
+}
@@ -1,26 +1,37 @@
 //! Cleaner core library: high-performance filesystem scanning, directory tree sizing,
 //! pattern matching, and concurrent artifact deletion engine.
 
+pub mod age;
 pub mod config;
 pub mod deleter;
 pub mod disk_usage;
 pub mod fastwalk;
+pub mod gitignore;
+pub mod journal;
 pub mod patterns;
 pub mod pool;
 pub mod protected;
+pub mod quarantine;
+pub mod remove;
 pub mod scanner;
 pub mod stats;
 pub mod sysclean;
 #[doc(hidden)]
 pub mod test_support;
+pub mod trash;
 pub mod tree;
+pub mod users;
+pub mod watch;
 
+pub use age::AgePolicy;
 pub use config::Config;
 pub use deleter::Deleter;
-pub use disk_usage::get_disk_usage;
+pub use disk_usage::{get_disk_usage, get_inode_usage};
 pub use patterns::PatternMatcher;
-pub use protected::{is_protected_for_root, protected_paths_for_root};
+pub use protected::{is_protected_for_root, protected_paths_for_root, ProtectedPaths};
 pub use scanner::{ScanResult, ScanSummary, Scanner};
 pub use stats::Stats;
 pub use sysclean::{Candidate, Group, Target, Tier};
 pub use tree::{DirEntry, DirTree, ScanProgress};
+pub use users::UserNames;
+pub use watch::Watcher;
@@ -0,0 +1,208 @@
+//! Shell glob translation for configured patterns.
+//!
+//! Globs are compiled to regular expressions so every pattern in a config can
+//! share one [`regex::RegexSet`]. Supported syntax: `*`, `?`, `[...]` (with
+//! `!` or `^` negation and `a-z` ranges), `{a,b}` alternation (nestable), and
+//! `\` to escape the next character.
+//!
+//! In path mode `*` and `?` stop at `/`, and `**` spans whole components, which
+//! is what exclude rules and ignore files need. Name patterns never see a `/`,
+//! so the distinction does not matter for them.
+
+/// Prefix that marks a pattern as a regular expression rather than a glob.
+pub const REGEX_PREFIX: &str = "re:";
+
+/// True when `pattern` uses any glob syntax.
+pub fn has_glob_syntax(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{', '\\'])
+}
+
+/// Escape `literal` so it matches only itself when used inside a glob.
+pub fn escape(literal: &str) -> String {
+    let mut out = String::with_capacity(literal.len());
+    for ch in literal.chars() {
+        if matches!(ch, '*' | '?' | '[' | ']' | '{' | '}' | ',' | '\\') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Translate a glob into an anchored regular expression.
+pub fn to_regex(glob: &str, path_mode: bool) -> Result<String, String> {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut out = String::with_capacity(glob.len() * 2 + 8);
+    out.push_str("^(?s:");
+    let mut depth = 0usize;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        match ch {
+            '*' if path_mode && chars.get(index + 1) == Some(&'*') => {
+                let at_start = index == 0 || chars[index - 1] == '/';
+                if at_start && chars.get(index + 2) == Some(&'/') {
+                    // `**/` matches zero or more leading components.
+                    out.push_str("(?:.*/)?");
+                    index += 3;
+                } else {
+                    out.push_str(".*");
+                    index += 2;
+                }
+                continue;
+            }
+            '*' => out.push_str(if path_mode { "[^/]*" } else { ".*" }),
+            '?' => out.push_str(if path_mode { "[^/]" } else { "." }),
+            '[' => {
+                index = push_class(&chars, index, &mut out, glob)?;
+                continue;
+            }
+            '{' => {
+                depth += 1;
+                out.push_str("(?:");
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                out.push(')');
+            }
+            ',' if depth > 0 => out.push('|'),
+            '\\' => {
+                let Some(&escaped) = chars.get(index + 1) else {
+                    return Err(format!("pattern '{glob}' ends with a lone backslash"));
+                };
+                push_literal(escaped, &mut out);
+                index += 2;
+                continue;
+            }
+            _ => push_literal(ch, &mut out),
+        }
+        index += 1;
+    }
+
+    if depth > 0 {
+        return Err(format!("pattern '{glob}' has an unclosed '{{'"));
+    }
+    out.push_str(")$");
+    Ok(out)
+}
+
+/// Translate a path glob into a regular expression that matches the path
+/// itself and everything below it.
+pub fn to_subtree_regex(glob: &str) -> Result<String, String> {
+    let expression = to_regex(glob.trim_end_matches('/'), true)?;
+    let body = expression.strip_suffix('$').unwrap_or(&expression);
+    Ok(format!("{body}(?:/.*)?$"))
+}
+
+/// Translate `[...]` starting at `start`; returns the index after `]`.
+fn push_class(chars: &[char], start: usize, out: &mut String, glob: &str) -> Result<usize, String> {
+    let mut index = start + 1;
+    out.push('[');
+    if matches!(chars.get(index), Some('!') | Some('^')) {
+        out.push('^');
+        index += 1;
+    }
+    let first = index;
+    loop {
+        let Some(&ch) = chars.get(index) else {
+            return Err(format!("pattern '{glob}' has an unclosed '['"));
+        };
+        // A `]` straight after the opening bracket is a literal, as in sh.
+        if ch == ']' && index > first {
+            out.push(']');
+            return Ok(index + 1);
+        }
+        let is_range =
+            ch == '-' && index > first && chars.get(index + 1).is_some_and(|c| *c != ']');
+        if is_range {
+            out.push('-');
+        } else {
+            push_literal(ch, out);
+        }
+        index += 1;
+    }
+}
+
+fn push_literal(ch: char, out: &mut String) {
+    let mut buffer = [0u8; 4];
+    out.push_str(&regex::escape(ch.encode_utf8(&mut buffer)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    fn name_matches(glob: &str, name: &str) -> bool {
+        Regex::new(&to_regex(glob, false).unwrap())
+            .unwrap()
+            .is_match(name)
+    }
+
+    fn path_matches(glob: &str, path: &str) -> bool {
+        Regex::new(&to_regex(glob, true).unwrap())
+            .unwrap()
+            .is_match(path)
+    }
+
+    #[test]
+    fn wildcards_classes_and_alternation() {
+        assert!(name_matches("cmake-build-*", "cmake-build-debug"));
+        assert!(!name_matches("cmake-build-*", "cmake-build"));
+        assert!(name_matches("*.log.[0-9]", "app.log.3"));
+        assert!(!name_matches("*.log.[0-9]", "app.log.x"));
+        assert!(name_matches("*.log.[!0-9]", "app.log.x"));
+        assert!(name_matches("file?.txt", "file1.txt"));
+        assert!(!name_matches("file?.txt", "file10.txt"));
+        assert!(name_matches("*.{o,obj}", "main.obj"));
+        assert!(name_matches("{a,b{c,d}}", "bd"));
+        assert!(!name_matches("*.{o,obj}", "main.orig"));
+    }
+
+    #[test]
+    fn regex_metacharacters_are_literal() {
+        assert!(name_matches("a.b", "a.b"));
+        assert!(!name_matches("a.b", "axb"));
+        assert!(name_matches("(x)+", "(x)+"));
+        assert!(name_matches(r"literal\*", "literal*"));
+        assert!(!name_matches(r"literal\*", "literally"));
+        assert!(name_matches("[]]", "]"));
+        assert!(name_matches("[a-]", "-"));
+    }
+
+    #[test]
+    fn path_mode_keeps_single_stars_inside_a_component() {
+        assert!(path_matches("/src/*/build", "/src/app/build"));
+        assert!(!path_matches("/src/*/build", "/src/a/b/build"));
+        assert!(path_matches("/src/**/build", "/src/a/b/build"));
+        assert!(path_matches("/src/**/build", "/src/build"));
+        assert!(path_matches("**/vendor", "/deep/tree/vendor"));
+        assert!(path_matches("/src/**", "/src/a/b"));
+    }
+
+    #[test]
+    fn subtree_regex_covers_descendants_only() {
+        let regex = Regex::new(&to_subtree_regex("/src/*/build/").unwrap()).unwrap();
+        assert!(regex.is_match("/src/app/build"));
+        assert!(regex.is_match("/src/app/build/out/x.o"));
+        assert!(!regex.is_match("/src/app/builder"));
+        assert!(!regex.is_match("/src/app"));
+    }
+
+    #[test]
+    fn malformed_globs_are_rejected() {
+        assert!(to_regex("[abc", false).is_err());
+        assert!(to_regex("{a,b", false).is_err());
+        assert!(to_regex("trailing\\", false).is_err());
+        assert!(has_glob_syntax("bazel-*"));
+        assert!(!has_glob_syntax("node_modules"));
+    }
+
+    #[test]
+    fn escaped_literals_match_only_themselves() {
+        let literal = "/home/a[1]/{x,y}*";
+        assert!(path_matches(&escape(literal), literal));
+        assert!(!path_matches(&escape(literal), "/home/a1/x"));
+    }
+}
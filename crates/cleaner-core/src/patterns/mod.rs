@@ -0,0 +1,658 @@
+//! Pattern matching for temporary files and folders
+//!
+//! A pattern is one of:
+//!
+//! - an exact name (`node_modules`); for file patterns a plain string is a
+//!   suffix instead (`.pyc`, `~`), which is what existing configs rely on;
+//! - a shell glob (`cmake-build-*`, `*.log.[0-9]`, `*.{o,obj}`), see [`glob`];
+//! - a regular expression prefixed with `re:` (`re:^bazel-.+$`), matched
+//!   against the whole name.
+//!
+//! Exact names and plain suffixes stay on a hash/`ends_with` fast path; every
+//! glob and regex in a list is compiled into a single [`RegexSet`], once per
+//! [`Config`]. Whichever path a pattern takes, the first one in config order
+//! that matches a name is the rule reported for it.
+//!
+//! A directory pattern can also carry marker rules ([`Config::markers`]): it
+//! then only matches when one of the marker names exists beside the
+//! directory. Walkers already hold the sibling listing, so they pass it to
+//! [`PatternMatcher::directory_rule_in`] instead of touching the disk again.
+//!
+//! Exclude rules ([`Config::exclude`]) are path globs rather than names; an
+//! excluded path and everything below it is never cleaned.
+
+pub mod glob;
+pub mod project;
+
+use crate::config::Config;
+use crate::fastwalk::RawEntry;
+use foldhash::{HashMap, HashMapExt};
+use regex::RegexSet;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Pattern matcher with configurable patterns
+pub struct PatternMatcher {
+    config: Arc<Config>,
+    directories: RuleSet,
+    /// Marker names per directory pattern index; `None` matches anywhere.
+    markers: Vec<Option<RuleSet>>,
+    files: RuleSet,
+    excludes: Option<RegexSet>,
+}
+
+/// One compiled pattern list. Every lookup returns the index of the pattern
+/// that matched so callers can report the rule, not just a yes/no.
+struct RuleSet {
+    exact: HashMap<Box<str>, usize>,
+    suffixes: Vec<(Box<str>, usize)>,
+    set: Option<RegexSet>,
+    set_rules: Vec<usize>,
+}
+
+impl RuleSet {
+    /// `plain_is_suffix` selects the legacy meaning of a pattern without any
+    /// glob syntax: exact for directories, `ends_with` for files.
+    fn compile(patterns: &[String], plain_is_suffix: bool) -> (Self, Vec<String>) {
+        let mut exact = HashMap::with_capacity(patterns.len());
+        let mut suffixes = Vec::new();
+        let mut expressions = Vec::new();
+        let mut set_rules = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, pattern) in patterns.iter().enumerate() {
+            if let Some(expression) = pattern.strip_prefix(glob::REGEX_PREFIX) {
+                expressions.push(format!("^(?:{expression})$"));
+                set_rules.push(index);
+            } else if !glob::has_glob_syntax(pattern) {
+                if plain_is_suffix {
+                    suffixes.push((pattern.as_str().into(), index));
+                } else {
+                    exact.entry(pattern.as_str().into()).or_insert(index);
+                }
+            } else if let Some(suffix) = pattern
+                .strip_prefix('*')
+                .filter(|rest| !glob::has_glob_syntax(rest))
+            {
+                // `*.egg-info` is by far the most common glob; keep it off the
+                // regex engine.
+                suffixes.push((suffix.into(), index));
+            } else {
+                match glob::to_regex(pattern, false) {
+                    Ok(expression) => {
+                        expressions.push(expression);
+                        set_rules.push(index);
+                    }
+                    Err(error) => errors.push(error),
+                }
+            }
+        }
+
+        let set = if expressions.is_empty() {
+            None
+        } else {
+            match RegexSet::new(&expressions) {
+                Ok(set) => Some(set),
+                // Only now compile the patterns one by one, to name the
+                // broken ones and keep the rest.
+                Err(error) => {
+                    let mut valid = Vec::with_capacity(expressions.len());
+                    let mut valid_rules = Vec::with_capacity(expressions.len());
+                    for (expression, index) in expressions.into_iter().zip(set_rules) {
+                        match regex::Regex::new(&expression) {
+                            Ok(_) => {
+                                valid.push(expression);
+                                valid_rules.push(index);
+                            }
+                            Err(error) => {
+                                errors.push(format!("invalid regex '{}': {error}", patterns[index]))
+                            }
+                        }
+                    }
+                    set_rules = valid_rules;
+                    match RegexSet::new(&valid) {
+                        Ok(set) if !valid.is_empty() => Some(set),
+                        Ok(_) => None,
+                        Err(_) => {
+                            errors.push(format!("cannot compile patterns: {error}"));
+                            None
+                        }
+                    }
+                }
+            }
+        };
+
+        (
+            Self {
+                exact,
+                suffixes,
+                set,
+                set_rules,
+            },
+            errors,
+        )
+    }
+
+    /// The first pattern in config order that matches `name`.
+    #[inline]
+    fn find(&self, name: &str) -> Option<usize> {
+        let mut found = self.exact.get(name).copied();
+        // Suffixes and set rules are each kept in config order, so only
+        // those ahead of a match found so far need looking at.
+        for (suffix, index) in &self.suffixes {
+            if found.is_some_and(|found| found < *index) {
+                break;
+            }
+            if name.ends_with(&**suffix) {
+                found = Some(*index);
+                break;
+            }
+        }
+        let earlier = |index: usize| found.is_none_or(|found| index < found);
+        if let Some(set) = self.set.as_ref() {
+            if self.set_rules.first().is_some_and(|&first| earlier(first)) {
+                let matched = set
+                    .matches(name)
+                    .iter()
+                    .next()
+                    .map(|slot| self.set_rules[slot]);
+                if let Some(index) = matched.filter(|&index| earlier(index)) {
+                    found = Some(index);
+                }
+            }
+        }
+        found
+    }
+
+    /// Every pattern that matches `name`, in config order.
+    fn matching(&self, name: &str) -> Vec<usize> {
+        let mut matched: Vec<usize> = self.exact.get(name).copied().into_iter().collect();
+        matched.extend(
+            self.suffixes
+                .iter()
+                .filter(|(suffix, _)| name.ends_with(&**suffix))
+                .map(|&(_, index)| index),
+        );
+        if let Some(set) = &self.set {
+            matched.extend(set.matches(name).iter().map(|slot| self.set_rules[slot]));
+        }
+        matched.sort_unstable();
+        matched
+    }
+}
+
+impl PatternMatcher {
+    /// Compile `config`'s patterns. Invalid patterns are skipped; use
+    /// [`PatternMatcher::try_new`] (or [`Config::try_load`]) to report them.
+    pub fn new(config: Arc<Config>) -> Self {
+        Self::compile(config).0
+    }
+
+    /// Compile `config`'s patterns, failing on the first invalid one.
+    pub fn try_new(config: Arc<Config>) -> Result<Self, String> {
+        let (matcher, errors) = Self::compile(config);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(matcher),
+        }
+    }
+
+    fn compile(config: Arc<Config>) -> (Self, Vec<String>) {
+        let (directories, mut errors) = RuleSet::compile(&config.directories, false);
+        let (files, file_errors) = RuleSet::compile(&config.files, true);
+        errors.extend(file_errors);
+        let markers = config
+            .directories
+            .iter()
+            .map(|directory| {
+                let names = config.markers_for(directory)?;
+                let (rules, marker_errors) = RuleSet::compile(names, false);
+                errors.extend(marker_errors);
+                Some(rules)
+            })
+            .collect();
+        let excludes = compile_excludes(&config.exclude, &mut errors);
+        (
+            Self {
+                config,
+                directories,
+                markers,
+                files,
+                excludes,
+            },
+            errors,
+        )
+    }
+
+    /// Check if a directory name matches any temp directory pattern.
+    ///
+    /// Marker rules are not consulted; walkers that have the sibling listing
+    /// should use [`PatternMatcher::is_temp_directory_in`].
+    #[inline]
+    pub fn is_temp_directory(&self, name: impl AsRef<OsStr>) -> bool {
+        self.directory_rule(name).is_some()
+    }
+
+    /// Like [`PatternMatcher::is_temp_directory`], but also requires the
+    /// pattern's marker (if it has one) among `siblings`.
+    #[inline]
+    pub fn is_temp_directory_in(&self, name: impl AsRef<OsStr>, siblings: &[RawEntry]) -> bool {
+        self.directory_rule_in(name, siblings).is_some()
+    }
+
+    /// Check if a file name matches any temp file pattern
+    #[inline]
+    pub fn is_temp_file(&self, name: impl AsRef<OsStr>) -> bool {
+        self.file_rule(name).is_some()
+    }
+
+    /// The directory pattern that matches `name`, as written in the config.
+    #[inline]
+    pub fn directory_rule(&self, name: impl AsRef<OsStr>) -> Option<&str> {
+        let name = name.as_ref().to_str()?;
+        self.directories
+            .find(name)
+            .map(|index| self.config.directories[index].as_str())
+    }
+
+    /// The directory pattern that matches `name` inside a directory listing
+    /// `siblings`, honouring marker rules.
+    #[inline]
+    pub fn directory_rule_in(
+        &self,
+        name: impl AsRef<OsStr>,
+        siblings: &[RawEntry],
+    ) -> Option<&str> {
+        let name = name.as_ref().to_str()?;
+        let marked = |index: usize| {
+            self.markers[index].as_ref().is_none_or(|markers| {
+                siblings.iter().any(|sibling| {
+                    sibling
+                        .name
+                        .to_str()
+                        .is_some_and(|sibling| markers.find(sibling).is_some())
+                })
+            })
+        };
+        let first = self.directories.find(name)?;
+        // A later pattern can still match when the first one's markers are
+        // missing; only then are all the matching patterns gathered.
+        let index = if marked(first) {
+            first
+        } else {
+            self.directories
+                .matching(name)
+                .into_iter()
+                .find(|&index| index > first && marked(index))?
+        };
+        Some(self.config.directories[index].as_str())
+    }
+
+    /// The file pattern that matches `name`, as written in the config.
+    #[inline]
+    pub fn file_rule(&self, name: impl AsRef<OsStr>) -> Option<&str> {
+        let name = name.as_ref().to_str()?;
+        self.files
+            .find(name)
+            .map(|index| self.config.files[index].as_str())
+    }
+
+    /// True if any exclude rule covers `path` (or one of its ancestors).
+    #[inline]
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.excludes
+            .as_ref()
+            .is_some_and(|set| set.is_match(&path.to_string_lossy()))
+    }
+
+    /// True if the config has any exclude rule; lets walkers skip building
+    /// paths they would only need for [`PatternMatcher::is_excluded`].
+    #[inline]
+    pub fn has_excludes(&self) -> bool {
+        self.excludes.is_some()
+    }
+
+    /// Check if path component matches any temp pattern
+    #[inline]
+    #[allow(dead_code)]
+    pub fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            if is_dir {
+                self.is_temp_directory(OsStr::new(name))
+            } else {
+                self.is_temp_file(OsStr::new(name))
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Get directory patterns for display
+    #[allow(dead_code)]
+    pub fn directory_patterns(&self) -> &[String] {
+        &self.config.directories
+    }
+
+    /// Get file patterns for display
+    #[allow(dead_code)]
+    pub fn file_patterns(&self) -> &[String] {
+        &self.config.files
+    }
+
+    pub fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.config)
+    }
+}
+
+/// Compile exclude globs. `~/` expands to the home directory, and a rule
+/// that is not absolute matches at any depth, with a leading `./` dropped.
+fn compile_excludes(patterns: &[String], errors: &mut Vec<String>) -> Option<RegexSet> {
+    let home = dirs::home_dir();
+    let mut expressions = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        let pattern = match (pattern.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => format!("{}/{rest}", home.display()),
+            _ if pattern.starts_with('/') => pattern.clone(),
+            _ => format!("**/{}", pattern.trim_start_matches("./")),
+        };
+        match glob::to_subtree_regex(&pattern) {
+            Ok(expression) => expressions.push(expression),
+            Err(error) => errors.push(format!("exclude: {error}")),
+        }
+    }
+    if expressions.is_empty() {
+        return None;
+    }
+    RegexSet::new(&expressions)
+        .map_err(|error| errors.push(format!("cannot compile excludes: {error}")))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MarkerRule;
+
+    fn test_config() -> Arc<Config> {
+        Arc::new(Config {
+            directories: vec![
+                ".terraform".to_string(),
+                "target".to_string(),
+                "node_modules".to_string(),
+                "__pycache__".to_string(),
+                "*.egg-info".to_string(),
+            ],
+            files: vec![".DS_Store".to_string(), ".pyc".to_string(), "~".to_string()],
+            ..Config::empty()
+        })
+    }
+
+    #[test]
+    fn test_temp_directories() {
+        let matcher = PatternMatcher::new(test_config());
+        assert!(matcher.is_temp_directory(".terraform"));
+        assert!(matcher.is_temp_directory("target"));
+        assert!(matcher.is_temp_directory("node_modules"));
+        assert!(matcher.is_temp_directory("__pycache__"));
+        assert!(!matcher.is_temp_directory("src"));
+        assert!(!matcher.is_temp_directory("lib"));
+    }
+
+    #[test]
+    fn test_temp_files() {
+        let matcher = PatternMatcher::new(test_config());
+        assert!(matcher.is_temp_file(".DS_Store"));
+        assert!(matcher.is_temp_file("test.pyc"));
+        assert!(matcher.is_temp_file("backup~"));
+        assert!(!matcher.is_temp_file("main.rs"));
+    }
+
+    #[test]
+    fn test_egg_info() {
+        let matcher = PatternMatcher::new(test_config());
+        assert!(matcher.is_temp_directory("mypackage.egg-info"));
+        assert!(!matcher.is_temp_directory("egg-info.mypackage"));
+    }
+
+    #[test]
+    fn matches_paths_and_exposes_configured_patterns() {
+        let matcher = PatternMatcher::new(test_config());
+        assert!(matcher.matches(Path::new("some/target"), true));
+        assert!(matcher.matches(Path::new("some/module.pyc"), false));
+        assert!(!matcher.matches(Path::new("some/main.rs"), false));
+        assert_eq!(matcher.directory_patterns()[0], ".terraform");
+        assert_eq!(matcher.file_patterns()[0], ".DS_Store");
+    }
+
+    #[test]
+    fn globs_and_regexes_match_whole_names() {
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            directories: vec![
+                "cmake-build-*".to_string(),
+                "bazel-*".to_string(),
+                "re:^out-[0-9]+$".to_string(),
+            ],
+            files: vec!["*.log.[0-9]".to_string(), "*.{o,obj}".to_string()],
+            ..Config::empty()
+        }));
+        assert_eq!(
+            matcher.directory_rule("cmake-build-release"),
+            Some("cmake-build-*")
+        );
+        assert!(matcher.is_temp_directory("bazel-bin"));
+        assert_eq!(matcher.directory_rule("out-42"), Some("re:^out-[0-9]+$"));
+        assert!(!matcher.is_temp_directory("out-42x"));
+        assert!(!matcher.is_temp_directory("my-cmake-build-x"));
+        assert_eq!(matcher.file_rule("server.log.1"), Some("*.log.[0-9]"));
+        assert!(!matcher.is_temp_file("server.log"));
+        assert!(matcher.is_temp_file("main.obj"));
+        assert!(!matcher.is_temp_file("main.objc"));
+    }
+
+    #[test]
+    fn plain_patterns_keep_their_legacy_meaning() {
+        let matcher = PatternMatcher::new(test_config());
+        // Directory names are exact, file names are suffixes.
+        assert!(!matcher.is_temp_directory("my-target"));
+        assert!(matcher.is_temp_file("notes.DS_Store"));
+        assert_eq!(matcher.file_rule("backup~"), Some("~"));
+        assert_eq!(matcher.directory_rule("x.egg-info"), Some("*.egg-info"));
+    }
+
+    #[test]
+    fn the_first_matching_pattern_in_config_order_wins() {
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            directories: vec![
+                "re:^bazel-.+$".to_string(),
+                "*-out".to_string(),
+                "bazel-out".to_string(),
+            ],
+            files: vec!["*.log.[0-9]".to_string(), ".1".to_string()],
+            ..Config::empty()
+        }));
+        assert_eq!(matcher.directory_rule("bazel-out"), Some("re:^bazel-.+$"));
+        assert_eq!(matcher.directory_rule("cmake-out"), Some("*-out"));
+        assert_eq!(matcher.file_rule("app.log.1"), Some("*.log.[0-9]"));
+        assert_eq!(matcher.file_rule("notes.1"), Some(".1"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_by_try_new_and_skipped_by_new() {
+        let config = Arc::new(Config {
+            directories: vec![
+                "re:(".to_string(),
+                "re:^bazel-.+$".to_string(),
+                "target".to_string(),
+            ],
+            files: vec!["[abc".to_string()],
+            ..Config::empty()
+        });
+        assert!(PatternMatcher::try_new(Arc::clone(&config))
+            .err()
+            .unwrap()
+            .contains("invalid regex"));
+        let matcher = PatternMatcher::new(config);
+        assert!(matcher.is_temp_directory("target"));
+        assert_eq!(matcher.directory_rule("bazel-bin"), Some("re:^bazel-.+$"));
+        assert!(!matcher.is_temp_file("a"));
+    }
+
+    fn listing(names: &[&str]) -> Vec<RawEntry> {
+        names
+            .iter()
+            .map(|name| RawEntry {
+                name: name.into(),
+                size: 0,
+                apparent_size: 0,
+                is_dir: false,
+                is_symlink: false,
+                modified: 0,
+                accessed: 0,
+                uid: None,
+                hard_link: None,
+                device: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn marker_rules_require_a_matching_sibling() {
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            directories: vec!["target".to_string(), "build".to_string(), "out".to_string()],
+            markers: vec![
+                MarkerRule::new("target", &["Cargo.toml"]),
+                MarkerRule::new("build", &["CMakeLists.txt", "build.gradle*"]),
+            ],
+            ..Config::empty()
+        }));
+        assert!(matcher.is_temp_directory_in("target", &listing(&["Cargo.toml", "src"])));
+        assert!(!matcher.is_temp_directory_in("target", &listing(&["target", "scripts"])));
+        assert!(matcher.is_temp_directory_in("build", &listing(&["build.gradle.kts"])));
+        assert!(!matcher.is_temp_directory_in("build", &listing(&["Makefile"])));
+        assert_eq!(matcher.directory_rule_in("out", &[]), Some("out"));
+        // The listing-free check keeps matching on the name alone.
+        assert!(matcher.is_temp_directory("build"));
+    }
+
+    #[test]
+    fn a_later_pattern_matches_when_the_first_ones_markers_are_missing() {
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            directories: vec![
+                "build".to_string(),
+                "bui*".to_string(),
+                "re:^b.*d$".to_string(),
+            ],
+            markers: vec![
+                MarkerRule::new("build", &["CMakeLists.txt"]),
+                MarkerRule::new("bui*", &["package.json"]),
+            ],
+            ..Config::empty()
+        }));
+        let rule = |siblings: &[&str]| matcher.directory_rule_in("build", &listing(siblings));
+        assert_eq!(rule(&["CMakeLists.txt", "package.json"]), Some("build"));
+        assert_eq!(rule(&["package.json"]), Some("bui*"));
+        assert_eq!(rule(&["Makefile"]), Some("re:^b.*d$"));
+        assert_eq!(matcher.directory_rule_in("builds", &[]), None);
+    }
+
+    #[test]
+    fn exclude_rules_cover_whole_subtrees() {
+        let home = dirs::home_dir().unwrap_or_default();
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            exclude: vec![
+                "/src/legacy-app/build".to_string(),
+                "~/keep".to_string(),
+                "vendor/*/target".to_string(),
+                "./fixtures".to_string(),
+            ],
+            ..Config::empty()
+        }));
+        assert!(matcher.has_excludes());
+        assert!(matcher.is_excluded(Path::new("/src/legacy-app/build")));
+        assert!(matcher.is_excluded(Path::new("/src/legacy-app/build/x.pyc")));
+        assert!(!matcher.is_excluded(Path::new("/src/legacy-app/builds")));
+        assert!(!matcher.is_excluded(Path::new("/src/other/build")));
+        assert!(matcher.is_excluded(&home.join("keep/node_modules")));
+        assert!(matcher.is_excluded(Path::new("/a/b/vendor/dep/target")));
+        assert!(!matcher.is_excluded(Path::new("/a/vendor/dep/x/target")));
+        assert!(matcher.is_excluded(Path::new("/a/fixtures/x.pyc")));
+        assert!(!PatternMatcher::new(test_config()).has_excludes());
+
+        let invalid = Config {
+            exclude: vec!["/a/[b".to_string()],
+            ..Config::empty()
+        };
+        assert!(PatternMatcher::try_new(Arc::new(invalid))
+            .err()
+            .unwrap()
+            .starts_with("exclude:"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path_does_not_match() {
+        use std::os::unix::ffi::OsStrExt;
+        let matcher = PatternMatcher::new(test_config());
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"\xff"));
+        assert!(!matcher.matches(path, false));
+    }
+
+    #[test]
+    #[ignore = "manual release microbenchmark"]
+    fn manual_profile_pattern_lookup() {
+        use foldhash::HashSet;
+        use std::collections::HashSet as StdHashSet;
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        let config = test_config();
+        let matcher = PatternMatcher::new(Arc::clone(&config));
+        let names = ["src", "target", "module.pyc", "package.egg-info"];
+        let iterations = 1_000_000;
+
+        let start = Instant::now();
+        for index in 0..iterations {
+            black_box(matcher.is_temp_directory(names[index % names.len()]));
+        }
+        let linear = start.elapsed();
+
+        let std_exact: StdHashSet<&str> = config
+            .directories
+            .iter()
+            .filter(|pattern| !pattern.starts_with('*'))
+            .map(String::as_str)
+            .collect();
+        let fold_exact: HashSet<&str> = config
+            .directories
+            .iter()
+            .filter(|pattern| !pattern.starts_with('*'))
+            .map(String::as_str)
+            .collect();
+        let suffixes: Vec<_> = config
+            .directories
+            .iter()
+            .filter_map(|pattern| pattern.strip_prefix('*'))
+            .collect();
+
+        let start = Instant::now();
+        for index in 0..iterations {
+            let name = names[index % names.len()];
+            black_box(
+                std_exact.contains(name) || suffixes.iter().any(|suffix| name.ends_with(suffix)),
+            );
+        }
+        let std_hash = start.elapsed();
+
+        let start = Instant::now();
+        for index in 0..iterations {
+            let name = names[index % names.len()];
+            black_box(
+                fold_exact.contains(name) || suffixes.iter().any(|suffix| name.ends_with(suffix)),
+            );
+        }
+        let fold_hash = start.elapsed();
+
+        println!("pattern lookup: linear={linear:?} std_hash={std_hash:?} foldhash={fold_hash:?}");
+    }
+}
@@ -0,0 +1,139 @@
+//! Per-project rules: a [`PROJECT_CONFIG`] layers its patterns and excludes
+//! over the ones inherited from above, for its own directory and everything
+//! below it.
+//!
+//! The scanner hands each directory's matcher down to its subdirectories.
+//! Tree walks map listings on many threads without a parent to ask, so they
+//! look the nearest project up in [`ProjectMatchers`] instead.
+
+use super::PatternMatcher;
+use crate::config::PROJECT_CONFIG;
+use crate::fastwalk::RawEntry;
+use foldhash::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// `matcher` with `dir`'s project config layered on top. A broken file
+/// counts as an error and leaves the inherited rules in place.
+pub fn enter(
+    matcher: Arc<PatternMatcher>,
+    dir: &Path,
+    errors: &AtomicUsize,
+) -> Arc<PatternMatcher> {
+    match layered(&matcher, dir) {
+        Some(project) => Arc::new(project),
+        None => {
+            errors.fetch_add(1, Ordering::Relaxed);
+            matcher
+        }
+    }
+}
+
+/// The matcher for a walk from `root`: project files in the root's
+/// ancestors apply to it just as they would had the walk started higher.
+pub fn for_root(
+    matcher: Arc<PatternMatcher>,
+    root: &Path,
+    errors: &AtomicUsize,
+) -> Arc<PatternMatcher> {
+    projects_above(root)
+        .into_iter()
+        .fold(matcher, |matcher, dir| enter(matcher, dir, errors))
+}
+
+/// The matchers of the projects met so far in a tree walk. Lookups rely on
+/// every directory being mapped before its subdirectories, which
+/// [`crate::fastwalk::walk_parallel_each`] guarantees.
+pub(crate) struct ProjectMatchers<'a> {
+    base: &'a PatternMatcher,
+    root: PathBuf,
+    /// `base` with the projects above `root` layered on, if there are any.
+    top: Option<Arc<PatternMatcher>>,
+    /// The matcher of each project directory below `root`, and of `root`.
+    found: Mutex<HashMap<PathBuf, Arc<PatternMatcher>>>,
+    errors: &'a AtomicUsize,
+}
+
+impl<'a> ProjectMatchers<'a> {
+    pub(crate) fn new(base: &'a PatternMatcher, root: &Path, errors: &'a AtomicUsize) -> Self {
+        let mut top: Option<Arc<PatternMatcher>> = None;
+        for dir in projects_above(root) {
+            let inherited = top.as_deref().unwrap_or(base);
+            match layered(inherited, dir) {
+                Some(project) => top = Some(Arc::new(project)),
+                None => {
+                    errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        Self {
+            base,
+            root: root.to_path_buf(),
+            top,
+            found: Mutex::new(HashMap::default()),
+            errors,
+        }
+    }
+
+    /// The matcher for `dir`, whose listing is `entries`, or `None` where
+    /// the base matcher applies.
+    pub(crate) fn matcher_in(
+        &self,
+        dir: &Path,
+        entries: &[RawEntry],
+    ) -> Option<Arc<PatternMatcher>> {
+        let inherited = self.inherited(dir);
+        if !entries.iter().any(|entry| entry.name == PROJECT_CONFIG) {
+            return inherited;
+        }
+        let project = match layered(inherited.as_deref().unwrap_or(self.base), dir) {
+            Some(project) => Arc::new(project),
+            None => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+                return inherited;
+            }
+        };
+        lock(&self.found).insert(dir.to_path_buf(), Arc::clone(&project));
+        Some(project)
+    }
+
+    /// The matcher of the nearest project above `dir`.
+    fn inherited(&self, dir: &Path) -> Option<Arc<PatternMatcher>> {
+        let found = lock(&self.found);
+        if found.is_empty() {
+            return self.top.clone();
+        }
+        dir.ancestors()
+            .skip(1)
+            .take_while(|ancestor| ancestor.starts_with(&self.root))
+            .find_map(|ancestor| found.get(ancestor))
+            .or(self.top.as_ref())
+            .cloned()
+    }
+}
+
+/// `matcher` with `dir`'s project config layered on top, or `None` if the
+/// file cannot be read or is invalid.
+fn layered(matcher: &PatternMatcher, dir: &Path) -> Option<PatternMatcher> {
+    let config = matcher.config().with_project_file(dir).ok()?;
+    PatternMatcher::try_new(Arc::new(config)).ok()
+}
+
+/// The directories above `root` holding a [`PROJECT_CONFIG`], outermost
+/// first.
+fn projects_above(root: &Path) -> Vec<&Path> {
+    let mut dirs: Vec<&Path> = root
+        .ancestors()
+        .skip(1)
+        .filter(|dir| dir.join(PROJECT_CONFIG).is_file())
+        .collect();
+    dirs.reverse();
+    dirs
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
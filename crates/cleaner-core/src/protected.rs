@@ -1,7 +1,76 @@
 //! Protected path helpers shared by scanner and analyze tree.
 
+use crate::config::ProtectRules;
 use std::path::{Path, PathBuf};
 
+/// The protected directories for one scan: the built-in list merged with the
+/// user's `[protect]` rules.
+///
+/// A directory is protected when the most specific rule covering it says so,
+/// so `remove = ["/opt/ci"]` opens `/opt/ci` while the rest of `/opt` stays
+/// protected, and an `add` deeper inside a removed path closes it again.
+/// Files follow the directory that contains them.
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedPaths {
+    /// `(path, protected)`, later entries win ties.
+    rules: Vec<(PathBuf, bool)>,
+}
+
+impl ProtectedPaths {
+    /// Built-ins as [`protected_paths_for_root`] returns them, then
+    /// `rules.remove`, then `rules.add`. `force` drops the built-ins but keeps
+    /// the user's own additions.
+    pub fn new(root: &Path, force: bool, rules: &ProtectRules) -> Self {
+        let builtin = protected_paths_for_root(root, force)
+            .into_iter()
+            .map(|path| (path, true));
+        let removed = rules.remove.iter().map(|path| (expand_home(path), false));
+        let added = rules.add.iter().map(|path| (expand_home(path), true));
+        Self {
+            rules: builtin.chain(removed).chain(added).collect(),
+        }
+    }
+
+    /// True if `path` is protected.
+    pub fn contains(&self, path: &Path) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+        for (rule, protected) in &self.rules {
+            if path.starts_with(rule) {
+                let depth = rule.components().count();
+                if best.is_none_or(|(best_depth, _)| depth >= best_depth) {
+                    best = Some((depth, *protected));
+                }
+            }
+        }
+        best.is_some_and(|(_, protected)| protected)
+    }
+
+    /// Paths that are protected, in rule order.
+    pub fn protected(&self) -> impl Iterator<Item = &Path> {
+        self.rules
+            .iter()
+            .filter(|(_, protected)| *protected)
+            .map(|(path, _)| path.as_path())
+    }
+
+    /// Paths carved out of a protected directory by `[protect] remove`.
+    pub fn unprotected(&self) -> impl Iterator<Item = &Path> {
+        self.rules
+            .iter()
+            .filter(|(_, protected)| !*protected)
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+/// Expand a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => dirs::home_dir().unwrap_or_else(|| PathBuf::from(path)),
+        _ => PathBuf::from(path),
+    }
+}
+
 /// Build the protected directory list for a scan rooted at `root`.
 ///
 /// When `force` is true the list is empty. Paths that contain `root` are
@@ -97,3 +166,64 @@ pub fn is_protected_for_root(root: &Path, path: &Path) -> bool {
         .iter()
         .any(|protected| path.starts_with(protected))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(add: &[&str], remove: &[&str]) -> ProtectRules {
+        ProtectRules {
+            add: add.iter().map(|path| path.to_string()).collect(),
+            remove: remove.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn most_specific_rule_wins() {
+        let protected = ProtectedPaths::new(
+            Path::new("/"),
+            false,
+            &rules(&["/srv/data", "/opt/ci/keep"], &["/opt/ci"]),
+        );
+        assert!(protected.contains(Path::new("/opt/tool")));
+        assert!(!protected.contains(Path::new("/opt/ci")));
+        assert!(!protected.contains(Path::new("/opt/ci/cache/target")));
+        assert!(protected.contains(Path::new("/opt/ci/keep/target")));
+        assert!(protected.contains(Path::new("/srv/data/build")));
+        assert!(!protected.contains(Path::new("/srv/www")));
+        assert_eq!(
+            protected.unprotected().collect::<Vec<_>>(),
+            [Path::new("/opt/ci")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn force_keeps_only_user_additions() {
+        let protected = ProtectedPaths::new(Path::new("/"), true, &rules(&["/srv/data"], &[]));
+        assert!(!protected.contains(Path::new("/usr/lib")));
+        assert!(protected.contains(Path::new("/srv/data/x")));
+        assert_eq!(protected.protected().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn user_additions_apply_even_when_they_contain_the_root() {
+        let protected =
+            ProtectedPaths::new(Path::new("/opt/project"), false, &rules(&["/srv"], &[]));
+        // The built-in `/opt` contains the root and is dropped.
+        assert!(!protected.contains(Path::new("/opt/project/target")));
+        let inside = ProtectedPaths::new(Path::new("/srv/app"), false, &rules(&["/srv"], &[]));
+        assert!(inside.contains(Path::new("/srv/app/target")));
+    }
+
+    #[test]
+    fn home_prefix_expands() {
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_home("~/src"), home.join("src"));
+            assert_eq!(expand_home("~"), home);
+        }
+        assert_eq!(expand_home("relative/~"), PathBuf::from("relative/~"));
+    }
+}
@@ -0,0 +1,406 @@
+//! Quarantine: cleaner's own undo area for live runs
+//!
+//! A quarantined item is renamed, never copied, so it stays on its own
+//! filesystem. Items on the same filesystem as the store
+//! (`$XDG_DATA_HOME/cleaner/quarantine`) go to its `items/` directory; items
+//! on another filesystem go to `$topdir/.cleaner-quarantine-$uid` at the top
+//! of their mount. Either way the manifest lives in the store, one TOML file
+//! per item under `manifest/`, so parallel workers and concurrent runs never
+//! contend for a shared file.
+//!
+//! [`Quarantine::restore`] renames items back; [`Quarantine::expired`] and
+//! [`Quarantine::forget`] let a purge delete them for good, and
+//! [`Quarantine::expire`] does both for the retention set in the config.
+
+use crate::age::seconds;
+use crate::remove::remove_tree;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// One quarantined item, as recorded in its manifest file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Manifest file stem; what `cleaner restore <id>` takes.
+    #[serde(skip)]
+    pub id: String,
+    /// Where the item was, and where a restore puts it back.
+    pub original: PathBuf,
+    /// Where the item is now.
+    pub stored: PathBuf,
+    pub is_dir: bool,
+    /// Bytes the item (and everything below it) took up.
+    pub size: u64,
+    /// Modification time of the item when it was quarantined, in seconds
+    /// since the epoch.
+    pub mtime: i64,
+    /// When the item was quarantined, in seconds since the epoch.
+    pub quarantined: i64,
+}
+
+impl Entry {
+    /// [`Entry::stored`] with symlinks in its parent directories resolved:
+    /// [`remove_tree`] refuses to pass through one.
+    pub fn stored_resolved(&self) -> PathBuf {
+        match (self.stored.parent(), self.stored.file_name()) {
+            (Some(parent), Some(name)) => parent
+                .canonicalize()
+                .map_or_else(|_| self.stored.clone(), |parent| parent.join(name)),
+            _ => self.stored.clone(),
+        }
+    }
+}
+
+/// A quarantine store.
+#[derive(Debug, Clone)]
+pub struct Quarantine {
+    root: PathBuf,
+}
+
+impl Quarantine {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// The current user's store: `$XDG_DATA_HOME/cleaner/quarantine`, or
+    /// `~/.local/share/cleaner/quarantine`.
+    pub fn from_env() -> Result<Self, String> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+            .ok_or_else(|| {
+                "Cannot locate the quarantine: neither XDG_DATA_HOME nor HOME is set".to_string()
+            })?;
+        Ok(Self::new(data_home.join("cleaner").join("quarantine")))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn manifest_dir(&self) -> PathBuf {
+        self.root.join("manifest")
+    }
+
+    /// Move `path`, which takes up `size` bytes, into quarantine.
+    pub fn put(&self, path: &Path, size: u64) -> Result<Entry, String> {
+        let fail = |reason: &dyn std::fmt::Display| {
+            format!("Cannot quarantine {}: {reason}", path.display())
+        };
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(fail(&"not a removable path"));
+        };
+        let parent = parent
+            .canonicalize()
+            .or_else(|_| std::path::absolute(parent))
+            .map_err(|error| fail(&error))?;
+        let original = parent.join(name);
+        let metadata = fs::symlink_metadata(&original).map_err(|error| fail(&error))?;
+        let items = self
+            .items_dir_for(&original)
+            .map_err(|error| fail(&error))?;
+        let manifests = self.manifest_dir();
+        fs::create_dir_all(&manifests).map_err(|error| fail(&error))?;
+
+        let now = seconds(SystemTime::now());
+        let (id, mut manifest) = loop {
+            let id = format!("{now}-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+            // Creating the manifest exclusively reserves the id, also
+            // against other cleaner processes.
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(manifests.join(format!("{id}.toml")))
+            {
+                Ok(file) => break (id, file),
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(fail(&error)),
+            }
+        };
+        let manifest_path = manifests.join(format!("{id}.toml"));
+        let entry = Entry {
+            stored: items.join(&id),
+            id,
+            original,
+            is_dir: metadata.is_dir(),
+            size,
+            mtime: metadata.modified().map(seconds).unwrap_or(0),
+            quarantined: now,
+        };
+
+        let written = toml::to_string(&entry)
+            .map_err(|error| error.to_string())
+            .and_then(|record| {
+                manifest
+                    .write_all(record.as_bytes())
+                    .map_err(|error| error.to_string())
+            })
+            .and_then(|()| {
+                fs::rename(&entry.original, &entry.stored).map_err(|error| error.to_string())
+            });
+        match written {
+            Ok(()) => Ok(entry),
+            Err(error) => {
+                let _ = fs::remove_file(&manifest_path);
+                Err(fail(&error))
+            }
+        }
+    }
+
+    /// The directory items on `path`'s filesystem are renamed into.
+    #[cfg(unix)]
+    fn items_dir_for(&self, path: &Path) -> Result<PathBuf, String> {
+        use crate::trash::{current_uid, mount_top, nearest_device, private_dir};
+        use std::os::unix::fs::MetadataExt;
+
+        let device = fs::symlink_metadata(path)
+            .map_err(|error| error.to_string())?
+            .dev();
+        let dir = if nearest_device(&self.root) == Some(device) {
+            self.root.join("items")
+        } else {
+            let topdir = mount_top(path, device);
+            if topdir == path {
+                return Err("it is a mount point".to_string());
+            }
+            topdir.join(format!(".cleaner-quarantine-{}", current_uid()))
+        };
+        private_dir(&dir)?;
+        Ok(dir)
+    }
+
+    #[cfg(not(unix))]
+    fn items_dir_for(&self, _path: &Path) -> Result<PathBuf, String> {
+        let dir = self.root.join("items");
+        fs::create_dir_all(&dir).map_err(|error| error.to_string())?;
+        Ok(dir)
+    }
+
+    /// Every quarantined item, oldest first. Unreadable manifests are
+    /// skipped.
+    pub fn entries(&self) -> Result<Vec<Entry>, String> {
+        let dir = self.manifest_dir();
+        let listing = match fs::read_dir(&dir) {
+            Ok(listing) => listing,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(format!("Cannot read {}: {error}", dir.display())),
+        };
+        let mut entries: Vec<Entry> = listing
+            .filter_map(Result::ok)
+            .filter_map(|file| {
+                let path = file.path();
+                let id = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_suffix(".toml")?
+                    .to_string();
+                let content = fs::read_to_string(&path).ok()?;
+                let entry: Entry = toml::from_str(&content).ok()?;
+                Some(Entry { id, ..entry })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (a.quarantined, id_sequence(&a.id)).cmp(&(b.quarantined, id_sequence(&b.id)))
+        });
+        Ok(entries)
+    }
+
+    /// Entries matching `target`: the entry with that id, or everything
+    /// quarantined from the path `target` or from below it. Newest first,
+    /// so restoring a path that was quarantined twice brings back the most
+    /// recent copy.
+    pub fn find(&self, target: &str) -> Result<Vec<Entry>, String> {
+        let entries = self.entries()?;
+        if let Some(entry) = entries.iter().find(|entry| entry.id == target) {
+            return Ok(vec![entry.clone()]);
+        }
+        let path = Path::new(target);
+        let path = path
+            .canonicalize()
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf());
+        let mut found: Vec<Entry> = entries
+            .into_iter()
+            .filter(|entry| entry.original.starts_with(&path))
+            .collect();
+        found.reverse();
+        Ok(found)
+    }
+
+    /// Rename `entry` back to its original path and drop its manifest.
+    pub fn restore(&self, entry: &Entry) -> Result<(), String> {
+        let fail = |reason: &dyn std::fmt::Display| {
+            format!("Cannot restore {}: {reason}", entry.original.display())
+        };
+        if fs::symlink_metadata(&entry.original).is_ok() {
+            return Err(fail(&"something already exists there"));
+        }
+        if let Some(parent) = entry.original.parent() {
+            fs::create_dir_all(parent).map_err(|error| fail(&error))?;
+        }
+        fs::rename(&entry.stored, &entry.original).map_err(|error| fail(&error))?;
+        self.remove_manifest(entry)
+    }
+
+    /// Entries quarantined at least `max_age_secs` seconds ago.
+    pub fn expired(&self, max_age_secs: u64) -> Result<Vec<Entry>, String> {
+        let max_age = i64::try_from(max_age_secs).unwrap_or(i64::MAX);
+        let cutoff = seconds(SystemTime::now()).saturating_sub(max_age);
+        let mut entries = self.entries()?;
+        entries.retain(|entry| entry.quarantined <= cutoff);
+        Ok(entries)
+    }
+
+    /// Drop `entry`'s manifest once its stored item is gone. Returns
+    /// whether it was dropped.
+    pub fn forget(&self, entry: &Entry) -> Result<bool, String> {
+        if fs::symlink_metadata(&entry.stored).is_ok() {
+            return Ok(false);
+        }
+        self.remove_manifest(entry)?;
+        Ok(true)
+    }
+
+    /// Delete the items quarantined at least `max_age_secs` seconds ago
+    /// and forget them. Returns how many went and the bytes they took up;
+    /// an error names the first item that stayed.
+    pub fn expire(&self, max_age_secs: u64) -> Result<(usize, u64), String> {
+        let mut purged = (0usize, 0u64);
+        let mut errors = Vec::new();
+        for entry in self.expired(max_age_secs)? {
+            let stored = entry.stored_resolved();
+            let removed = match fs::symlink_metadata(&stored) {
+                Ok(metadata) if metadata.is_dir() => {
+                    remove_tree(&stored, false, None).into_result()
+                }
+                Ok(_) => fs::remove_file(&stored)
+                    .map_err(|error| format!("Error deleting {}: {error}", stored.display())),
+                // Already gone; only the manifest is left.
+                Err(_) => Ok(()),
+            };
+            match removed.and_then(|()| self.forget(&entry)) {
+                Ok(true) => {
+                    purged.0 += 1;
+                    purged.1 = purged.1.saturating_add(entry.size);
+                }
+                Ok(false) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+        match errors.len() {
+            0 => Ok(purged),
+            1 => Err(errors.swap_remove(0)),
+            more => Err(format!("{} (and {} more)", errors[0], more - 1)),
+        }
+    }
+
+    fn remove_manifest(&self, entry: &Entry) -> Result<(), String> {
+        let path = self.manifest_dir().join(format!("{}.toml", entry.id));
+        fs::remove_file(&path).map_err(|error| format!("Cannot remove {}: {error}", path.display()))
+    }
+}
+
+/// The per-process counter part of an id, so items quarantined in the same
+/// second keep their order.
+fn id_sequence(id: &str) -> u64 {
+    id.rsplit('-')
+        .next()
+        .and_then(|sequence| sequence.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn put_records_a_manifest_and_restore_undoes_it() {
+        let temp = TempDir::new("quarantine-roundtrip");
+        let store = Quarantine::new(temp.join("store"));
+        temp.write("proj/target/debug/app", b"binary");
+        let target = temp.path().canonicalize().unwrap().join("proj/target");
+
+        let entry = store.put(&temp.join("proj/target"), 6).unwrap();
+        assert!(!target.exists());
+        assert!(entry.stored.join("debug/app").is_file());
+        assert_eq!(entry.original, target);
+        assert!(entry.is_dir);
+        assert_eq!(entry.size, 6);
+        assert!(entry.mtime > 0 && entry.quarantined >= entry.mtime);
+        assert_eq!(store.entries().unwrap(), vec![entry.clone()]);
+
+        assert_eq!(store.find(&entry.id).unwrap(), vec![entry.clone()]);
+        store.restore(&entry).unwrap();
+        assert_eq!(fs::read(target.join("debug/app")).unwrap(), b"binary");
+        assert!(store.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn paths_find_everything_below_them_newest_first() {
+        let temp = TempDir::new("quarantine-find");
+        let store = Quarantine::new(temp.join("store"));
+        temp.write("proj/a/cache.pyc", b"1");
+        temp.write("proj/b/cache.pyc", b"2");
+        temp.write("other/cache.pyc", b"3");
+        for path in ["proj/a/cache.pyc", "proj/b/cache.pyc", "other/cache.pyc"] {
+            store.put(&temp.join(path), 1).unwrap();
+        }
+        temp.write("proj/a/cache.pyc", b"4");
+        let newest = store.put(&temp.join("proj/a/cache.pyc"), 1).unwrap();
+
+        let found = store.find(temp.join("proj").to_str().unwrap()).unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0], newest);
+        let again = store
+            .find(temp.join("proj/a/cache.pyc").to_str().unwrap())
+            .unwrap();
+        store.restore(&again[0]).unwrap();
+        assert!(store
+            .restore(&again[1])
+            .unwrap_err()
+            .contains("already exists"));
+        assert_eq!(fs::read(temp.join("proj/a/cache.pyc")).unwrap(), b"4");
+        assert!(store.find("no-such-id").unwrap().is_empty());
+    }
+
+    #[test]
+    fn expired_entries_are_forgotten_once_deleted() {
+        let temp = TempDir::new("quarantine-expire");
+        let store = Quarantine::new(temp.join("store"));
+        temp.write("a.log", b"1");
+        let entry = store.put(&temp.join("a.log"), 1).unwrap();
+        assert!(store.expired(3600).unwrap().is_empty());
+        assert_eq!(store.expired(0).unwrap(), vec![entry.clone()]);
+
+        assert!(!store.forget(&entry).unwrap());
+        fs::remove_file(&entry.stored).unwrap();
+        assert!(store.forget(&entry).unwrap());
+        assert!(store.entries().unwrap().is_empty());
+        assert!(store.put(&temp.join("missing"), 0).is_err());
+        assert!(store.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn expire_deletes_only_items_past_the_retention() {
+        let temp = TempDir::new("quarantine-retention");
+        let store = Quarantine::new(temp.join("store"));
+        temp.write("proj/target/debug/app", b"binary");
+        temp.write("a.log", b"12");
+        let dir = store.put(&temp.join("proj/target"), 6).unwrap();
+        let file = store.put(&temp.join("a.log"), 2).unwrap();
+
+        assert_eq!(store.expire(3600).unwrap(), (0, 0));
+        assert_eq!(store.entries().unwrap().len(), 2);
+
+        assert_eq!(store.expire(0).unwrap(), (2, 8));
+        assert!(!dir.stored.exists() && !file.stored.exists());
+        assert!(store.entries().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,477 @@
+//! Recursive directory removal that cannot be redirected mid-flight
+//!
+//! Removing a tree by joining path strings re-resolves every ancestor for
+//! every child, so a directory swapped for a symlink after the scan would
+//! send the deletion wherever the link points. On Linux and FreeBSD the tree
+//! is instead walked through open directory descriptors: each child is opened
+//! with `openat(O_NOFOLLOW | O_DIRECTORY)` relative to its already-open
+//! parent and removed with `unlinkat`. The parent of the root is reached the
+//! same way, one component at a time from `/`, so a symlinked ancestor is
+//! refused too. A directory that is no longer a directory, that sits on a
+//! different device from the directory it was found in, or whose device and
+//! inode differ from the ones the scan recorded, is refused rather than
+//! entered. Other platforms fall back to path-based removal.
+
+use crate::fastwalk::HardLink;
+use std::path::Path;
+
+/// What [`remove_tree`] removed and what it could not.
+#[derive(Debug, Default)]
+pub struct Removal {
+    /// Whether `root` itself is gone.
+    pub removed: bool,
+    pub files: usize,
+    /// Bytes of removed files that had no other link: their length with
+    /// `apparent`, otherwise the space they took on disk.
+    pub bytes: u64,
+    /// Removed files that had other links, with their sizes. Whether
+    /// removing them freed anything depends on where the other links are.
+    pub links: Vec<(HardLink, u64)>,
+    pub errors: Vec<String>,
+}
+
+impl Removal {
+    /// The first error, if any, with a count of the rest.
+    pub fn into_result(self) -> Result<(), String> {
+        let more = self.errors.len().saturating_sub(1);
+        match self.errors.into_iter().next() {
+            None => Ok(()),
+            Some(first) if more == 0 => Err(first),
+            Some(first) => Err(format!("{first} (and {more} more)")),
+        }
+    }
+}
+
+/// Remove the directory `root` and everything below it, sizing files by
+/// length if `apparent` is set. With `identity`, the device and inode `root`
+/// had when it was scanned, a different directory at that path is refused.
+pub fn remove_tree(root: &Path, apparent: bool, identity: Option<(u64, u64)>) -> Removal {
+    imp::remove_tree(root, apparent, identity)
+}
+
+fn replaced(root: &Path) -> String {
+    format!(
+        "Refusing to delete {}: not the directory that was scanned",
+        root.display()
+    )
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+mod imp {
+    use super::{replaced, HardLink, Removal};
+    use rustix::fd::{AsFd, BorrowedFd, OwnedFd};
+    use rustix::fs::{AtFlags, Dir, FileType, Mode, OFlags};
+    use rustix::io::Errno;
+    use std::ffi::{CStr, CString, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Component, Path, PathBuf};
+
+    /// An open directory being emptied.
+    struct Frame {
+        dir: OwnedFd,
+        path: PathBuf,
+        /// Name in the parent frame's directory.
+        name: CString,
+        entries: std::vec::IntoIter<(CString, FileType)>,
+    }
+
+    impl Frame {
+        /// Open `name` below `parent` without following a symlink, and
+        /// refuse it unless it is still a directory on `device`, with
+        /// `identity` if one is given.
+        fn open(
+            parent: BorrowedFd<'_>,
+            name: CString,
+            path: PathBuf,
+            device: u64,
+            identity: Option<(u64, u64)>,
+        ) -> Result<Self, String> {
+            let flags = OFlags::RDONLY | OFlags::DIRECTORY | OFlags::NOFOLLOW | OFlags::CLOEXEC;
+            let dir = match rustix::fs::openat(parent, name.as_c_str(), flags, Mode::empty()) {
+                Ok(dir) => dir,
+                Err(Errno::LOOP | Errno::NOTDIR) => {
+                    return Err(format!(
+                        "Refusing to delete {}: no longer a directory",
+                        path.display()
+                    ))
+                }
+                Err(error) => {
+                    return Err(format!("Error reading {}: {}", path.display(), io(error)))
+                }
+            };
+            let stat = rustix::fs::fstat(&dir)
+                .map_err(|error| format!("Error reading {}: {}", path.display(), io(error)))?;
+            // `dev_t` is narrower than u64 on some targets.
+            #[allow(clippy::unnecessary_cast)]
+            if stat.st_dev as u64 != device {
+                return Err(format!(
+                    "Refusing to delete {}: on a different filesystem",
+                    path.display()
+                ));
+            }
+            #[allow(clippy::unnecessary_cast)]
+            if identity.is_some_and(|identity| identity != (stat.st_dev as u64, stat.st_ino as u64))
+            {
+                return Err(replaced(&path));
+            }
+            let entries = read_entries(&dir)
+                .map_err(|error| format!("Error reading {}: {}", path.display(), io(error)))?;
+            Ok(Self {
+                dir,
+                path,
+                name,
+                entries: entries.into_iter(),
+            })
+        }
+    }
+
+    pub(super) fn remove_tree(
+        root: &Path,
+        apparent: bool,
+        identity: Option<(u64, u64)>,
+    ) -> Removal {
+        let mut removal = Removal::default();
+        let (Some(parent_path), Some(name)) = (root.parent(), root.file_name()) else {
+            removal
+                .errors
+                .push(format!("Refusing to delete {}", root.display()));
+            return removal;
+        };
+        let opened = open_parent(root, parent_path).and_then(|parent| {
+            Ok((
+                rustix::fs::fstat(&parent).map_err(|error| {
+                    format!("Error reading {}: {}", parent_path.display(), io(error))
+                })?,
+                parent,
+            ))
+        });
+        let (parent_stat, parent) = match opened {
+            Ok(opened) => opened,
+            Err(error) => {
+                removal.errors.push(error);
+                return removal;
+            }
+        };
+        #[allow(clippy::unnecessary_cast)]
+        let device = parent_stat.st_dev as u64;
+        let Ok(name) = CString::new(name.as_bytes()) else {
+            removal
+                .errors
+                .push(format!("Refusing to delete {}", root.display()));
+            return removal;
+        };
+
+        let mut stack =
+            match Frame::open(parent.as_fd(), name, root.to_path_buf(), device, identity) {
+                Ok(frame) => vec![frame],
+                Err(error) => {
+                    removal.errors.push(error);
+                    return removal;
+                }
+            };
+        while let Some(top) = stack.last_mut() {
+            let Some((name, file_type)) = top.entries.next() else {
+                let done = stack.pop().expect("the loop saw a frame");
+                let Frame {
+                    dir, path, name, ..
+                } = done;
+                drop(dir);
+                let parent = stack
+                    .last()
+                    .map_or(parent.as_fd(), |frame| frame.dir.as_fd());
+                match rustix::fs::unlinkat(parent, name.as_c_str(), AtFlags::REMOVEDIR) {
+                    Ok(()) if stack.is_empty() => removal.removed = true,
+                    Ok(()) => {}
+                    Err(error) => removal.errors.push(format!(
+                        "Error deleting {}: {}",
+                        path.display(),
+                        io(error)
+                    )),
+                }
+                continue;
+            };
+            let path = top.path.join(OsStr::from_bytes(name.to_bytes()));
+            if file_type == FileType::Directory {
+                match Frame::open(top.dir.as_fd(), name, path, device, None) {
+                    Ok(frame) => stack.push(frame),
+                    Err(error) => removal.errors.push(error),
+                }
+                continue;
+            }
+            let stat =
+                rustix::fs::statat(top.dir.as_fd(), name.as_c_str(), AtFlags::SYMLINK_NOFOLLOW)
+                    .ok()
+                    .filter(|_| file_type == FileType::RegularFile);
+            let size = stat.as_ref().map_or(0, |stat| {
+                if apparent {
+                    stat.st_size as u64
+                } else {
+                    std::cmp::min(stat.st_size as u64, stat.st_blocks as u64 * 512)
+                }
+            });
+            #[allow(clippy::unnecessary_cast)]
+            let hard_link = stat.and_then(|stat| {
+                HardLink::new(stat.st_dev as u64, stat.st_ino as u64, stat.st_nlink as u64)
+            });
+            match rustix::fs::unlinkat(top.dir.as_fd(), name.as_c_str(), AtFlags::empty()) {
+                Ok(()) => {
+                    removal.files = removal.files.saturating_add(1);
+                    match hard_link {
+                        Some(link) => removal.links.push((link, size)),
+                        None => removal.bytes = removal.bytes.saturating_add(size),
+                    }
+                }
+                Err(error) => {
+                    removal
+                        .errors
+                        .push(format!("Error deleting {}: {}", path.display(), io(error)))
+                }
+            }
+        }
+        removal
+    }
+
+    /// Open `parent_path`, the parent of `root`, one component at a time
+    /// from `/` or the working directory, following no symlink on the way.
+    fn open_parent(root: &Path, parent_path: &Path) -> Result<OwnedFd, String> {
+        let flags = OFlags::RDONLY | OFlags::DIRECTORY | OFlags::NOFOLLOW | OFlags::CLOEXEC;
+        let start = if parent_path.has_root() { "/" } else { "." };
+        let mut dir = rustix::fs::open(start, flags, Mode::empty())
+            .map_err(|error| format!("Error reading {}: {}", parent_path.display(), io(error)))?;
+        let mut reached = PathBuf::from(start);
+        for component in parent_path.components() {
+            let name = match component {
+                Component::RootDir | Component::CurDir | Component::Prefix(_) => continue,
+                Component::ParentDir => OsStr::new(".."),
+                Component::Normal(name) => name,
+            };
+            reached.push(name);
+            let Ok(name) = CString::new(name.as_bytes()) else {
+                return Err(format!("Refusing to delete {}", root.display()));
+            };
+            dir = match rustix::fs::openat(&dir, name.as_c_str(), flags, Mode::empty()) {
+                Ok(dir) => dir,
+                Err(Errno::LOOP | Errno::NOTDIR) => {
+                    return Err(format!(
+                        "Refusing to delete {}: {} is no longer a directory",
+                        root.display(),
+                        reached.display()
+                    ))
+                }
+                Err(error) => {
+                    return Err(format!(
+                        "Error reading {}: {}",
+                        reached.display(),
+                        io(error)
+                    ))
+                }
+            };
+        }
+        Ok(dir)
+    }
+
+    /// Names and types of `dir`'s entries, asking `fstatat` where `readdir`
+    /// does not say.
+    fn read_entries(dir: &OwnedFd) -> rustix::io::Result<Vec<(CString, FileType)>> {
+        let mut entries = Vec::new();
+        for entry in Dir::read_from(dir)? {
+            let entry = entry?;
+            let name: &CStr = entry.file_name();
+            if name.to_bytes() == b"." || name.to_bytes() == b".." {
+                continue;
+            }
+            let mut file_type = entry.file_type();
+            if file_type == FileType::Unknown {
+                file_type = rustix::fs::statat(dir, name, AtFlags::SYMLINK_NOFOLLOW)
+                    .map_or(FileType::Unknown, |stat| {
+                        FileType::from_raw_mode(stat.st_mode)
+                    });
+            }
+            entries.push((name.to_owned(), file_type));
+        }
+        Ok(entries)
+    }
+
+    fn io(error: Errno) -> std::io::Error {
+        std::io::Error::from_raw_os_error(error.raw_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+mod imp {
+    use super::{replaced, Removal};
+    use std::fs;
+    use std::path::Path;
+
+    pub(super) fn remove_tree(
+        root: &Path,
+        apparent: bool,
+        identity: Option<(u64, u64)>,
+    ) -> Removal {
+        let mut removal = Removal::default();
+        match fs::symlink_metadata(root) {
+            Ok(_) if identity.is_some() && crate::fastwalk::identity_of(root) != identity => {
+                removal.errors.push(replaced(root));
+                return removal;
+            }
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                removal.errors.push(format!(
+                    "Refusing to delete {}: no longer a directory",
+                    root.display()
+                ));
+                return removal;
+            }
+            Err(error) => {
+                removal
+                    .errors
+                    .push(format!("Error reading {}: {error}", root.display()));
+                return removal;
+            }
+        }
+        let device = crate::fastwalk::device_of(root);
+        let mut stack = vec![(root.to_path_buf(), false)];
+        while let Some((path, visited)) = stack.pop() {
+            if visited {
+                match fs::remove_dir(&path) {
+                    Ok(()) if path == root => removal.removed = true,
+                    Ok(()) => {}
+                    Err(error) => removal
+                        .errors
+                        .push(format!("Error deleting {}: {error}", path.display())),
+                }
+                continue;
+            }
+
+            stack.push((path.clone(), true));
+            match crate::fastwalk::read_dir_fast(&path) {
+                Ok(entries) => {
+                    for entry in entries {
+                        let child = path.join(&entry.name);
+                        if entry.is_dir && !entry.is_symlink {
+                            if crate::fastwalk::device_of(&child) != device {
+                                removal.errors.push(format!(
+                                    "Refusing to delete {}: on a different filesystem",
+                                    child.display()
+                                ));
+                                continue;
+                            }
+                            stack.push((child, false));
+                        } else {
+                            let size = if apparent {
+                                entry.apparent_size
+                            } else {
+                                entry.size
+                            };
+                            match fs::remove_file(&child) {
+                                Ok(()) => {
+                                    removal.files = removal.files.saturating_add(1);
+                                    match entry.hard_link {
+                                        Some(link) => removal.links.push((link, size)),
+                                        None => removal.bytes = removal.bytes.saturating_add(size),
+                                    }
+                                }
+                                Err(error) => removal
+                                    .errors
+                                    .push(format!("Error deleting {}: {error}", child.display())),
+                            }
+                        }
+                    }
+                }
+                Err(error) => removal
+                    .errors
+                    .push(format!("Error reading {}: {error}", path.display())),
+            }
+        }
+        removal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn removes_a_tree_and_counts_its_files() {
+        let temp = TempDir::new("remove-tree");
+        temp.write("target/a.bin", b"123");
+        temp.write("target/nested/deeper/b.bin", b"12345");
+        temp.mkdir("target/empty");
+        let removal = remove_tree(&temp.join("target"), false, None);
+        assert!(removal.errors.is_empty(), "{:?}", removal.errors);
+        assert!(removal.removed);
+        assert_eq!(removal.files, 2);
+        assert!(!temp.join("target").exists());
+        assert!(temp.path().exists());
+
+        let missing = remove_tree(&temp.join("missing"), false, None);
+        assert!(!missing.removed);
+        assert_eq!(missing.errors.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_unlinked_or_refused_never_followed() {
+        use std::os::unix::fs::symlink;
+        let temp = TempDir::new("remove-links");
+        let outside = temp.write("outside/keep.txt", b"keep");
+        temp.write("target/a.bin", b"1");
+        symlink(temp.join("outside"), temp.join("target/link")).unwrap();
+        let removal = remove_tree(&temp.join("target"), false, None);
+        assert!(removal.removed, "{:?}", removal.errors);
+        assert!(outside.exists());
+
+        // The match itself was swapped for a link after the scan.
+        symlink(temp.join("outside"), temp.join("swapped")).unwrap();
+        let removal = remove_tree(&temp.join("swapped"), false, None);
+        assert!(!removal.removed);
+        assert_eq!(removal.files, 0);
+        assert!(outside.exists());
+        assert!(temp.join("swapped").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn swapped_ancestors_and_replaced_roots_are_refused() {
+        use crate::fastwalk::identity_of;
+        use std::os::unix::fs::symlink;
+        let temp = TempDir::new("remove-ancestor");
+        let decoy = temp.write("elsewhere/target/keep.txt", b"keep");
+        temp.write("project/target/a.bin", b"1");
+        let root = temp.join("project/target");
+        let identity = identity_of(&root);
+
+        // The scanned project directory is swapped for a link to a look-alike.
+        std::fs::rename(temp.join("project"), temp.join("moved")).unwrap();
+        symlink(temp.join("elsewhere"), temp.join("project")).unwrap();
+        let removal = remove_tree(&root, false, identity);
+        assert!(!removal.removed);
+        assert_eq!(removal.files, 0);
+        assert!(decoy.exists());
+
+        // A fresh directory at the same path is not the one scanned either.
+        std::fs::remove_file(temp.join("project")).unwrap();
+        temp.write("project/target/new.txt", b"new");
+        let removal = remove_tree(&root, false, identity);
+        assert!(!removal.removed, "{:?}", removal.errors);
+        assert!(temp.join("project/target/new.txt").exists());
+
+        let removal = remove_tree(&root, false, identity_of(&root));
+        assert!(removal.removed, "{:?}", removal.errors);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_reported_apart() {
+        let temp = TempDir::new("remove-hard-links");
+        let linked = temp.write("target/linked.bin", b"1234");
+        std::fs::hard_link(&linked, temp.join("elsewhere.bin")).unwrap();
+        temp.write("target/single.bin", b"12");
+        let removal = remove_tree(&temp.join("target"), false, None);
+        assert!(removal.removed, "{:?}", removal.errors);
+        assert_eq!(removal.files, 2);
+        assert_eq!(removal.links.len(), 1);
+        assert_eq!(removal.links[0].0.links, 2);
+    }
+}
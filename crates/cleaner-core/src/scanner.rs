@@ -1,13 +1,16 @@
 //! Parallel directory scanner using jwalk
 //! Configured for maximum performance with rayon thread pool
 
-use crate::config::Config;
-use crate::fastwalk;
-use crate::patterns::PatternMatcher;
+use crate::age::AgePolicy;
+use crate::config::{Config, PROJECT_CONFIG};
+use crate::fastwalk::{self, MetadataMode};
+use crate::gitignore::GitDir;
+use crate::patterns::{project, PatternMatcher};
 #[cfg(test)]
 use crate::pool::build_worker_pool;
-use crate::protected::protected_paths_for_root;
-use crossbeam_channel::Sender;
+use crate::protected::ProtectedPaths;
+use crossbeam_channel::{unbounded, Sender};
+use rayon::prelude::*;
 use rayon::ThreadPool;
 #[allow(unused_imports)]
 use std::path::{Path, PathBuf};
@@ -15,46 +18,28 @@ use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-fn passes_age_filter(path: &Path, days: Option<u64>) -> bool {
+fn passes_age_filter(path: &Path, is_dir: bool, days: Option<u64>, policy: AgePolicy) -> bool {
     let Some(days) = days else { return true };
-    std::fs::metadata(path)
-        .and_then(|metadata| metadata.modified())
-        .ok()
-        .and_then(|modified| modified.elapsed().ok())
-        .is_some_and(|elapsed| elapsed.as_secs() > days.saturating_mul(24 * 60 * 60))
+    policy.is_older_than(path, is_dir, days)
 }
 
-fn matched_file_size(path: &Path, days: Option<u64>) -> Option<u64> {
-    let metadata = std::fs::metadata(path);
-    let Some(days) = days else {
-        return Some(
-            metadata
-                .map(|value| {
-                    #[cfg(unix)]
-                    {
-                        use std::os::unix::fs::MetadataExt;
-                        std::cmp::min(value.len(), value.blocks() * 512)
-                    }
-                    #[cfg(not(unix))]
-                    value.len()
-                })
-                .unwrap_or(0),
-        );
-    };
-
-    let metadata = metadata.ok()?;
-    let modified = metadata.modified().ok()?;
-    let elapsed = modified.elapsed().ok()?;
-    let size = {
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::MetadataExt;
-            std::cmp::min(metadata.len(), metadata.blocks() * 512)
-        }
-        #[cfg(not(unix))]
-        metadata.len()
-    };
-    (elapsed.as_secs() > days.saturating_mul(24 * 60 * 60)).then_some(size)
+fn matched_file_size(path: &Path, days: Option<u64>, policy: AgePolicy) -> Option<u64> {
+    if !passes_age_filter(path, false, days, policy) {
+        return None;
+    }
+    Some(
+        std::fs::metadata(path)
+            .map(|value| {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::MetadataExt;
+                    std::cmp::min(value.len(), value.blocks() * 512)
+                }
+                #[cfg(not(unix))]
+                value.len()
+            })
+            .unwrap_or(0),
+    )
 }
 
 /// Result of scanning - a path to delete and whether it's a directory
@@ -63,6 +48,12 @@ pub struct ScanResult {
     pub path: PathBuf,
     pub is_dir: bool,
     pub size: u64,
+    /// The pattern that matched, for the journal; `None` when the item did
+    /// not come from a pattern.
+    pub rule: Option<String>,
+    /// Device and inode of a matched directory when it was scanned. Deleting
+    /// it refuses a directory that is not the same one any more.
+    pub identity: Option<(u64, u64)>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -74,7 +65,7 @@ pub struct ScanSummary {
 
 /// Parallel directory scanner
 pub struct Scanner {
-    matcher: PatternMatcher,
+    matcher: Arc<PatternMatcher>,
     config: Arc<Config>,
     root: PathBuf,
     pool: Arc<ThreadPool>,
@@ -92,7 +83,7 @@ impl Scanner {
 
     pub fn with_pool(root: PathBuf, pool: Arc<ThreadPool>, config: Arc<Config>) -> Self {
         Self {
-            matcher: PatternMatcher::new(Arc::clone(&config)),
+            matcher: Arc::new(PatternMatcher::new(Arc::clone(&config))),
             config,
             root,
             pool,
@@ -129,15 +120,15 @@ impl Scanner {
         let docker_path: Option<PathBuf> = None;
 
         // Protected directories (NEVER auto-clean inside these, but allow scanning)
-        let protected_paths = protected_paths_for_root(&self.root, self.config.force);
+        let protected = ProtectedPaths::new(&self.root, self.config.force, &self.config.protect);
 
         let context = ScanContext {
-            matcher: &self.matcher,
             config: &self.config,
             tx: &tx,
             scanned: &scanned,
             docker_path: &docker_path,
-            protected_paths: &protected_paths,
+            protected: &protected,
+            device: self.config.root_device(&self.root),
             #[cfg(target_os = "macos")]
             root: &self.root,
             cancelled,
@@ -145,8 +136,17 @@ impl Scanner {
             receiver_closed: &receiver_closed,
         };
 
+        // Ignore rules above the scan root still apply to everything below it.
+        let git = self
+            .config
+            .gitignored_only
+            .then(|| GitDir::discover(&self.root))
+            .flatten();
+
+        let matcher = project::for_root(Arc::clone(&self.matcher), &self.root, &errors);
+        let root_protected = protected.contains(&self.root) || matcher.is_excluded(&self.root);
         self.pool.scope(|s| {
-            walk_scanner(s, self.root.clone(), false, &context);
+            walk_scanner(s, self.root.clone(), root_protected, git, matcher, &context);
         });
 
         ScanSummary {
@@ -155,15 +155,66 @@ impl Scanner {
             receiver_closed: receiver_closed.into_inner(),
         }
     }
+
+    /// Scan to completion and rank the matches for a free-space goal: oldest
+    /// first by day, and the largest first within a day, so a goal is met by
+    /// removing as little recent work as possible. The day comes from the
+    /// configured [`AgePolicy`]; matches it counts as too recent
+    /// for `days`, or whose times cannot be read, are left out. Directory
+    /// sizes are measured for the ranking and kept in [`ScanResult::size`].
+    pub fn scan_ranked(&self) -> (Vec<ScanResult>, ScanSummary) {
+        let (tx, rx) = unbounded();
+        let summary = self.scan(tx);
+        let matches: Vec<ScanResult> = rx.into_iter().collect();
+        let policy = self.config.age_policy;
+        let cutoff = self.config.days.map(crate::age::cutoff);
+        let mut ranked: Vec<(i64, ScanResult)> = self.pool.install(|| {
+            matches
+                .into_par_iter()
+                .filter_map(|mut item| {
+                    let newest = policy.newest(&item.path, item.is_dir)?;
+                    if cutoff.is_some_and(|cutoff| cutoff.is_none_or(|cutoff| newest >= cutoff)) {
+                        return None;
+                    }
+                    if item.is_dir {
+                        item.size = directory_size(&item.path);
+                    }
+                    Some((newest.div_euclid(86_400), item))
+                })
+                .collect()
+        });
+        ranked.sort_by(|(a_day, a), (b_day, b)| a_day.cmp(b_day).then(b.size.cmp(&a.size)));
+        (ranked.into_iter().map(|(_, item)| item).collect(), summary)
+    }
+}
+
+/// Bytes of the files below `root`, not following symlinks.
+fn directory_size(root: &Path) -> u64 {
+    let mut total = 0u64;
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fastwalk::read_dir_fast(&dir) else {
+            continue;
+        };
+        for entry in entries {
+            if entry.is_dir && !entry.is_symlink {
+                pending.push(dir.join(&entry.name));
+            } else {
+                total = total.saturating_add(entry.size);
+            }
+        }
+    }
+    total
 }
 
 struct ScanContext<'a> {
-    matcher: &'a PatternMatcher,
     config: &'a Config,
     tx: &'a Sender<ScanResult>,
     scanned: &'a std::sync::atomic::AtomicUsize,
     docker_path: &'a Option<PathBuf>,
-    protected_paths: &'a [PathBuf],
+    protected: &'a ProtectedPaths,
+    /// The only device to walk, with [`Config::one_file_system`].
+    device: Option<u64>,
     #[cfg(target_os = "macos")]
     root: &'a Path,
     cancelled: &'a AtomicBool,
@@ -175,12 +226,21 @@ fn walk_scanner<'scope>(
     scope: &rayon::Scope<'scope>,
     dir: PathBuf,
     in_protected_dir: bool,
+    git: Option<GitDir>,
+    matcher: Arc<PatternMatcher>,
     context: &'scope ScanContext<'scope>,
 ) {
     if context.cancelled.load(Ordering::Relaxed) {
         return;
     }
-    let entries = match fastwalk::read_dir_types(&dir) {
+    // Staying on one filesystem needs the device of every subdirectory,
+    // which the reader stats from the open directory.
+    let mode = if context.device.is_some() {
+        MetadataMode::WithDevices
+    } else {
+        MetadataMode::TypesOnly
+    };
+    let entries = match fastwalk::read_dir(&dir, mode) {
         Ok(e) => e,
         Err(_) => {
             context.errors.fetch_add(1, Ordering::Relaxed);
@@ -189,9 +249,28 @@ fn walk_scanner<'scope>(
     };
     context.scanned.fetch_add(entries.len(), Ordering::Relaxed);
 
+    let matcher = if entries.iter().any(|entry| entry.name == PROJECT_CONFIG) {
+        project::enter(matcher, &dir, context.errors)
+    } else {
+        matcher
+    };
+
+    let git = if context.config.gitignored_only {
+        GitDir::enter(git, &dir, &entries)
+    } else {
+        None
+    };
+    // Outside a repository nothing is ignored, so nothing is reported.
+    let git_allows = |name: &std::ffi::OsStr, is_dir: bool| {
+        !context.config.gitignored_only
+            || git
+                .as_ref()
+                .is_some_and(|git| git.allows(&name.to_string_lossy(), is_dir))
+    };
+
     let mut subdirs = Vec::with_capacity(8);
 
-    for e in entries {
+    for e in &entries {
         if context.cancelled.load(Ordering::Relaxed) {
             return;
         }
@@ -204,6 +283,12 @@ fn walk_scanner<'scope>(
             {
                 continue;
             }
+            if context
+                .device
+                .is_some_and(|device| e.device_at(&path) != Some(device))
+            {
+                continue;
+            }
 
             #[cfg(target_os = "macos")]
             {
@@ -217,21 +302,26 @@ fn walk_scanner<'scope>(
                 }
             }
 
-            let in_protected = in_protected_dir
-                || context
-                    .protected_paths
-                    .iter()
-                    .any(|protected| path.starts_with(protected));
-            if !in_protected && context.matcher.is_temp_directory(&e.name) {
-                let should_delete = passes_age_filter(&path, context.config.days);
+            // Not inherited from `in_protected_dir`: `[protect] remove` can
+            // open a directory inside a protected one.
+            let in_protected = context.protected.contains(&path) || matcher.is_excluded(&path);
+            let rule = (!in_protected)
+                .then(|| matcher.directory_rule_in(&e.name, &entries))
+                .flatten()
+                .filter(|_| git_allows(&e.name, true));
+            if let Some(rule) = rule {
+                let should_delete =
+                    passes_age_filter(&path, true, context.config.days, context.config.age_policy);
 
                 if should_delete {
                     if context
                         .tx
                         .send(ScanResult {
+                            identity: fastwalk::identity_of(&path),
                             path,
                             is_dir: true,
                             size: 0,
+                            rule: Some(rule.to_string()),
                         })
                         .is_err()
                     {
@@ -244,17 +334,25 @@ fn walk_scanner<'scope>(
             }
 
             if !e.is_symlink {
-                subdirs.push((path, in_protected));
+                let child_git = git.as_ref().map(|git| git.child(&e.name.to_string_lossy()));
+                subdirs.push((path, in_protected, child_git, Arc::clone(&matcher)));
             }
-        } else if !in_protected_dir && context.matcher.is_temp_file(&e.name) {
+        } else if !in_protected_dir && matcher.is_temp_file(&e.name) && git_allows(&e.name, false) {
             let path = dir.join(&e.name);
-            if let Some(size) = matched_file_size(&path, context.config.days) {
+            if matcher.is_excluded(&path) {
+                continue;
+            }
+            if let Some(size) =
+                matched_file_size(&path, context.config.days, context.config.age_policy)
+            {
                 if context
                     .tx
                     .send(ScanResult {
                         path,
                         is_dir: false,
                         size,
+                        rule: matcher.file_rule(&e.name).map(str::to_string),
+                        identity: None,
                     })
                     .is_err()
                 {
@@ -267,9 +365,9 @@ fn walk_scanner<'scope>(
     }
 
     // Spawn sub-tasks in parallel using rayon work-stealing
-    for (subdir, protected) in subdirs {
+    for (subdir, protected, child_git, matcher) in subdirs {
         scope.spawn(move |s| {
-            walk_scanner(s, subdir, protected, context);
+            walk_scanner(s, subdir, protected, child_git, matcher, context);
         });
     }
 }
@@ -278,14 +376,13 @@ fn walk_scanner<'scope>(
 mod tests {
     use super::*;
     use crate::test_support::TempDir;
-    use crossbeam_channel::unbounded;
 
     fn config(days: Option<u64>) -> Arc<Config> {
         Arc::new(Config {
             directories: vec!["target".into()],
             files: vec![".pyc".into()],
             days,
-            force: false,
+            ..Config::empty()
         })
     }
 
@@ -314,6 +411,87 @@ mod tests {
         assert_eq!(scanned.errors, 0);
     }
 
+    #[test]
+    fn project_config_applies_only_inside_its_subtree() {
+        let temp = TempDir::new("scanner-project");
+        temp.write(
+            "repo/.cleaner.toml",
+            b"[patterns]\ndirectories = { extend = ['out'] }\n[exclude]\npaths = ['keep']\n",
+        );
+        temp.write("repo/out/a", b"1");
+        temp.write("repo/keep/target/a", b"1");
+        temp.write("repo/target/a", b"1");
+        temp.write("other/out/a", b"1");
+        temp.write("broken/.cleaner.toml", b"[patterns\n");
+        temp.write("broken/target/a", b"1");
+
+        let scan = |root: PathBuf| {
+            let (tx, rx) = unbounded();
+            let summary = Scanner::new(root, 2, config(None)).scan(tx);
+            let mut paths: Vec<_> = rx.iter().map(|result| result.path).collect();
+            paths.sort();
+            (paths, summary.errors)
+        };
+        let (paths, errors) = scan(temp.path().to_path_buf());
+        assert_eq!(
+            paths,
+            vec![
+                temp.join("broken/target"),
+                temp.join("repo/out"),
+                temp.join("repo/target"),
+            ]
+        );
+        assert_eq!(errors, 1);
+
+        // Starting below the project directory still picks its file up.
+        let (paths, _) = scan(temp.join("repo/keep"));
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn project_config_cannot_drop_inherited_excludes() {
+        let temp = TempDir::new("scanner-project-exclude");
+        temp.write(
+            "repo/.cleaner.toml",
+            b"[exclude]\npaths = { replace = [] }\n",
+        );
+        temp.write("repo/vendor/target/a", b"1");
+        temp.write("repo/target/a", b"1");
+        let config = Arc::new(Config {
+            exclude: vec![format!("{}/repo/vendor", temp.path().display())],
+            ..(*config(None)).clone()
+        });
+
+        let (tx, rx) = unbounded();
+        let summary = Scanner::new(temp.path().to_path_buf(), 2, config).scan(tx);
+        let paths: Vec<_> = rx.iter().map(|result| result.path).collect();
+        assert_eq!(paths, [temp.join("repo/target")]);
+        assert_eq!(summary.errors, 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn one_file_system_scans_skip_other_mounts() {
+        // /dev/shm is a tmpfs of its own wherever it is mounted at all.
+        let root = PathBuf::from("/dev");
+        if fastwalk::device_of(&root) == fastwalk::device_of(&root.join("shm")) {
+            return;
+        }
+        let scan = |one_file_system| {
+            let config = Arc::new(Config {
+                directories: vec!["shm".into()],
+                force: true,
+                one_file_system,
+                ..Config::empty()
+            });
+            let (tx, rx) = unbounded();
+            Scanner::new(root.clone(), 2, config).scan(tx);
+            rx.iter().map(|result| result.path).collect::<Vec<_>>()
+        };
+        assert_eq!(scan(false), [root.join("shm")]);
+        assert!(scan(true).is_empty());
+    }
+
     #[test]
     fn scanner_uses_requested_thread_count() {
         let temp = TempDir::new("scanner-threads");
@@ -335,6 +513,52 @@ mod tests {
         assert_eq!(summary.errors, 1);
     }
 
+    #[test]
+    fn excluded_paths_and_their_contents_are_never_reported() {
+        let temp = TempDir::new("scanner-exclude");
+        temp.write("legacy/target/out", b"1");
+        temp.write("legacy/module.pyc", b"1");
+        temp.write("app/target/out", b"1");
+        temp.write("app/keep.pyc", b"1");
+        let config = Arc::new(Config {
+            directories: vec!["target".into()],
+            files: vec![".pyc".into()],
+            exclude: vec![
+                format!("{}/legacy", temp.path().display()),
+                "app/keep.pyc".into(),
+            ],
+            ..Config::empty()
+        });
+        let (tx, rx) = unbounded();
+        Scanner::new(temp.path().to_path_buf(), 2, config).scan(tx);
+        let paths: Vec<_> = rx.iter().map(|result| result.path).collect();
+        assert_eq!(paths, vec![temp.join("app/target")]);
+    }
+
+    #[test]
+    fn gitignored_only_reports_just_what_git_ignores() {
+        let temp = TempDir::new("scanner-gitignored");
+        temp.mkdir(".git");
+        temp.write(".gitignore", b"target/\n*.pyc\n");
+        temp.write("target/out", b"1");
+        temp.write("build/deploy.sh", b"checked in");
+        temp.write("src/module.pyc", b"12");
+        let config = Arc::new(Config {
+            directories: vec!["target".into(), "build".into()],
+            files: vec![".pyc".into()],
+            gitignored_only: true,
+            ..Config::empty()
+        });
+        let (tx, rx) = unbounded();
+        Scanner::new(temp.path().to_path_buf(), 2, config).scan(tx);
+        let mut paths: Vec<_> = rx.iter().map(|result| result.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![temp.join("src/module.pyc"), temp.join("target")]
+        );
+    }
+
     #[cfg(unix)]
     #[test]
     fn scanner_traverses_non_utf8_directories() {
@@ -359,15 +583,95 @@ mod tests {
     fn age_filter_keeps_recent_items_and_handles_huge_values() {
         let temp = TempDir::new("scanner-age");
         let file = temp.write("recent.pyc", b"data");
-        assert!(passes_age_filter(&file, None));
-        assert!(!passes_age_filter(&file, Some(u64::MAX)));
-        assert!(!passes_age_filter(&temp.join("missing"), Some(1)));
+        let policy = AgePolicy::DirMtime;
+        assert!(passes_age_filter(&file, false, None, policy));
+        assert!(!passes_age_filter(&file, false, Some(u64::MAX), policy));
+        assert!(!passes_age_filter(
+            &temp.join("missing"),
+            false,
+            Some(1),
+            policy
+        ));
+        assert_eq!(matched_file_size(&file, None, policy), Some(4));
+        assert_eq!(matched_file_size(&file, Some(1), policy), None);
 
         let (tx, rx) = unbounded();
         Scanner::new(temp.path().to_path_buf(), 1, config(Some(u64::MAX))).scan(tx);
         assert!(rx.iter().next().is_none());
     }
 
+    #[test]
+    fn ranked_scan_puts_oldest_then_largest_first() {
+        let temp = TempDir::new("scanner-ranked");
+        let old = temp.write("old.pyc", b"1234567890");
+        let ten_days_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 86_400);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(ten_days_ago)
+            .unwrap();
+        temp.write("new.pyc", b"123");
+        temp.write("target/blob", &[0u8; 5000]);
+
+        let (ranked, summary) =
+            Scanner::new(temp.path().to_path_buf(), 2, config(None)).scan_ranked();
+        assert_eq!(summary.errors, 0);
+        let order: Vec<_> = ranked
+            .iter()
+            .map(|item| item.path.strip_prefix(temp.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            order,
+            [
+                PathBuf::from("old.pyc"),
+                PathBuf::from("target"),
+                PathBuf::from("new.pyc")
+            ]
+        );
+        assert!(ranked[1].size > 0);
+    }
+
+    #[test]
+    fn ranked_scan_dates_matches_by_the_age_policy() {
+        let temp = TempDir::new("scanner-ranked-policy");
+        let old = temp.write("old.pyc", b"1234567890");
+        temp.write("target/blob", &[0u8; 5000]);
+        let ten_days_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 86_400);
+        for path in [old, temp.join("target")] {
+            std::fs::File::open(&path)
+                .unwrap()
+                .set_modified(ten_days_ago)
+                .unwrap();
+        }
+        let ranked = |age_policy, days| {
+            let config = Arc::new(Config {
+                age_policy,
+                ..(*config(days)).clone()
+            });
+            let (ranked, _) = Scanner::new(temp.path().to_path_buf(), 2, config).scan_ranked();
+            ranked
+                .iter()
+                .map(|item| item.path.strip_prefix(temp.path()).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ranked(AgePolicy::DirMtime, None),
+            [PathBuf::from("target"), PathBuf::from("old.pyc")]
+        );
+        assert_eq!(
+            ranked(AgePolicy::NewestDescendant, None),
+            [PathBuf::from("old.pyc"), PathBuf::from("target")]
+        );
+        assert_eq!(
+            ranked(AgePolicy::NewestDescendant, Some(5)),
+            [PathBuf::from("old.pyc")]
+        );
+    }
+
     #[cfg(unix)]
     #[test]
     fn scanner_does_not_follow_directory_symlinks() {
@@ -10,6 +10,7 @@
 use super::glob;
 use super::{allowed_roots, is_container_allowed, is_path_allowed, Action, Target, Tier};
 use crate::deleter::Deleter;
+use crate::journal::{Journal, Record};
 use crate::pool::SCAN_POOL;
 use crate::scanner::ScanResult;
 use crate::stats::Stats;
@@ -33,11 +34,14 @@ pub struct RunReport {
 /// Execute `marked`.
 ///
 /// `dry_run` measures without deleting and without running any command.
+/// With a `journal`, every deleted path and every command run is recorded
+/// there under the target's id.
 pub fn run(
     marked: Vec<Target>,
     home: &Path,
     dry_run: bool,
     sink: Arc<Mutex<Vec<String>>>,
+    journal: Option<Arc<Journal>>,
 ) -> RunReport {
     let roots = allowed_roots(home);
     let mut report = RunReport::default();
@@ -59,17 +63,42 @@ pub fn run(
             )),
             Action::Elevated { .. } => unreachable!("elevated targets are deferred above"),
             Action::Command { program, args } => {
-                if dry_run {
-                    report.done.push(target.label.clone());
-                    continue;
+                let result = if dry_run {
+                    Ok(())
+                } else {
+                    run_command(program, args)
+                };
+                if let Some(journal) = &journal {
+                    let record = Record {
+                        path: Path::new(program),
+                        kind: "command",
+                        bytes: 0,
+                        rule: Some(&target.id),
+                        dry_run,
+                        disposal: "command",
+                        source: "deep-clean",
+                        error: result.as_ref().err().map(String::as_str),
+                    };
+                    if let Err(error) = journal.write([record]) {
+                        if let Ok(mut lines) = sink.lock() {
+                            lines.push(error);
+                        }
+                    }
                 }
-                match run_command(program, args) {
+                match result {
                     Ok(()) => report.done.push(target.label.clone()),
                     Err(error) => report.failed.push((target.label.clone(), error)),
                 }
             }
             Action::Remove(_) | Action::Empty(_) | Action::Glob(_) => {
-                match delete(&target, &roots, dry_run, Arc::clone(&stats), &sink) {
+                match delete(
+                    &target,
+                    &roots,
+                    dry_run,
+                    Arc::clone(&stats),
+                    &sink,
+                    journal.as_ref(),
+                ) {
                     Ok(()) => report.done.push(target.label.clone()),
                     Err(error) => report.failed.push((target.label.clone(), error)),
                 }
@@ -88,6 +117,7 @@ fn delete(
     dry_run: bool,
     stats: Arc<Stats>,
     sink: &Arc<Mutex<Vec<String>>>,
+    journal: Option<&Arc<Journal>>,
 ) -> Result<(), String> {
     let mut items = Vec::new();
 
@@ -120,7 +150,7 @@ fn delete(
                         if !is_path_allowed(&child, roots) {
                             return Err(refusal(&child));
                         }
-                        if let Some(item) = validated(&child) {
+                        if let Some(item) = validated(&child, &target.id) {
                             items.push(item);
                         }
                     }
@@ -130,7 +160,7 @@ fn delete(
                     if !is_path_allowed(&path, roots) {
                         return Err(refusal(&path));
                     }
-                    if let Some(item) = validated(&path) {
+                    if let Some(item) = validated(&path, &target.id) {
                         items.push(item);
                     }
                 }
@@ -143,13 +173,16 @@ fn delete(
     }
 
     let (tx, rx) = crossbeam_channel::bounded(1024);
-    let deleter = Deleter::with_sink(
+    let mut deleter = Deleter::with_sink(
         stats,
         dry_run,
         false,
         Arc::clone(&SCAN_POOL),
         Arc::clone(sink),
     );
+    if let Some(journal) = journal {
+        deleter = deleter.journal(Arc::clone(journal), "deep-clean");
+    }
     let worker = std::thread::spawn(move || deleter.process(rx));
 
     for item in items {
@@ -173,7 +206,7 @@ fn refusal(path: &Path) -> String {
 /// Mirrors the guard the TUI already applies to manual deletes: a path whose
 /// type changed since the scan, or that turned out to be a symlink, is skipped
 /// rather than followed.
-fn validated(path: &Path) -> Option<ScanResult> {
+fn validated(path: &Path, rule: &str) -> Option<ScanResult> {
     let metadata = std::fs::symlink_metadata(path).ok()?;
     if metadata.file_type().is_symlink() {
         return None;
@@ -184,6 +217,8 @@ fn validated(path: &Path) -> Option<ScanResult> {
         path: path.to_path_buf(),
         is_dir,
         size,
+        rule: Some(rule.to_string()),
+        identity: is_dir.then(|| crate::fastwalk::identity_of(path)).flatten(),
     })
 }
 
@@ -7,7 +7,7 @@
 
 use super::glob;
 use super::{Candidate, Group, Target};
-use crate::fastwalk::walk_parallel_mapped;
+use crate::fastwalk::{walk_parallel_mapped, MetadataMode, RawEntry};
 use crate::pool::SCAN_POOL;
 use crate::tree::ScanProgress;
 use std::path::Path;
@@ -108,17 +108,22 @@ fn size_of(path: &Path, cancelled: &Arc<AtomicBool>) -> u64 {
     // point so a huge tree can be abandoned mid-flight rather than only between
     // targets.
     let flag = Arc::clone(cancelled);
-    let skip = Arc::new(move |_: &Path| flag.load(Ordering::Relaxed));
-
-    let output = walk_parallel_mapped(path.to_path_buf(), &SCAN_POOL, skip, None, &|_,
-                                                                                    entries|
-     -> u64 {
-        entries
-            .iter()
-            .filter(|entry| !entry.is_dir && !entry.is_symlink)
-            .map(|entry| entry.size)
-            .sum()
-    });
+    let skip = Arc::new(move |_: &Path, _: &RawEntry| flag.load(Ordering::Relaxed));
+
+    let output = walk_parallel_mapped(
+        path.to_path_buf(),
+        &SCAN_POOL,
+        MetadataMode::WithSizes,
+        skip,
+        None,
+        &|_, entries| -> u64 {
+            entries
+                .iter()
+                .filter(|entry| !entry.is_dir && !entry.is_symlink)
+                .map(|entry| entry.size)
+                .sum()
+        },
+    );
 
     output.entries.values().sum()
 }
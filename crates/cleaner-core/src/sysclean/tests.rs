@@ -241,7 +241,8 @@ fn dry_run_reports_bytes_without_deleting() {
     };
 
     let sink = Arc::new(Mutex::new(Vec::new()));
-    let report = run(vec![target], temp.path(), true, sink);
+    let journal = Arc::new(crate::journal::Journal::open(&temp.join("audit.jsonl")).unwrap());
+    let report = run(vec![target], temp.path(), true, sink, Some(journal));
 
     assert_eq!(report.done.len(), 1);
     assert!(report.failed.is_empty(), "{:?}", report.failed);
@@ -251,6 +252,11 @@ fn dry_run_reports_bytes_without_deleting() {
         report.freed
     );
     assert!(temp.join("cache/a.bin").exists(), "dry run must not delete");
+    let journal = std::fs::read_to_string(temp.join("audit.jsonl")).unwrap();
+    assert_eq!(journal.lines().count(), 1);
+    assert!(journal.contains("\"rule\":\"dry\""), "{journal}");
+    assert!(journal.contains("\"dry_run\":true"), "{journal}");
+    assert!(journal.contains("\"source\":\"deep-clean\""), "{journal}");
 }
 
 #[test]
@@ -282,7 +288,7 @@ fn live_run_removes_paths_and_empty_keeps_the_directory() {
     };
 
     let sink = Arc::new(Mutex::new(Vec::new()));
-    let report = run(vec![remove, empty], temp.path(), false, sink);
+    let report = run(vec![remove, empty], temp.path(), false, sink, None);
 
     assert!(report.failed.is_empty(), "{:?}", report.failed);
     assert!(
@@ -321,7 +327,7 @@ fn elevated_targets_are_deferred_not_executed() {
     };
 
     let sink = Arc::new(Mutex::new(Vec::new()));
-    let report = run(vec![target], temp.path(), false, sink);
+    let report = run(vec![target], temp.path(), false, sink, None);
 
     assert_eq!(report.deferred.len(), 1);
     assert!(report.done.is_empty());
@@ -348,7 +354,7 @@ fn report_only_targets_are_refused() {
     };
 
     let sink = Arc::new(Mutex::new(Vec::new()));
-    let report = run(vec![target], temp.path(), false, sink);
+    let report = run(vec![target], temp.path(), false, sink, None);
 
     assert_eq!(report.failed.len(), 1);
     assert!(temp.join("vm/disk.img").exists());
@@ -373,7 +379,7 @@ fn paths_outside_the_allowed_roots_are_refused() {
     };
 
     let sink = Arc::new(Mutex::new(Vec::new()));
-    let report = run(vec![target], temp.path(), false, sink);
+    let report = run(vec![target], temp.path(), false, sink, None);
 
     assert_eq!(report.failed.len(), 1);
     assert!(report.failed[0].1.contains("outside the allowed roots"));
@@ -424,6 +430,8 @@ fn deleter_sink_collects_errors_instead_of_printing() {
         path: PathBuf::from("/definitely/not/a/real/path/xyzzy"),
         is_dir: false,
         size: 0,
+        rule: None,
+        identity: None,
     })
     .unwrap();
     drop(tx);
@@ -0,0 +1,363 @@
+//! Move-to-trash disposal following the FreeDesktop.org Trash specification
+//!
+//! Items on the same filesystem as the home trash (`$XDG_DATA_HOME/Trash`)
+//! are moved there. Items on another filesystem go to the trash at the top
+//! of their own mount: `$topdir/.Trash/$uid` when an administrator created a
+//! shared, sticky `.Trash`, otherwise `$topdir/.Trash-$uid`. Every item gets
+//! a `.trashinfo` record, so desktop file managers can list and restore it.
+//!
+//! Items are only ever renamed. Nothing is copied across filesystems; an item
+//! that cannot be renamed into a trash directory is reported and left alone.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A user's trash: the home trash plus the per-mount ones it falls back to.
+#[derive(Debug, Clone)]
+pub struct Trash {
+    home: PathBuf,
+}
+
+impl Trash {
+    /// Trash whose home trash directory is `home` (normally
+    /// `~/.local/share/Trash`).
+    pub fn new(home: PathBuf) -> Self {
+        Self { home }
+    }
+
+    /// The current user's trash: `$XDG_DATA_HOME/Trash`, or
+    /// `~/.local/share/Trash` when that is unset or relative.
+    pub fn from_env() -> Result<Self, String> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+            .ok_or_else(|| {
+                "Cannot locate the trash: neither XDG_DATA_HOME nor HOME is set".to_string()
+            })?;
+        Ok(Self::new(data_home.join("Trash")))
+    }
+
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    /// Move `path` into the trash and return where it ended up.
+    pub fn put(&self, path: &Path) -> Result<PathBuf, String> {
+        let fail =
+            |reason: &dyn std::fmt::Display| format!("Cannot trash {}: {reason}", path.display());
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(fail(&"not a removable path"));
+        };
+        let parent = parent
+            .canonicalize()
+            .or_else(|_| std::path::absolute(parent))
+            .map_err(|error| fail(&error))?;
+        let path = parent.join(name);
+        let (trash_dir, original) = self.trash_dir_for(&path).map_err(|error| fail(&error))?;
+        move_into(&trash_dir, &path, &original).map_err(|error| fail(&error))
+    }
+
+    /// The trash directory for `path` and the path to record in its
+    /// `.trashinfo` file.
+    #[cfg(unix)]
+    fn trash_dir_for(&self, path: &Path) -> Result<(PathBuf, PathBuf), String> {
+        use std::os::unix::fs::MetadataExt;
+
+        let device = fs::symlink_metadata(path)
+            .map_err(|error| error.to_string())?
+            .dev();
+        if nearest_device(&self.home) == Some(device) {
+            private_dir(&self.home)?;
+            return Ok((self.home.clone(), path.to_path_buf()));
+        }
+
+        let topdir = mount_top(path, device);
+        if topdir == path {
+            return Err("it is a mount point".to_string());
+        }
+        let trash_dir = topdir_trash(topdir, current_uid())?;
+        // Per-mount trashes record paths relative to the mount.
+        let relative = path.strip_prefix(topdir).unwrap_or(path);
+        Ok((trash_dir, relative.to_path_buf()))
+    }
+
+    #[cfg(not(unix))]
+    fn trash_dir_for(&self, _path: &Path) -> Result<(PathBuf, PathBuf), String> {
+        Err("moving to the trash is not supported on this platform".to_string())
+    }
+}
+
+/// The device of `path`, or of its nearest existing ancestor when `path`
+/// has not been created yet.
+#[cfg(unix)]
+pub(crate) fn nearest_device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    path.ancestors()
+        .find_map(|ancestor| fs::metadata(ancestor).ok())
+        .map(|metadata| metadata.dev())
+}
+
+/// The highest ancestor of `path` on filesystem `device`.
+#[cfg(unix)]
+pub(crate) fn mount_top(path: &Path, device: u64) -> &Path {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut top = path;
+    for ancestor in path.ancestors().skip(1) {
+        match fs::metadata(ancestor) {
+            Ok(metadata) if metadata.dev() == device => top = ancestor,
+            _ => break,
+        }
+    }
+    top
+}
+
+/// The trash directory inside mount `topdir`: the shared `.Trash/$uid` if an
+/// administrator set one up (a real, sticky directory), else `.Trash-$uid`.
+#[cfg(unix)]
+fn topdir_trash(topdir: &Path, uid: u32) -> Result<PathBuf, String> {
+    use std::os::unix::fs::PermissionsExt;
+    const STICKY: u32 = 0o1000;
+
+    let shared = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared) {
+        if metadata.is_dir() && metadata.permissions().mode() & STICKY != 0 {
+            let dir = shared.join(uid.to_string());
+            if private_dir(&dir).is_ok() {
+                return Ok(dir);
+            }
+        }
+    }
+    let dir = topdir.join(format!(".Trash-{uid}"));
+    private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Create `dir` (mode 0700) if needed and check that it is a real directory
+/// owned by the current user.
+#[cfg(unix)]
+pub(crate) fn private_dir(dir: &Path) -> Result<(), String> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .map_err(|error| format!("{}: {error}", dir.display()))?;
+    let metadata = fs::symlink_metadata(dir).map_err(|error| error.to_string())?;
+    if !metadata.is_dir() || metadata.uid() != current_uid() {
+        return Err(format!(
+            "{} is not a directory owned by the current user",
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    unsafe { libc::getuid() }
+}
+
+/// Names tried for one item before giving up on a trash that is full of
+/// its namesakes.
+const NAME_ATTEMPTS: u32 = 10_000;
+
+/// Rename `path` into `trash_dir/files`, next to its `info` record. The
+/// record is created first, exclusively, which is what reserves the name.
+fn move_into(trash_dir: &Path, path: &Path, original: &Path) -> Result<PathBuf, String> {
+    let files = trash_dir.join("files");
+    let info = trash_dir.join("info");
+    for dir in [&files, &info] {
+        fs::create_dir_all(dir).map_err(|error| format!("{}: {error}", dir.display()))?;
+    }
+    let name = path.file_name().unwrap_or_default();
+    let record = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original),
+        deletion_date()
+    );
+
+    for attempt in 1..=NAME_ATTEMPTS {
+        let mut candidate = name.to_os_string();
+        if attempt > 1 {
+            candidate.push(format!(".{attempt}"));
+        }
+        let mut info_name = candidate.clone();
+        info_name.push(".trashinfo");
+        let info_path = info.join(info_name);
+        let mut file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(format!("{}: {error}", info_path.display())),
+        };
+        let target = files.join(&candidate);
+        // A stray file without a record still occupies the name.
+        if fs::symlink_metadata(&target).is_ok() {
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+        let moved = file
+            .write_all(record.as_bytes())
+            .and_then(|()| fs::rename(path, &target));
+        return match moved {
+            Ok(()) => Ok(target),
+            Err(error) => {
+                let _ = fs::remove_file(&info_path);
+                Err(error.to_string())
+            }
+        };
+    }
+    Err(format!(
+        "{}: no free name for {} after {NAME_ATTEMPTS} attempts",
+        files.display(),
+        name.to_string_lossy()
+    ))
+}
+
+/// Percent-encode `path` as the spec requires (RFC 2396 escaping, `/` kept).
+fn encode_path(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+
+    let mut out = String::with_capacity(bytes.len());
+    for byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Local time as `YYYY-MM-DDThh:mm:ss`.
+#[cfg(unix)]
+fn deletion_date() -> String {
+    // SAFETY: `time` accepts a null pointer, and `localtime_r` only writes
+    // to the zeroed `tm` it is given.
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        tm
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(not(unix))]
+fn deletion_date() -> String {
+    String::new()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn same_filesystem_items_go_to_the_home_trash_with_a_record() {
+        let temp = TempDir::new("trash-home");
+        let trash = Trash::new(temp.join("data/Trash"));
+        let file = temp.write("src/a b%.log", b"log");
+        let dir = temp.mkdir("src/target/debug");
+
+        let trashed = trash.put(&file).unwrap();
+        assert_eq!(trashed, temp.join("data/Trash/files/a b%.log"));
+        assert!(!file.exists());
+        assert_eq!(fs::read(&trashed).unwrap(), b"log");
+
+        let record = fs::read_to_string(temp.join("data/Trash/info/a b%.log.trashinfo")).unwrap();
+        let expected_path = encode_path(&temp.path().canonicalize().unwrap().join("src/a b%.log"));
+        assert!(expected_path.ends_with("/src/a%20b%25.log"));
+        let lines: Vec<&str> = record.lines().collect();
+        assert_eq!(lines[0], "[Trash Info]");
+        assert_eq!(lines[1], format!("Path={expected_path}"));
+        assert!(lines[2].starts_with("DeletionDate="));
+        assert_eq!(lines[2].len(), "DeletionDate=2024-01-31T12:00:00".len());
+
+        trash.put(&temp.join("src/target")).unwrap();
+        assert!(!dir.exists());
+        assert!(temp.join("data/Trash/files/target/debug").is_dir());
+        let mode = fs::metadata(temp.join("data/Trash"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[test]
+    fn clashing_names_get_a_numeric_suffix() {
+        let temp = TempDir::new("trash-clash");
+        let trash = Trash::new(temp.join("Trash"));
+        let first = temp.write("a/node_modules/x", b"");
+        let second = temp.write("b/node_modules/y", b"");
+        // A file with no record still blocks its name.
+        temp.write("Trash/files/node_modules.2", b"");
+
+        trash.put(first.parent().unwrap()).unwrap();
+        let moved = trash.put(second.parent().unwrap()).unwrap();
+        assert_eq!(moved, temp.join("Trash/files/node_modules.3"));
+        assert!(temp.join("Trash/info/node_modules.3.trashinfo").is_file());
+        assert!(!temp.join("Trash/info/node_modules.2.trashinfo").exists());
+        assert!(trash.put(&temp.join("missing")).is_err());
+    }
+
+    #[test]
+    fn a_trash_without_a_free_name_is_an_error() {
+        let temp = TempDir::new("trash-full");
+        let item = temp.write("cache.bin", b"1");
+        let info = temp.mkdir("Trash/info");
+        for attempt in 1..=NAME_ATTEMPTS {
+            let name = match attempt {
+                1 => "cache.bin.trashinfo".to_string(),
+                _ => format!("cache.bin.{attempt}.trashinfo"),
+            };
+            fs::write(info.join(name), b"").unwrap();
+        }
+
+        let error = Trash::new(temp.join("Trash")).put(&item).unwrap_err();
+        assert!(error.contains("no free name"), "{error}");
+        assert!(item.exists());
+    }
+
+    #[test]
+    fn mount_trash_prefers_a_sticky_shared_directory() {
+        let temp = TempDir::new("trash-topdir");
+        let uid = current_uid();
+        assert_eq!(
+            topdir_trash(temp.path(), uid).unwrap(),
+            temp.join(format!(".Trash-{uid}"))
+        );
+
+        let shared = temp.mkdir(".Trash");
+        assert_eq!(
+            topdir_trash(temp.path(), uid).unwrap(),
+            temp.join(format!(".Trash-{uid}"))
+        );
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        assert_eq!(
+            topdir_trash(temp.path(), uid).unwrap(),
+            shared.join(uid.to_string())
+        );
+    }
+}
@@ -0,0 +1,654 @@
+//! Storage behind [`DirTree`]. Every entry is one fixed-size [`Node`] in a
+//! single vector, linked to its parent by index, with all names packed into
+//! one byte buffer. A directory that has been read also has [`Contents`]:
+//! the IDs of its entries. Paths are never stored; they are followed from a
+//! top directory, one name at a time.
+//!
+//! Each entry costs a 56-byte node, a 4-byte ID in its parent's contents and
+//! its name; each directory read adds about 64 bytes more. See
+//! [`DirTree::memory_usage`].
+
+use super::sizing::Totals;
+use super::{DirEntry, DirTree};
+use foldhash::fast::FixedState;
+use foldhash::{HashMap, HashMapExt};
+use std::ffi::{OsStr, OsString};
+use std::hash::BuildHasher;
+use std::path::{Component, Path, PathBuf};
+
+/// Index of a [`Node`] in its tree.
+pub(super) type NodeId = u32;
+
+/// No node, or no contents.
+pub(super) const NONE: u32 = u32::MAX;
+
+const DIR: u8 = 1;
+const TEMP: u8 = 2;
+const HAS_UID: u8 = 4;
+
+/// One entry: what a [`DirEntry`] holds, with its name kept elsewhere.
+#[derive(Clone)]
+pub(super) struct Node {
+    pub(super) size: u64,
+    pub(super) apparent: u64,
+    pub(super) items: u64,
+    pub(super) modified: i64,
+    /// Where the name starts in [`DirTree::names`].
+    name: u64,
+    parent: NodeId,
+    /// Index in [`DirTree::contents`], or [`NONE`] for a file or a
+    /// directory that was not read.
+    contents: u32,
+    uid: u32,
+    name_len: u16,
+    flags: u8,
+}
+
+impl Node {
+    pub(super) fn is_dir(&self) -> bool {
+        self.flags & DIR != 0
+    }
+
+    pub(super) fn is_temp(&self) -> bool {
+        self.flags & TEMP != 0
+    }
+
+    pub(super) fn is_listed(&self) -> bool {
+        self.contents != NONE
+    }
+
+    pub(super) fn parent(&self) -> NodeId {
+        self.parent
+    }
+
+    pub(super) fn contents(&self) -> u32 {
+        self.contents
+    }
+
+    /// A hard link to a file counted under another of its names, which
+    /// [`DirTree::dedup_hard_links`] leaves with no size and no items.
+    pub(super) fn is_extra_link(&self) -> bool {
+        !self.is_dir() && self.items == 0
+    }
+
+    /// The owner, where the scan could read it.
+    pub(super) fn uid(&self) -> Option<u32> {
+        (self.flags & HAS_UID != 0).then_some(self.uid)
+    }
+
+    /// Take the sizes of `old`, the node this one replaces, whose contents
+    /// it keeps.
+    pub(super) fn carry(&mut self, old: &Node) {
+        self.size = old.size;
+        self.apparent = old.apparent;
+        self.items = old.items;
+        self.modified = self.modified.max(old.modified);
+    }
+
+    /// Size a directory whose contents are not known.
+    pub(super) fn clear(&mut self) {
+        self.settle(Totals::default());
+    }
+
+    /// Size a directory whose contents add up to `totals`.
+    fn settle(&mut self, totals: Totals) {
+        self.size = totals.size;
+        self.apparent = totals.apparent;
+        self.items = totals.items.saturating_add(1);
+        self.modified = self.modified.max(totals.modified);
+    }
+
+    /// Move a directory whose contents added up to `before` to `after`.
+    fn shift(&mut self, before: Totals, after: Totals) {
+        self.size = self
+            .size
+            .saturating_sub(before.size)
+            .saturating_add(after.size);
+        self.apparent = self
+            .apparent
+            .saturating_sub(before.apparent)
+            .saturating_add(after.apparent);
+        self.items = self
+            .items
+            .saturating_sub(before.items)
+            .saturating_add(after.items);
+        self.modified = self.modified.max(after.modified);
+    }
+
+    fn totals(&self) -> Totals {
+        Totals {
+            size: self.size,
+            apparent: self.apparent,
+            items: self.items,
+            modified: self.modified,
+        }
+    }
+}
+
+/// The entries of a directory that has been read.
+#[derive(Clone, Default)]
+pub(super) struct Contents {
+    pub(super) children: Vec<NodeId>,
+    /// Whether its listing starts with "..".
+    up: bool,
+}
+
+fn name_hash(name: &[u8]) -> u64 {
+    FixedState::with_seed(0).hash_one(name)
+}
+
+impl DirTree {
+    pub(super) fn empty() -> Self {
+        Self {
+            nodes: Vec::new(),
+            names: Vec::new(),
+            contents: Vec::new(),
+            tops: Vec::new(),
+            dirs: HashMap::new(),
+            free_nodes: Vec::new(),
+            free_contents: Vec::new(),
+            dead_names: 0,
+            shown: None,
+            owned: None,
+        }
+    }
+
+    /// The tree of `listings`, keyed by directory. A directory listed by no
+    /// other becomes a top directory. With `nested_up` every other listing
+    /// gets "..", otherwise only those that have it already.
+    pub(super) fn arrange(
+        mut listings: HashMap<PathBuf, std::sync::Arc<Vec<DirEntry>>>,
+        nested_up: bool,
+    ) -> Self {
+        let mut tree = Self::empty();
+        tree.nodes
+            .reserve(listings.values().map(|entries| entries.len()).sum());
+        tree.contents.reserve(listings.len());
+        tree.dirs.reserve(listings.len());
+
+        let mut tops: Vec<PathBuf> = listings
+            .keys()
+            .filter(|path| {
+                path.parent()
+                    .is_none_or(|parent| !listings.contains_key(parent))
+            })
+            .cloned()
+            .collect();
+        tops.sort_unstable();
+        tree.arrange_from(&mut listings, tops, nested_up);
+        // Whatever no top reaches, such as a directory its parent does not
+        // list, stands on its own; parents sort before their children.
+        let mut rest: Vec<PathBuf> = listings.keys().cloned().collect();
+        rest.sort_unstable();
+        tree.arrange_from(&mut listings, rest, nested_up);
+        tree.shrink_to_fit();
+        tree
+    }
+
+    /// Add each of `tops` that `listings` still has as a top directory, with
+    /// every listing below it, taking them out of `listings`.
+    fn arrange_from(
+        &mut self,
+        listings: &mut HashMap<PathBuf, std::sync::Arc<Vec<DirEntry>>>,
+        tops: Vec<PathBuf>,
+        nested_up: bool,
+    ) {
+        for top in tops {
+            if !listings.contains_key(&top) {
+                continue;
+            }
+            let id = self.add_top(&top);
+            let mut stack = vec![(top, id, false)];
+            while let Some((path, id, nested)) = stack.pop() {
+                let Some(entries) = listings.remove(&path) else {
+                    continue;
+                };
+                let up = entries.iter().any(|entry| entry.name == "..");
+                let unlisted = self.relist(id, &entries, |_, _, _| {});
+                self.contents[self.nodes[id as usize].contents as usize].up =
+                    up || (nested_up && nested);
+                for child in unlisted {
+                    stack.push((path.join(self.name(child)), child, true));
+                }
+            }
+        }
+    }
+
+    /// Add the listing of `dir`, a directory of the tree of `root` whose
+    /// parent is already listed, as a walk delivers it. Sizes are left for
+    /// [`DirTree::size_directories`]. Returns the directory's node, whose
+    /// contents follow the order of `entries`.
+    pub(super) fn place_listing(
+        &mut self,
+        root: &Path,
+        dir: &Path,
+        entries: &[DirEntry],
+    ) -> NodeId {
+        let id = match self.node_at(dir) {
+            Some(id) => id,
+            None => self.add_top(dir),
+        };
+        self.relist(id, entries, |_, _, _| {});
+        self.set_up(id, dir != root);
+        id
+    }
+
+    /// Give back the room reserved for growth once the tree is built.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.names.shrink_to_fit();
+        self.contents.shrink_to_fit();
+        self.dirs.shrink_to_fit();
+    }
+
+    /// Add `path` as a top directory with no contents yet.
+    pub(super) fn add_top(&mut self, path: &Path) -> NodeId {
+        let id = self.alloc(NONE, &DirEntry::new("", 0, true, false));
+        self.tops.push((path.to_path_buf(), id));
+        self.contents_of(id);
+        id
+    }
+
+    /// The node at `path`: a top directory or an entry below one.
+    pub(super) fn node_at(&self, path: &Path) -> Option<NodeId> {
+        let (top, id) = self
+            .tops
+            .iter()
+            .filter(|(top, _)| path.starts_with(top))
+            .max_by_key(|(top, _)| top.as_os_str().len())?;
+        let mut id = *id;
+        for component in path.strip_prefix(top).ok()?.components() {
+            let Component::Normal(name) = component else {
+                return None;
+            };
+            id = self.child_named(id, name)?;
+        }
+        Some(id)
+    }
+
+    /// The node of the directory at `path`, if it has been read.
+    pub(super) fn listed_at(&self, path: &Path) -> Option<NodeId> {
+        self.node_at(path)
+            .filter(|&id| self.nodes[id as usize].is_listed())
+    }
+
+    fn child_named(&self, dir: NodeId, name: &OsStr) -> Option<NodeId> {
+        let name = name.as_encoded_bytes();
+        if let Some(&id) = self.dirs.get(&(dir, name_hash(name))) {
+            if self.name_bytes(id) == name {
+                return Some(id);
+            }
+        }
+        // Files, and directories whose name hash another sibling took.
+        self.children_of(dir)
+            .iter()
+            .copied()
+            .find(|&id| self.name_bytes(id) == name)
+    }
+
+    pub(super) fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    pub(super) fn children_of(&self, dir: NodeId) -> &[NodeId] {
+        self.contents
+            .get(self.nodes[dir as usize].contents as usize)
+            .map_or(&[], |contents| contents.children.as_slice())
+    }
+
+    pub(super) fn has_up(&self, dir: NodeId) -> bool {
+        self.contents
+            .get(self.nodes[dir as usize].contents as usize)
+            .is_some_and(|contents| contents.up)
+    }
+
+    pub(super) fn set_up(&mut self, dir: NodeId, up: bool) {
+        let index = self.contents_of(dir);
+        self.contents[index].up = up;
+    }
+
+    fn name_bytes(&self, id: NodeId) -> &[u8] {
+        let node = &self.nodes[id as usize];
+        let start = node.name as usize;
+        &self.names[start..start + usize::from(node.name_len)]
+    }
+
+    pub(super) fn name(&self, id: NodeId) -> &OsStr {
+        // SAFETY: names are stored exactly as `as_encoded_bytes` returned
+        // them, and split only at their own boundaries.
+        unsafe { OsStr::from_encoded_bytes_unchecked(self.name_bytes(id)) }
+    }
+
+    pub(super) fn entry(&self, id: NodeId) -> DirEntry {
+        let node = &self.nodes[id as usize];
+        DirEntry {
+            name: OsString::from(self.name(id)),
+            size: node.size,
+            apparent_size: node.apparent,
+            modified: node.modified,
+            items: node.items,
+            uid: node.uid(),
+            is_dir: node.is_dir(),
+            is_temp: node.is_temp(),
+        }
+    }
+
+    /// What the contents of `dir` add up to.
+    pub(super) fn totals(&self, dir: NodeId) -> Totals {
+        let mut totals = Totals::default();
+        for &id in self.children_of(dir) {
+            let node = &self.nodes[id as usize];
+            totals.size = totals.size.saturating_add(node.size);
+            totals.apparent = totals.apparent.saturating_add(node.apparent);
+            totals.items = totals.items.saturating_add(node.items);
+            totals.modified = totals.modified.max(node.modified);
+        }
+        totals
+    }
+
+    /// What `id` itself adds to its parent.
+    pub(super) fn measures(&self, id: NodeId) -> Totals {
+        self.nodes[id as usize].totals()
+    }
+
+    /// Size the directory `dir` from its contents.
+    pub(super) fn settle(&mut self, dir: NodeId) {
+        let totals = self.totals(dir);
+        self.nodes[dir as usize].settle(totals);
+    }
+
+    /// Move `dir` and every directory above it from contents adding up to
+    /// `before` to contents adding up to `after`.
+    pub(super) fn propagate(&mut self, dir: NodeId, before: Totals, after: Totals) {
+        let mut id = dir;
+        while id != NONE {
+            let node = &mut self.nodes[id as usize];
+            node.shift(before, after);
+            id = node.parent;
+        }
+        self.forget_views();
+    }
+
+    /// The path of `id`, followed up to its top directory.
+    pub(super) fn path_of(&self, id: NodeId) -> PathBuf {
+        let mut below = Vec::new();
+        let mut top = id;
+        while self.nodes[top as usize].parent != NONE {
+            below.push(top);
+            top = self.nodes[top as usize].parent;
+        }
+        let mut path = self
+            .tops
+            .iter()
+            .find(|(_, id)| *id == top)
+            .map(|(path, _)| path.clone())
+            .unwrap_or_default();
+        for &id in below.iter().rev() {
+            path.push(self.name(id));
+        }
+        path
+    }
+
+    /// Every directory that has been read, with its path.
+    pub(super) fn listed(&self) -> Vec<(PathBuf, NodeId)> {
+        let mut listed = Vec::with_capacity(self.dir_count());
+        let mut stack = self.tops.clone();
+        while let Some((path, id)) = stack.pop() {
+            for &child in self.children_of(id) {
+                if self.nodes[child as usize].is_listed() {
+                    stack.push((path.join(self.name(child)), child));
+                }
+            }
+            listed.push((path, id));
+        }
+        listed
+    }
+
+    /// Every directory that has been read, parents before their
+    /// subdirectories.
+    pub(super) fn listed_ids(&self) -> Vec<NodeId> {
+        let mut listed = Vec::with_capacity(self.contents.len());
+        let mut pending: Vec<NodeId> = self.tops.iter().map(|(_, id)| *id).collect();
+        while let Some(dir) = pending.pop() {
+            listed.push(dir);
+            pending.extend(
+                self.children_of(dir)
+                    .iter()
+                    .filter(|&&child| self.nodes[child as usize].is_listed()),
+            );
+        }
+        listed
+    }
+
+    /// Drop the views kept of the tree as it was, after a change.
+    fn forget_views(&mut self) {
+        self.shown = None;
+        self.owned = None;
+    }
+
+    /// Index of the contents of `dir`, which are added empty if it has
+    /// none.
+    fn contents_of(&mut self, dir: NodeId) -> usize {
+        let index = self.nodes[dir as usize].contents;
+        if index != NONE {
+            return index as usize;
+        }
+        let index = match self.free_contents.pop() {
+            Some(index) => index,
+            None => {
+                self.contents.push(Contents::default());
+                u32::try_from(self.contents.len() - 1).expect("fewer than 4 billion directories")
+            }
+        };
+        self.nodes[dir as usize].contents = index;
+        index as usize
+    }
+
+    /// Make `entries` the contents of `dir`, leaving out "..". An entry
+    /// named like one already there, of the same kind, keeps that node and
+    /// so its contents; `adjust` sees the new node, the old one and the
+    /// entry, and may change the sizes. Entries no longer there are dropped
+    /// with everything below them. Returns the directories left unread.
+    pub(super) fn relist(
+        &mut self,
+        dir: NodeId,
+        entries: &[DirEntry],
+        mut adjust: impl FnMut(&mut Node, Option<&Node>, &DirEntry),
+    ) -> Vec<NodeId> {
+        let index = self.contents_of(dir);
+        let old = std::mem::take(&mut self.contents[index].children);
+        let mut known: HashMap<Vec<u8>, NodeId> = old
+            .iter()
+            .map(|&id| (self.name_bytes(id).to_vec(), id))
+            .collect();
+
+        let mut children = Vec::with_capacity(entries.len());
+        let mut unlisted = Vec::new();
+        let mut gone = Vec::new();
+        for entry in entries.iter().filter(|entry| entry.name != "..") {
+            let mut node = self.node_for(dir, entry);
+            let old = match known.remove(entry.name.as_encoded_bytes()) {
+                Some(id) if self.nodes[id as usize].is_dir() == entry.is_dir => Some(id),
+                Some(id) => {
+                    gone.push(id);
+                    None
+                }
+                None => None,
+            };
+            adjust(&mut node, old.map(|id| &self.nodes[id as usize]), entry);
+            let id = match old {
+                Some(id) => {
+                    let kept = &mut self.nodes[id as usize];
+                    node.name = kept.name;
+                    node.contents = kept.contents;
+                    *kept = node;
+                    id
+                }
+                None => self.alloc_node(node, entry),
+            };
+            if entry.is_dir && !self.nodes[id as usize].is_listed() {
+                unlisted.push(id);
+            }
+            children.push(id);
+        }
+        gone.extend(known.into_values());
+        for id in gone {
+            self.free(id);
+        }
+        self.contents[index].children = children;
+        self.forget_views();
+        unlisted
+    }
+
+    /// Copy the contents of `source` in `from`, and everything below, into
+    /// the directory `at`.
+    pub(super) fn graft(&mut self, at: NodeId, from: &DirTree, source: NodeId) {
+        let mut stack = vec![(at, source)];
+        while let Some((at, source)) = stack.pop() {
+            self.set_up(at, true);
+            let index = self.contents_of(at);
+            let mut children = Vec::with_capacity(from.children_of(source).len());
+            for &child in from.children_of(source) {
+                let id = self.alloc(at, &from.entry(child));
+                if from.node(child).is_listed() {
+                    stack.push((id, child));
+                }
+                children.push(id);
+            }
+            self.contents[index].children = children;
+        }
+        self.forget_views();
+    }
+
+    fn node_for(&self, parent: NodeId, entry: &DirEntry) -> Node {
+        let mut flags = 0;
+        if entry.is_dir {
+            flags |= DIR;
+        }
+        if entry.is_temp {
+            flags |= TEMP;
+        }
+        if entry.uid.is_some() {
+            flags |= HAS_UID;
+        }
+        Node {
+            size: entry.size,
+            apparent: entry.apparent_size,
+            items: entry.items,
+            modified: entry.modified,
+            name: 0,
+            parent,
+            contents: NONE,
+            uid: entry.uid.unwrap_or(0),
+            name_len: u16::try_from(entry.name.len()).expect("file names are under 64 KiB"),
+            flags,
+        }
+    }
+
+    fn alloc(&mut self, parent: NodeId, entry: &DirEntry) -> NodeId {
+        let node = self.node_for(parent, entry);
+        self.alloc_node(node, entry)
+    }
+
+    fn alloc_node(&mut self, mut node: Node, entry: &DirEntry) -> NodeId {
+        let name = entry.name.as_encoded_bytes();
+        node.name = self.names.len() as u64;
+        self.names.extend_from_slice(name);
+        let parent = node.parent;
+        let id = match self.free_nodes.pop() {
+            Some(id) => {
+                self.nodes[id as usize] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                NodeId::try_from(self.nodes.len() - 1).expect("fewer than 4 billion entries")
+            }
+        };
+        if entry.is_dir && parent != NONE {
+            self.dirs.insert((parent, name_hash(name)), id);
+        }
+        id
+    }
+
+    /// Drop `id` and everything below it. The caller takes it out of its
+    /// parent's contents.
+    pub(super) fn free(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id as usize];
+            let (parent, index, len) = (node.parent, node.contents, node.name_len);
+            if node.is_dir() {
+                let key = (parent, name_hash(self.name_bytes(id)));
+                if self.dirs.get(&key) == Some(&id) {
+                    self.dirs.remove(&key);
+                }
+            }
+            if index != NONE {
+                let contents = std::mem::take(&mut self.contents[index as usize]);
+                stack.extend(contents.children);
+                self.free_contents.push(index);
+            }
+            let node = &mut self.nodes[id as usize];
+            node.contents = NONE;
+            node.name_len = 0;
+            node.flags = 0;
+            self.dead_names += usize::from(len);
+            self.free_nodes.push(id);
+        }
+        // Names of dropped entries stay until they are half the buffer.
+        if self.dead_names > 1 << 20 && self.dead_names > self.names.len() / 2 {
+            self.compact_names();
+        }
+        self.forget_views();
+    }
+
+    fn compact_names(&mut self) {
+        let mut names = Vec::with_capacity(self.names.len() - self.dead_names);
+        for node in &mut self.nodes {
+            let start = node.name as usize;
+            node.name = names.len() as u64;
+            names.extend_from_slice(&self.names[start..start + usize::from(node.name_len)]);
+        }
+        self.names = names;
+        self.dead_names = 0;
+    }
+
+    /// Entries in the tree, not counting the top directories.
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.free_nodes.len() - self.tops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Directories whose contents the tree holds.
+    pub fn dir_count(&self) -> usize {
+        self.contents.len() - self.free_contents.len()
+    }
+
+    /// Bytes the tree keeps on the heap, leaving out allocator overhead and
+    /// the listing last handed out by [`DirTree::get_children`]. The target
+    /// is at most 64 bytes per entry plus its name, and 64 more for each
+    /// directory read.
+    pub fn memory_usage(&self) -> usize {
+        let lists: usize = self
+            .contents
+            .iter()
+            .map(|contents| contents.children.capacity() * size_of::<NodeId>())
+            .sum();
+        let tops: usize = self
+            .tops
+            .iter()
+            .map(|(path, _)| path.as_os_str().len() + size_of::<(PathBuf, NodeId)>())
+            .sum();
+        self.nodes.capacity() * size_of::<Node>()
+            + self.names.capacity()
+            + self.contents.capacity() * size_of::<Contents>()
+            + lists
+            + self.dirs.capacity() * (size_of::<((NodeId, u64), NodeId)>() + 1)
+            + (self.free_nodes.capacity() + self.free_contents.capacity()) * size_of::<u32>()
+            + tops
+    }
+}
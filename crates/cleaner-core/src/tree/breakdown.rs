@@ -0,0 +1,176 @@
+//! How much of a tree each file extension, and each broad kind of file,
+//! takes up.
+
+use super::DirTree;
+use foldhash::HashMap;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// A broad kind of file, told by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    Media,
+    Archives,
+    BuildOutputs,
+    DiskImages,
+    Logs,
+    Other,
+}
+
+impl Category {
+    /// The category of files with `extension`, given in lowercase without
+    /// the dot.
+    pub fn of(extension: &str) -> Self {
+        match extension {
+            "mp4" | "mkv" | "mov" | "avi" | "webm" | "m4v" | "wmv" | "flv" | "mpg" | "mpeg"
+            | "mp3" | "flac" | "wav" | "aac" | "ogg" | "m4a" | "opus" | "jpg" | "jpeg" | "png"
+            | "gif" | "heic" | "webp" | "tif" | "tiff" | "bmp" | "cr2" | "nef" | "dng" | "psd" => {
+                Self::Media
+            }
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "lz4" | "7z" | "rar" | "jar"
+            | "war" | "ear" | "deb" | "rpm" | "apk" | "whl" | "gem" | "nupkg" | "pkg" | "cab"
+            | "msi" => Self::Archives,
+            "o" | "obj" | "a" | "lib" | "so" | "dylib" | "dll" | "exe" | "class" | "pyc"
+            | "pyo" | "rlib" | "rmeta" | "pdb" | "ilk" | "pch" | "gch" | "wasm" | "dex" => {
+                Self::BuildOutputs
+            }
+            "iso" | "img" | "dmg" | "vmdk" | "vdi" | "vhd" | "vhdx" | "qcow" | "qcow2" | "ova"
+            | "hdd" | "sparseimage" => Self::DiskImages,
+            "log" | "journal" | "trace" => Self::Logs,
+            _ => Self::Other,
+        }
+    }
+
+    /// Lowercase name, as the JSON report spells it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Media => "media",
+            Self::Archives => "archives",
+            Self::BuildOutputs => "build-outputs",
+            Self::DiskImages => "disk-images",
+            Self::Logs => "logs",
+            Self::Other => "other",
+        }
+    }
+
+    /// Name for people.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Media => "Media",
+            Self::Archives => "Archives",
+            Self::BuildOutputs => "Build outputs",
+            Self::DiskImages => "Disk & VM images",
+            Self::Logs => "Logs",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// Files counted, with their disk usage and length.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub files: u64,
+    pub size: u64,
+    pub apparent_size: u64,
+}
+
+impl Tally {
+    pub(super) fn add(&mut self, other: Tally) {
+        self.files = self.files.saturating_add(other.files);
+        self.size = self.size.saturating_add(other.size);
+        self.apparent_size = self.apparent_size.saturating_add(other.apparent_size);
+    }
+
+    /// `apparent_size` when `apparent` is set, otherwise `size`.
+    pub fn size_as(&self, apparent: bool) -> u64 {
+        if apparent {
+            self.apparent_size
+        } else {
+            self.size
+        }
+    }
+}
+
+/// The files below a directory, by extension and by category. Each list is
+/// largest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breakdown {
+    pub total: Tally,
+    /// Only categories with files.
+    pub categories: Vec<(Category, Tally)>,
+    /// Lowercase extensions without the dot; "" for files without one.
+    pub extensions: Vec<(String, Tally)>,
+}
+
+impl Breakdown {
+    /// Order both lists by disk usage, or by length with `apparent`.
+    pub fn sort(&mut self, apparent: bool) {
+        self.categories
+            .sort_by(|(left_kind, left), (right_kind, right)| {
+                (right.size_as(apparent), left_kind).cmp(&(left.size_as(apparent), right_kind))
+            });
+        self.extensions
+            .sort_by(|(left_ext, left), (right_ext, right)| {
+                (right.size_as(apparent), left_ext).cmp(&(left.size_as(apparent), right_ext))
+            });
+    }
+}
+
+impl DirTree {
+    /// Add up the files at any depth below `dir` by extension and category,
+    /// largest on disk first. Empty for a directory the tree has not read.
+    pub fn breakdown(&self, dir: &Path) -> Breakdown {
+        let Some(start) = self.listed_at(dir) else {
+            return Breakdown::default();
+        };
+        let mut extensions: HashMap<String, Tally> = HashMap::default();
+        let mut stack = vec![start];
+        while let Some(dir) = stack.pop() {
+            for &id in self.children_of(dir) {
+                let node = self.node(id);
+                if node.is_dir() {
+                    if node.is_listed() {
+                        stack.push(id);
+                    }
+                    continue;
+                }
+                if node.is_extra_link() {
+                    continue;
+                }
+                let tally = Tally {
+                    files: 1,
+                    size: node.size,
+                    apparent_size: node.apparent,
+                };
+                let extension = Path::new(self.name(id))
+                    .extension()
+                    .map_or(Cow::Borrowed(""), |extension| extension.to_string_lossy());
+                let extension = if extension.bytes().any(|byte| byte.is_ascii_uppercase()) {
+                    Cow::Owned(extension.to_ascii_lowercase())
+                } else {
+                    extension
+                };
+                match extensions.get_mut(extension.as_ref()) {
+                    Some(known) => known.add(tally),
+                    None => {
+                        extensions.insert(extension.into_owned(), tally);
+                    }
+                }
+            }
+        }
+
+        let mut breakdown = Breakdown::default();
+        let mut categories: HashMap<Category, Tally> = HashMap::default();
+        for (extension, tally) in extensions {
+            breakdown.total.add(tally);
+            categories
+                .entry(Category::of(&extension))
+                .or_default()
+                .add(tally);
+            breakdown.extensions.push((extension, tally));
+        }
+        breakdown.categories = categories.into_iter().collect();
+        breakdown.sort(false);
+        breakdown
+    }
+}
@@ -1,18 +1,25 @@
 use super::entry::DirEntry;
+use super::index::Recorder;
 use super::progress::ScanProgress;
-use super::sizing::apply_directory_sizes;
 use super::DirTree;
-use crate::fastwalk;
+use crate::fastwalk::{self, HardLink, MetadataMode, RawEntry};
+use crate::patterns::project::ProjectMatchers;
 use crate::patterns::PatternMatcher;
 use crate::pool::SCAN_POOL;
-use crate::protected::protected_paths_for_root;
-use foldhash::{HashMap, HashMapExt};
-use rayon::prelude::*;
+use crate::protected::ProtectedPaths;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+/// Times and owners need every entry stat'ed. The macOS bulk reader only
+/// returns names, types and sizes, and the per-entry fallback costs too much
+/// on large trees, so macOS trees go without them.
+#[cfg(target_os = "macos")]
+pub(super) const TREE_METADATA: MetadataMode = MetadataMode::WithSizes;
+#[cfg(not(target_os = "macos"))]
+pub(super) const TREE_METADATA: MetadataMode = MetadataMode::WithTimes;
+
 impl DirTree {
     /// Build tree with SINGLE WalkDir pass - maximum performance
     pub fn build_with_progress(
@@ -21,6 +28,33 @@ impl DirTree {
         progress: Arc<ScanProgress>,
         cancelled: Arc<AtomicBool>,
         force: bool,
+    ) -> Self {
+        Self::build(root, matcher, progress, cancelled, force, None)
+    }
+
+    /// Build the tree, letting `recorder` supply and keep directory
+    /// listings when one is given.
+    pub(super) fn build(
+        root: &Path,
+        matcher: &PatternMatcher,
+        progress: Arc<ScanProgress>,
+        cancelled: Arc<AtomicBool>,
+        force: bool,
+        recorder: Option<&Recorder>,
+    ) -> Self {
+        Self::build_within(root, root, matcher, progress, cancelled, force, recorder)
+    }
+
+    /// Build the tree of `root`, a directory inside the tree of `scope`:
+    /// protected paths and the filesystem boundary are those of `scope`.
+    pub(super) fn build_within(
+        root: &Path,
+        scope: &Path,
+        matcher: &PatternMatcher,
+        progress: Arc<ScanProgress>,
+        cancelled: Arc<AtomicBool>,
+        force: bool,
+        recorder: Option<&Recorder>,
     ) -> Self {
         #[cfg(test)]
         let profile_started = std::time::Instant::now();
@@ -46,9 +80,13 @@ impl DirTree {
         let root_clone = root.to_path_buf();
 
         // Protected directories (NEVER auto-clean inside these, but allow scanning and manual TUI deletion)
-        let protected_paths = protected_paths_for_root(root, force);
+        let protected = ProtectedPaths::new(scope, force, &matcher.config().protect);
+        let device = matcher.config().root_device(scope);
 
-        let skip_check = Arc::new(move |path: &Path| -> bool {
+        let skip_check = Arc::new(move |path: &Path, entry: &RawEntry| -> bool {
+            if device.is_some_and(|device| entry.device_at(path) != Some(device)) {
+                return true;
+            }
             if let Some(ref docker) = docker_path {
                 if path.starts_with(docker) {
                     return true;
@@ -77,100 +115,84 @@ impl DirTree {
             progress_clone.bytes.fetch_add(bytes, Ordering::Relaxed);
         });
 
-        // Walk and index each directory in one pass so RawEntry collections are
-        // consumed before the next directory is retained.
-        let walk = fastwalk::walk_parallel_mapped(
+        // Hard-linked files are rare; collect them so each is sized once.
+        let count_links = matcher.config().count_links;
+        let projects = ProjectMatchers::new(matcher, root, &progress.errors);
+
+        let mapper = |dir_path: &Path, entries: Vec<RawEntry>| {
+            if let Some(recorder) = recorder {
+                recorder.record(dir_path, &entries);
+            }
+            // Positions among the entries the listing keeps, which leaves
+            // out symlinks.
+            let linked: Vec<(usize, HardLink)> = if count_links {
+                Vec::new()
+            } else {
+                entries
+                    .iter()
+                    .filter(|entry| !entry.is_symlink)
+                    .enumerate()
+                    .filter_map(|(index, entry)| Some((index, entry.hard_link?)))
+                    .collect()
+            };
+            let project = projects.matcher_in(dir_path, &entries);
+            let matcher = project.as_deref().unwrap_or(matcher);
+            let entries = Arc::new(listing(dir_path, entries, &protected, matcher));
+            progress.offer_listing(dir_path, &entries);
+            (entries, linked)
+        };
+        // Pack each listing into the tree as the walk delivers it, so no
+        // listing outlives its directory's turn. Entry sorting is deferred
+        // until a directory is opened, avoiding work for directories never
+        // viewed.
+        let reuse = |dir: &Path| recorder.and_then(|recorder| recorder.reuse(dir));
+        let mut tree = Self::empty();
+        let mut hard_links = Vec::new();
+        let errors = fastwalk::walk_parallel_each(
             root.to_path_buf(),
             &SCAN_POOL,
+            TREE_METADATA,
             skip_check,
             Some(progress_cb),
-            &|dir_path, entries| {
-                let dir_is_protected = protected_paths
-                    .iter()
-                    .any(|protected| dir_path.starts_with(protected));
-                Arc::new(
-                    entries
-                        .into_iter()
-                        .filter(|entry| !entry.is_symlink)
-                        .map(|entry| {
-                            let entry_is_protected = dir_is_protected
-                                || (entry.is_dir
-                                    && protected_paths.iter().any(|protected| {
-                                        dir_path.join(&entry.name).starts_with(protected)
-                                    }));
-                            let is_temp = if entry_is_protected {
-                                false
-                            } else if entry.is_dir {
-                                matcher.is_temp_directory(&entry.name)
-                            } else {
-                                matcher.is_temp_file(&entry.name)
-                            };
-                            DirEntry {
-                                name: entry.name,
-                                size: entry.size,
-                                is_dir: entry.is_dir,
-                                is_temp,
-                            }
-                        })
-                        .collect(),
-                )
+            recorder.is_some().then_some(&reuse),
+            &mapper,
+            &mut |dir, (entries, linked): (Arc<Vec<DirEntry>>, Vec<(usize, HardLink)>)| {
+                let id = tree.place_listing(root, &dir, &entries);
+                let children = tree.children_of(id);
+                for (index, link) in linked {
+                    let child = children[index];
+                    hard_links.push((dir.join(tree.name(child)), child, link));
+                }
             },
         );
         #[cfg(test)]
         let scan_elapsed = profile_started.elapsed();
-        progress.errors.fetch_add(walk.errors, Ordering::Relaxed);
-        let mut children: HashMap<PathBuf, Arc<Vec<DirEntry>>> = walk.entries;
+        progress.errors.fetch_add(errors, Ordering::Relaxed);
 
         if cancelled.load(Ordering::Relaxed) {
             progress.done.store(true, Ordering::Release);
-            return Self::from_shared_children(HashMap::new());
+            return Self::empty();
         }
 
-        progress.begin_stage(1, children.len());
-        progress
-            .stage_current
-            .store(children.len(), Ordering::Relaxed);
+        let directories = tree.dir_count();
+        progress.begin_stage(1, directories);
+        progress.stage_current.store(directories, Ordering::Relaxed);
 
-        if cancelled.load(Ordering::Relaxed) {
-            progress.done.store(true, Ordering::Release);
-            return Self::from_shared_children(children);
-        }
-
-        // 3. Compute sizes in place.
-        progress.begin_stage(2, children.len());
-        apply_directory_sizes(root, &mut children, &progress, &cancelled);
+        // Compute sizes in place.
+        progress.begin_stage(2, directories);
+        tree.dedup_hard_links(hard_links);
+        let sized = tree.size_directories(&progress, &cancelled);
         #[cfg(test)]
         let sizing_elapsed = profile_started.elapsed().saturating_sub(scan_elapsed);
 
-        if cancelled.load(Ordering::Relaxed) {
+        if !sized {
             progress.done.store(true, Ordering::Release);
-            return Self::from_shared_children(children);
+            return tree;
         }
 
-        // Add navigation in parallel. Entry sorting is deferred until a
-        // directory is opened, avoiding work for directories never viewed.
-        progress.begin_stage(3, children.len());
-        let root_clone = root.to_path_buf();
-        children.par_iter_mut().for_each(|(dir_path, entries)| {
-            if cancelled.load(Ordering::Relaxed) {
-                return;
-            }
-            // Add navigation
-            if dir_path != &root_clone && dir_path.parent().is_some() {
-                Arc::make_mut(entries).insert(
-                    0,
-                    DirEntry {
-                        name: OsString::from(".."),
-                        size: 0,
-                        is_dir: true,
-                        is_temp: false,
-                    },
-                );
-            }
-        });
-        progress
-            .stage_current
-            .store(children.len(), Ordering::Relaxed);
+        progress.begin_stage(3, directories);
+        tree.shrink_to_fit();
+        progress.stage_current.store(directories, Ordering::Relaxed);
 
         progress.done.store(true, Ordering::Release);
         #[cfg(test)]
@@ -183,6 +205,69 @@ impl DirTree {
                     .saturating_sub(sizing_elapsed)
             );
         }
-        Self::from_shared_children(children)
+        tree
+    }
+}
+
+/// The tree's entries for the listing of `dir_path`, with symlinks left out
+/// and each entry flagged as temporary by `matcher` unless protected.
+pub(super) fn listing(
+    dir_path: &Path,
+    entries: Vec<RawEntry>,
+    protected: &ProtectedPaths,
+    matcher: &PatternMatcher,
+) -> Vec<DirEntry> {
+    let dir_is_protected = protected.contains(dir_path) || matcher.is_excluded(dir_path);
+    // Marker rules look at siblings, so decide every flag before the
+    // listing is consumed.
+    let temp_flags: Vec<bool> = entries
+        .iter()
+        .map(|entry| {
+            // Directories are checked on their own: `[protect] remove` can
+            // open one inside a protected parent.
+            let entry_is_protected = if entry.is_dir {
+                let path = dir_path.join(&entry.name);
+                protected.contains(&path) || matcher.is_excluded(&path)
+            } else {
+                dir_is_protected
+                    || (matcher.has_excludes() && matcher.is_excluded(&dir_path.join(&entry.name)))
+            };
+            if entry_is_protected {
+                false
+            } else if entry.is_dir {
+                matcher.is_temp_directory_in(&entry.name, &entries)
+            } else {
+                matcher.is_temp_file(&entry.name)
+            }
+        })
+        .collect();
+    entries
+        .into_iter()
+        .zip(temp_flags)
+        .filter(|(entry, _)| !entry.is_symlink)
+        .map(|(entry, is_temp)| DirEntry {
+            name: entry.name,
+            size: entry.size,
+            apparent_size: entry.apparent_size,
+            modified: entry.modified,
+            items: 1,
+            uid: entry.uid,
+            is_dir: entry.is_dir,
+            is_temp,
+        })
+        .collect()
+}
+
+/// The `..` entry that leads back up from every directory but the root.
+pub(super) fn up_entry() -> DirEntry {
+    DirEntry {
+        name: OsString::from(".."),
+        size: 0,
+        apparent_size: 0,
+        modified: 0,
+        items: 0,
+        uid: None,
+        is_dir: true,
+        is_temp: false,
     }
 }
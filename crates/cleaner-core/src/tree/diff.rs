@@ -0,0 +1,184 @@
+//! Growth between two trees of the same root, such as the previous and the
+//! latest [`super::ScanIndex`].
+
+use super::arena::Node;
+use super::{DirEntry, DirTree};
+use foldhash::{HashMap, HashMapExt};
+use std::path::{Path, PathBuf};
+
+/// Disk usage and entry count of one entry in one tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Measure {
+    pub size: u64,
+    pub items: u64,
+}
+
+impl From<&Node> for Measure {
+    fn from(node: &Node) -> Self {
+        Self {
+            size: node.size,
+            items: node.items,
+        }
+    }
+}
+
+impl From<&DirEntry> for Measure {
+    fn from(entry: &DirEntry) -> Self {
+        Self {
+            size: entry.size,
+            items: entry.items,
+        }
+    }
+}
+
+/// How one directory or file changed. A side is `None` where it does not
+/// exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub path: PathBuf,
+    pub old: Option<Measure>,
+    pub new: Option<Measure>,
+}
+
+impl Change {
+    /// Bytes gained; negative when the directory shrank.
+    pub fn size_delta(&self) -> i64 {
+        delta(self.old.map(|m| m.size), self.new.map(|m| m.size))
+    }
+
+    /// Entries added minus entries removed.
+    pub fn items_delta(&self) -> i64 {
+        delta(self.old.map(|m| m.items), self.new.map(|m| m.items))
+    }
+}
+
+fn delta(old: Option<u64>, new: Option<u64>) -> i64 {
+    let (old, new) = (old.unwrap_or(0), new.unwrap_or(0));
+    if new >= old {
+        i64::try_from(new - old).unwrap_or(i64::MAX)
+    } else {
+        i64::try_from(old - new).map_or(i64::MIN, |shrink| -shrink)
+    }
+}
+
+/// Every directory at or below `root` whose size or entry count differs
+/// between `old` and `new`, largest growth first.
+pub fn diff_dirs(old: &DirTree, new: &DirTree, root: &Path) -> Vec<Change> {
+    let mut old = measures(old, root);
+    let mut changes: Vec<Change> = measures(new, root)
+        .into_iter()
+        .map(|(path, new)| Change {
+            old: old.remove(&path),
+            path,
+            new: Some(new),
+        })
+        .collect();
+    changes.extend(old.into_iter().map(|(path, old)| Change {
+        path,
+        old: Some(old),
+        new: None,
+    }));
+    changes.retain(|change| change.old != change.new);
+    changes.sort_unstable_by(|left, right| {
+        (right.size_delta(), right.items_delta(), &left.path).cmp(&(
+            left.size_delta(),
+            left.items_delta(),
+            &right.path,
+        ))
+    });
+    changes
+}
+
+/// Size and entry count of every directory in `tree`, from its entry in
+/// the parent's listing; the root's are summed from its own.
+fn measures(tree: &DirTree, root: &Path) -> HashMap<PathBuf, Measure> {
+    let listed = tree.listed();
+    let mut measures = HashMap::with_capacity(listed.len());
+    for (dir, id) in listed {
+        for &child in tree.children_of(id) {
+            if tree.node(child).is_dir() {
+                measures.insert(dir.join(tree.name(child)), Measure::from(tree.node(child)));
+            }
+        }
+    }
+    if let Some(id) = tree.listed_at(root) {
+        let root_measure =
+            tree.children_of(id)
+                .iter()
+                .fold(Measure { size: 0, items: 1 }, |total, &child| Measure {
+                    size: total.size.saturating_add(tree.node(child).size),
+                    items: total.items.saturating_add(tree.node(child).items),
+                });
+        measures.insert(root.to_path_buf(), root_measure);
+    }
+    measures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::DirEntry;
+
+    /// Name, size, items and whether it is a directory.
+    type Row<'a> = (&'a str, u64, u64, bool);
+
+    fn tree(listings: &[(&str, &[Row])]) -> DirTree {
+        let mut children = HashMap::new();
+        for (dir, entries) in listings {
+            let entries = entries
+                .iter()
+                .map(|&(name, size, items, is_dir)| DirEntry {
+                    items,
+                    ..DirEntry::new(name, size, is_dir, false)
+                })
+                .collect();
+            children.insert(PathBuf::from(dir), entries);
+        }
+        DirTree::from_children(children)
+    }
+
+    #[test]
+    fn directories_are_listed_by_growth_including_new_and_removed_ones() {
+        let old = tree(&[
+            ("/r", &[("logs", 10, 2, true), ("old", 5, 2, true)]),
+            ("/r/logs", &[("..", 0, 0, true), ("a.log", 10, 1, false)]),
+            ("/r/old", &[("..", 0, 0, true), ("x", 5, 1, false)]),
+        ]);
+        let new = tree(&[
+            ("/r", &[("logs", 110, 3, true), ("cache", 20, 4, true)]),
+            (
+                "/r/logs",
+                &[
+                    ("..", 0, 0, true),
+                    ("a.log", 10, 1, false),
+                    ("b.log", 100, 1, false),
+                ],
+            ),
+            ("/r/cache", &[("..", 0, 0, true), ("c", 20, 3, false)]),
+        ]);
+
+        let changes = diff_dirs(&old, &new, Path::new("/r"));
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.path.to_string_lossy().into_owned(),
+                    change.size_delta(),
+                    change.items_delta(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("/r".to_string(), 115, 3),
+                ("/r/logs".to_string(), 100, 1),
+                ("/r/cache".to_string(), 20, 4),
+                ("/r/old".to_string(), -5, -2),
+            ]
+        );
+        assert_eq!(changes[2].old, None);
+        assert_eq!(changes[3].new, None);
+        assert!(diff_dirs(&new, &new, Path::new("/r")).is_empty());
+    }
+}
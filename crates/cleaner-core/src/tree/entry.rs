@@ -3,7 +3,18 @@ use std::ffi::OsString;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DirEntry {
     pub name: OsString,
+    /// Space taken on disk; for a directory, the sum over its contents.
     pub size: u64,
+    /// Length in bytes, summed the same way as `size`.
+    pub apparent_size: u64,
+    /// Modification time in seconds since the Unix epoch; for a directory,
+    /// the newest of its own and its contents'. 0 when unknown.
+    pub modified: i64,
+    /// Entries at or below this one, itself included: the inodes it
+    /// would free. 1 for a file.
+    pub items: u64,
+    /// Owner's user ID, when the walk could read it.
+    pub uid: Option<u32>,
     pub is_dir: bool,
     pub is_temp: bool,
 }
@@ -13,8 +24,21 @@ impl DirEntry {
         Self {
             name: name.into(),
             size,
+            apparent_size: size,
+            modified: 0,
+            items: 1,
+            uid: None,
             is_dir,
             is_temp,
         }
     }
+
+    /// `apparent_size` when `apparent` is set, otherwise `size`.
+    pub fn size_as(&self, apparent: bool) -> u64 {
+        if apparent {
+            self.apparent_size
+        } else {
+            self.size
+        }
+    }
 }
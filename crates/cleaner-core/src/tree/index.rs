@@ -0,0 +1,757 @@
+//! Persistent scan index
+//!
+//! A [`ScanIndex`] keeps every directory's raw listing from a tree walk,
+//! stamped with the directory's own modification time. The next walk reads
+//! only the directories whose time moved and takes the rest from the index.
+//! Temp flags, sizes and hard-link counting are recomputed from the listings
+//! either way, so a changed config applies at once.
+//!
+//! A directory's time changes when an entry is added, removed or renamed in
+//! it, not when a file in it is rewritten, so a file that grew in place keeps
+//! its indexed size until its directory changes or the index is rebuilt.
+//!
+//! On disk the index is a version header, the root, then one record per
+//! directory, with LEB128 integers and length-prefixed names.
+
+use super::progress::ScanProgress;
+use super::DirTree;
+use crate::fastwalk::{HardLink, RawEntry};
+use crate::patterns::PatternMatcher;
+use foldhash::{HashMap, HashMapExt};
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File signature; the last byte is the format version.
+const MAGIC: &[u8; 8] = b"CLNIDX\x00\x01";
+
+/// A directory changed this close to the walk may change again within the
+/// filesystem's timestamp granularity without its time moving, so its
+/// listing is never reused.
+const RACY_NANOS: i64 = 2_000_000_000;
+
+/// Stored for a directory whose listing must be read again next time.
+const UNTRUSTED: i64 = i64::MIN;
+
+const IS_DIR: u8 = 1;
+const IS_SYMLINK: u8 = 2;
+const HAS_UID: u8 = 4;
+const HAS_HARD_LINK: u8 = 8;
+const HAS_APPARENT: u8 = 16;
+
+/// Every directory listing of one tree walk.
+pub struct ScanIndex {
+    root: PathBuf,
+    dirs: HashMap<PathBuf, Listing>,
+    /// When the index was written, for one loaded from disk.
+    saved: Option<SystemTime>,
+}
+
+pub(super) struct Listing {
+    /// The directory's own modification time in nanoseconds since the Unix
+    /// epoch, read before its entries; [`UNTRUSTED`] when it cannot be.
+    modified: i64,
+    /// How many entries `encoded` holds.
+    len: u64,
+    /// The entries as they are written to disk, which takes a fraction of
+    /// the memory of the entries themselves.
+    encoded: Vec<u8>,
+}
+
+impl Listing {
+    fn new(modified: i64, entries: &[RawEntry]) -> Self {
+        let mut encoded = Vec::with_capacity(entries.len().saturating_mul(24));
+        for entry in entries {
+            put_entry(&mut encoded, entry);
+        }
+        Self {
+            modified,
+            len: entries.len() as u64,
+            encoded,
+        }
+    }
+
+    /// The entries, which [`parse`] or [`Listing::new`] made sure decode.
+    fn entries(&self) -> Vec<RawEntry> {
+        let mut reader = Reader {
+            data: &self.encoded,
+        };
+        (0..self.len).map_while(|_| reader.entry()).collect()
+    }
+}
+
+impl ScanIndex {
+    /// Where the index for `root` is kept: a file named after the canonical
+    /// root in `$XDG_CACHE_HOME/cleaner/index`, or `~/.cache/cleaner/index`,
+    /// so `..` and symlinks lead to the same index. A root that cannot be
+    /// resolved is only made absolute.
+    pub fn path_for(root: &Path) -> Result<PathBuf, String> {
+        let cache_home = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".cache")))
+            .ok_or_else(|| {
+                "Cannot locate the index: neither XDG_CACHE_HOME nor HOME is set".to_string()
+            })?;
+        let root = root
+            .canonicalize()
+            .or_else(|_| std::path::absolute(root))
+            .unwrap_or_else(|_| root.to_path_buf());
+        let name = format!("{:016x}.idx", fnv1a(&os_bytes(root.as_os_str())));
+        Ok(cache_home.join("cleaner").join("index").join(name))
+    }
+
+    /// Where [`ScanIndex::save`] keeps the index it replaces at `path`.
+    pub fn previous_path(path: &Path) -> PathBuf {
+        path.with_extension("prev.idx")
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn saved(&self) -> Option<SystemTime> {
+        self.saved
+    }
+
+    /// Read the index at `path`, which must have been recorded for `root`.
+    pub fn load(path: &Path, root: &Path) -> Result<Self, String> {
+        let index = Self::read(path)?;
+        if index.root != root {
+            return Err(format!(
+                "Index {} is for {}, not {}",
+                path.display(),
+                index.root.display(),
+                root.display()
+            ));
+        }
+        Ok(index)
+    }
+
+    /// Read the index at `path`, whatever root it was recorded for.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let read = |e: std::io::Error| format!("Cannot read index {}: {e}", path.display());
+        let data = std::fs::read(path).map_err(read)?;
+        let saved = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let (root, dirs) = parse(&data).ok_or_else(|| {
+            format!(
+                "Index {} is damaged or from another version",
+                path.display()
+            )
+        })?;
+        Ok(Self { root, dirs, saved })
+    }
+
+    /// Write the index to `path` through a rename, so a concurrent reader
+    /// never sees half a file. With `rotate`, the index it replaces moves to
+    /// [`ScanIndex::previous_path`]; a session rotates on its first save
+    /// only, so the previous index stays that of an earlier session however
+    /// often this one saves.
+    pub fn save(&self, path: &Path, rotate: bool) -> Result<(), String> {
+        let mut out = Vec::with_capacity(self.dirs.len().saturating_mul(256));
+        out.extend_from_slice(MAGIC);
+        put_bytes(&mut out, &os_bytes(self.root.as_os_str()));
+        put_varint(&mut out, self.dirs.len() as u64);
+        for (dir, listing) in &self.dirs {
+            let relative = dir.strip_prefix(&self.root).unwrap_or(dir);
+            put_bytes(&mut out, &os_bytes(relative.as_os_str()));
+            put_varint(&mut out, listing.modified as u64);
+            put_varint(&mut out, listing.len);
+            out.extend_from_slice(&listing.encoded);
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Cannot create {}: {e}", parent.display()))?;
+        }
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(format!(".{}.tmp", std::process::id()));
+        let temp = PathBuf::from(temp);
+        write_private(&temp, &out)
+            .map_err(|e| format!("Cannot write index {}: {e}", path.display()))
+            .and_then(|()| {
+                if rotate {
+                    Self::rotate(path)?;
+                }
+                std::fs::rename(&temp, path)
+                    .map_err(|e| format!("Cannot write index {}: {e}", path.display()))
+            })
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&temp);
+            })
+    }
+
+    /// Move the index at `path` to [`ScanIndex::previous_path`]. The first
+    /// save has nothing to move.
+    fn rotate(path: &Path) -> Result<(), String> {
+        let previous = Self::previous_path(path);
+        match std::fs::rename(path, &previous) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!(
+                "Cannot keep the previous index as {}: {e}",
+                previous.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DirTree {
+    /// [`DirTree::build_with_progress`], taking the listing of every
+    /// directory that has not changed since `previous` was recorded from
+    /// `previous` instead of from disk. Also returns the index of this walk,
+    /// or `None` when the build was cancelled.
+    pub fn build_indexed(
+        root: &Path,
+        matcher: &PatternMatcher,
+        progress: Arc<ScanProgress>,
+        cancelled: Arc<AtomicBool>,
+        force: bool,
+        previous: Option<&ScanIndex>,
+    ) -> (Self, Option<ScanIndex>) {
+        let recorder = Recorder::Refresh {
+            previous: previous.filter(|index| index.root == root),
+            started: now_nanos(),
+            modified: Mutex::new(HashMap::new()),
+            listings: Mutex::new(HashMap::new()),
+        };
+        let tree = Self::build(
+            root,
+            matcher,
+            progress,
+            Arc::clone(&cancelled),
+            force,
+            Some(&recorder),
+        );
+        if cancelled.load(Ordering::Relaxed) {
+            return (tree, None);
+        }
+        (tree, recorder.into_index(root))
+    }
+
+    /// The tree `index` describes, built without reading the directories it
+    /// lists. It is only as current as the index; [`DirTree::build_indexed`]
+    /// brings it up to date.
+    pub fn build_from_index(
+        index: &ScanIndex,
+        matcher: &PatternMatcher,
+        progress: Arc<ScanProgress>,
+        cancelled: Arc<AtomicBool>,
+        force: bool,
+    ) -> Self {
+        Self::build(
+            &index.root,
+            matcher,
+            progress,
+            cancelled,
+            force,
+            Some(&Recorder::Trust(index)),
+        )
+    }
+}
+
+/// Supplies and keeps directory listings for one tree walk.
+pub(super) enum Recorder<'a> {
+    /// Take every listing the index has, without looking at disk.
+    Trust(&'a ScanIndex),
+    /// Reuse the listings of `previous` whose directory has not changed, and
+    /// keep every listing the walk sees. Reused files are not stat'ed again:
+    /// that would cost as much as the walk the index saves.
+    Refresh {
+        previous: Option<&'a ScanIndex>,
+        /// When the walk began, in nanoseconds since the Unix epoch.
+        started: i64,
+        /// Directory times read before their listings, until recorded.
+        modified: Mutex<HashMap<PathBuf, i64>>,
+        listings: Mutex<HashMap<PathBuf, Listing>>,
+    },
+}
+
+impl Recorder<'_> {
+    /// The listing of `dir` if it need not be read; called before reading.
+    pub(super) fn reuse(&self, dir: &Path) -> Option<Vec<RawEntry>> {
+        match self {
+            // A directory the index lacks could not be read back then. It
+            // stays empty rather than being read now, so a tree built from
+            // an old index, as `cleaner diff` builds two, shows that scan
+            // alone and never what is on disk today.
+            Self::Trust(index) => Some(index.dirs.get(dir).map_or_else(Vec::new, Listing::entries)),
+            Self::Refresh {
+                previous,
+                started,
+                modified,
+                ..
+            } => {
+                let current = modified_nanos(dir)?;
+                let trusted = if current.saturating_add(RACY_NANOS) < *started {
+                    current
+                } else {
+                    UNTRUSTED
+                };
+                lock(modified).insert(dir.to_path_buf(), trusted);
+                (*previous)?
+                    .dirs
+                    .get(dir)
+                    .filter(|listing| listing.modified != UNTRUSTED && listing.modified == trusted)
+                    .map(Listing::entries)
+            }
+        }
+    }
+
+    /// Keep the listing the walk settled on for `dir`, encoded as the
+    /// index stores it.
+    pub(super) fn record(&self, dir: &Path, entries: &[RawEntry]) {
+        if let Self::Refresh {
+            modified, listings, ..
+        } = self
+        {
+            let trusted = lock(modified).remove(dir).unwrap_or(UNTRUSTED);
+            let listing = Listing::new(trusted, entries);
+            lock(listings).insert(dir.to_path_buf(), listing);
+        }
+    }
+
+    fn into_index(self, root: &Path) -> Option<ScanIndex> {
+        let Self::Refresh { listings, .. } = self else {
+            return None;
+        };
+        let dirs = listings
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Some(ScanIndex {
+            root: root.to_path_buf(),
+            dirs,
+            saved: None,
+        })
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn nanos_since_epoch(time: SystemTime) -> Option<i64> {
+    let elapsed = time.duration_since(UNIX_EPOCH).ok()?;
+    i64::try_from(elapsed.as_nanos()).ok()
+}
+
+fn now_nanos() -> i64 {
+    nanos_since_epoch(SystemTime::now()).unwrap_or(i64::MAX)
+}
+
+fn modified_nanos(path: &Path) -> Option<i64> {
+    let modified = std::fs::symlink_metadata(path).ok()?.modified().ok()?;
+    nanos_since_epoch(modified)
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn put_entry(out: &mut Vec<u8>, entry: &RawEntry) {
+    let mut flags = 0;
+    for (set, flag) in [
+        (entry.is_dir, IS_DIR),
+        (entry.is_symlink, IS_SYMLINK),
+        (entry.uid.is_some(), HAS_UID),
+        (entry.hard_link.is_some(), HAS_HARD_LINK),
+        (entry.apparent_size != entry.size, HAS_APPARENT),
+    ] {
+        if set {
+            flags |= flag;
+        }
+    }
+    put_bytes(out, &os_bytes(&entry.name));
+    out.push(flags);
+    put_varint(out, entry.size);
+    if flags & HAS_APPARENT != 0 {
+        put_varint(out, entry.apparent_size);
+    }
+    put_varint(out, entry.modified as u64);
+    if let Some(uid) = entry.uid {
+        put_varint(out, u64::from(uid));
+    }
+    if let Some(link) = entry.hard_link {
+        put_varint(out, link.device);
+        put_varint(out, link.inode);
+        put_varint(out, link.links);
+    }
+}
+
+type Parsed = (PathBuf, HashMap<PathBuf, Listing>);
+
+/// `None` for anything that is not a complete index of this version.
+fn parse(data: &[u8]) -> Option<Parsed> {
+    let mut reader = Reader { data };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return None;
+    }
+    let root = PathBuf::from(reader.os_string()?);
+    let count = reader.varint()?;
+    let mut dirs = HashMap::with_capacity(count.min(1 << 20) as usize);
+    for _ in 0..count {
+        let relative = reader.os_string()?;
+        let dir = if relative.is_empty() {
+            root.clone()
+        } else {
+            root.join(relative)
+        };
+        let modified = reader.varint()? as i64;
+        let len = reader.varint()?;
+        let start = reader.data;
+        for _ in 0..len {
+            reader.entry()?;
+        }
+        let encoded = start[..start.len() - reader.data.len()].to_vec();
+        dirs.insert(
+            dir,
+            Listing {
+                modified,
+                len,
+                encoded,
+            },
+        );
+    }
+    reader.data.is_empty().then_some((root, dirs))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.data.len() {
+            return None;
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(taken)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.take(1)?.first()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn os_string(&mut self) -> Option<OsString> {
+        let len = usize::try_from(self.varint()?).ok()?;
+        os_string_from(self.take(len)?)
+    }
+
+    fn entry(&mut self) -> Option<RawEntry> {
+        let name = self.os_string()?;
+        let flags = *self.take(1)?.first()?;
+        let size = self.varint()?;
+        let apparent_size = if flags & HAS_APPARENT != 0 {
+            self.varint()?
+        } else {
+            size
+        };
+        let modified = self.varint()? as i64;
+        let uid = if flags & HAS_UID != 0 {
+            Some(u32::try_from(self.varint()?).ok()?)
+        } else {
+            None
+        };
+        let hard_link = if flags & HAS_HARD_LINK != 0 {
+            HardLink::new(self.varint()?, self.varint()?, self.varint()?)
+        } else {
+            None
+        };
+        Some(RawEntry {
+            name,
+            size,
+            apparent_size,
+            is_dir: flags & IS_DIR != 0,
+            is_symlink: flags & IS_SYMLINK != 0,
+            modified,
+            accessed: 0,
+            uid,
+            hard_link,
+            device: None,
+        })
+    }
+}
+
+/// Create `path` readable by its owner only, since an index lists every
+/// file name below its root.
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(data)
+}
+
+#[cfg(unix)]
+fn os_bytes(value: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(value.as_bytes())
+}
+
+#[cfg(unix)]
+fn os_string_from(bytes: &[u8]) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(OsString::from_vec(bytes.to_vec()))
+}
+
+#[cfg(windows)]
+fn os_bytes(value: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::windows::ffi::OsStrExt;
+    Cow::Owned(
+        value
+            .encode_wide()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect(),
+    )
+}
+
+#[cfg(windows)]
+fn os_string_from(bytes: &[u8]) -> Option<OsString> {
+    use std::os::windows::ffi::OsStringExt;
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Some(OsString::from_wide(&wide))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn os_bytes(value: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Owned(value.to_string_lossy().into_owned().into_bytes())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn os_string_from(bytes: &[u8]) -> Option<OsString> {
+    String::from_utf8(bytes.to_vec()).ok().map(OsString::from)
+}
+
+/// FNV-1a, which unlike the hashers in use here is stable across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_support::TempDir;
+    use crate::tree::SortOrder;
+
+    fn matcher() -> PatternMatcher {
+        PatternMatcher::new(Arc::new(Config::empty()))
+    }
+
+    fn indexed(root: &Path, previous: Option<&ScanIndex>) -> (DirTree, ScanIndex) {
+        let (tree, index) = DirTree::build_indexed(
+            root,
+            &matcher(),
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+            previous,
+        );
+        (tree, index.expect("build was not cancelled"))
+    }
+
+    fn names(tree: &mut DirTree, dir: &Path) -> Vec<String> {
+        tree.get_children(dir, SortOrder::Name)
+            .iter()
+            .filter(|entry| entry.name != "..")
+            .map(|entry| entry.name.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn index_round_trips_and_refuses_other_roots() {
+        let temp = TempDir::new("index-round-trip");
+        let root = temp.join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::create_dir(root.join("logs")).unwrap();
+        std::fs::write(root.join("logs/app.log"), b"12345").unwrap();
+        std::fs::write(root.join("notes.txt"), b"1").unwrap();
+        let (_, index) = indexed(&root, None);
+
+        let path = temp.join("cache/index.idx");
+        index.save(&path, true).unwrap();
+        let loaded = ScanIndex::load(&path, &root).unwrap();
+        assert_eq!(loaded.root(), root);
+        assert_eq!(loaded.dirs.len(), 2);
+        let logs = &loaded.dirs[&root.join("logs")];
+        assert_eq!(logs.entries()[0].name, "app.log");
+        assert_eq!(logs.entries()[0].apparent_size, 5);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(loaded.saved().is_some());
+        assert!(!ScanIndex::previous_path(&path).exists());
+        loaded.save(&path, true).unwrap();
+        let previous = ScanIndex::load(&ScanIndex::previous_path(&path), &root).unwrap();
+        assert_eq!(previous.dirs.len(), 2);
+
+        let error = ScanIndex::load(&path, &temp.join("elsewhere"))
+            .err()
+            .unwrap();
+        assert!(error.contains("is for"), "{error}");
+        let data = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &data[..data.len() - 1]).unwrap();
+        let error = ScanIndex::load(&path, &root).err().unwrap();
+        assert!(error.contains("damaged"), "{error}");
+    }
+
+    #[test]
+    fn only_rotating_saves_replace_the_previous_index() {
+        let temp = TempDir::new("index-rotate");
+        let root = temp.join("root");
+        std::fs::create_dir(&root).unwrap();
+        let path = temp.join("index.idx");
+        let previous = ScanIndex::previous_path(&path);
+        let (_, first) = indexed(&root, None);
+        first.save(&path, true).unwrap();
+        std::fs::write(root.join("a"), b"1").unwrap();
+        let (_, second) = indexed(&root, None);
+        second.save(&path, true).unwrap();
+        std::fs::write(root.join("b"), b"1").unwrap();
+        let (_, third) = indexed(&root, None);
+        third.save(&path, false).unwrap();
+
+        let count = |path: &Path| ScanIndex::read(path).unwrap().dirs[&root].len;
+        assert_eq!((count(&previous), count(&path)), (0, 2));
+
+        // A previous index that cannot be replaced fails the save.
+        std::fs::remove_file(&previous).unwrap();
+        std::fs::create_dir_all(previous.join("blocker")).unwrap();
+        let error = third.save(&path, true).unwrap_err();
+        assert!(error.contains("previous index"), "{error}");
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn index_paths_are_per_root() {
+        let first = ScanIndex::path_for(Path::new("/data")).unwrap();
+        let second = ScanIndex::path_for(Path::new("/data/other")).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.extension().unwrap(), "idx");
+
+        let temp = TempDir::new("index-paths");
+        temp.mkdir("real/sub");
+        let canonical = ScanIndex::path_for(&temp.join("real")).unwrap();
+        assert_eq!(
+            ScanIndex::path_for(&temp.join("real/sub/..")).unwrap(),
+            canonical
+        );
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(temp.join("real"), temp.join("link")).unwrap();
+            assert_eq!(ScanIndex::path_for(&temp.join("link")).unwrap(), canonical);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refresh_reads_only_directories_whose_time_moved() {
+        let temp = TempDir::new("index-refresh");
+        let root = temp.join("root");
+        for dir in ["stable", "busy"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("a"), b"12345").unwrap();
+        }
+        let an_hour_ago = SystemTime::now() - std::time::Duration::from_secs(3600);
+        for dir in [root.join("stable"), root.join("busy"), root.clone()] {
+            std::fs::File::open(&dir)
+                .unwrap()
+                .set_modified(an_hour_ago)
+                .unwrap();
+        }
+        let (_, mut index) = indexed(&root, None);
+
+        // Mark the stable listing so a reuse is visible, then change busy.
+        let stable = index.dirs.get_mut(&root.join("stable")).unwrap();
+        let mut entries = stable.entries();
+        entries[0].apparent_size = 999;
+        *stable = Listing::new(stable.modified, &entries);
+        std::fs::write(root.join("busy/b"), b"1").unwrap();
+
+        let (mut tree, refreshed) = indexed(&root, Some(&index));
+        let stable = tree.get_children(&root.join("stable"), SortOrder::Name);
+        assert_eq!(stable.last().unwrap().apparent_size, 999);
+        assert_eq!(names(&mut tree, &root.join("busy")), ["a", "b"]);
+        assert_eq!(refreshed.dirs[&root.join("busy")].modified, UNTRUSTED);
+        assert_ne!(refreshed.dirs[&root.join("stable")].modified, UNTRUSTED);
+    }
+
+    #[test]
+    fn trusted_index_builds_without_reading_disk() {
+        let temp = TempDir::new("index-trust");
+        let root = temp.join("root");
+        std::fs::create_dir_all(root.join("gone")).unwrap();
+        std::fs::write(root.join("gone/a"), b"12345").unwrap();
+        let (_, index) = indexed(&root, None);
+        std::fs::remove_dir_all(root.join("gone")).unwrap();
+
+        let mut tree = DirTree::build_from_index(
+            &index,
+            &matcher(),
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+        );
+        assert_eq!(names(&mut tree, &root), ["gone"]);
+        assert_eq!(names(&mut tree, &root.join("gone")), ["a"]);
+    }
+
+    #[test]
+    fn trusted_index_leaves_directories_it_lacks_empty() {
+        let temp = TempDir::new("index-trust-missing");
+        let root = temp.join("root");
+        std::fs::create_dir_all(root.join("unread")).unwrap();
+        let (_, mut index) = indexed(&root, None);
+        // As if `unread` could not be listed when the index was recorded.
+        index.dirs.remove(&root.join("unread"));
+        std::fs::write(root.join("unread/new"), b"12345").unwrap();
+
+        let mut tree = DirTree::build_from_index(
+            &index,
+            &matcher(),
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+        );
+        assert_eq!(names(&mut tree, &root), ["unread"]);
+        assert!(names(&mut tree, &root.join("unread")).is_empty());
+    }
+}
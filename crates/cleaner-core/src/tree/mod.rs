@@ -1,126 +1,147 @@
 //! Directory tree with MAXIMUM PERFORMANCE single-pass scan
 //! Single WalkDir, no duplicate syscalls, O(n) everywhere
 
+mod arena;
+mod breakdown;
 mod builder;
+mod diff;
 mod entry;
+mod index;
+mod owners;
 mod progress;
+mod refresh;
 mod sizing;
 mod sort;
+mod top;
 
 #[cfg(test)]
 mod tests;
 
+pub use breakdown::{Breakdown, Category, Tally};
+pub use diff::{diff_dirs, Change, Measure};
 pub use entry::DirEntry;
-pub use progress::ScanProgress;
-pub use sort::{sort_by_name, sort_by_size};
+pub use index::ScanIndex;
+pub use progress::{Listings, ScanProgress};
+pub use refresh::DirRefresh;
+pub use sort::{
+    sort_by_apparent_size, sort_by_items, sort_by_modified, sort_by_name, sort_by_size, SortOrder,
+};
 
-use foldhash::{HashMap, HashMapExt};
+use arena::{Contents, Node, NodeId, NONE};
+use builder::up_entry;
+use foldhash::HashMap;
+use sizing::Totals;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Every directory and file below one or more top directories, usually the
+/// scan root. Entries are kept compactly (see [`DirTree::memory_usage`]) and
+/// turned into [`DirEntry`] values only when a listing is asked for.
 #[derive(Clone)]
 pub struct DirTree {
-    pub children: HashMap<PathBuf, Arc<Vec<DirEntry>>>,
-    sort_modes: HashMap<PathBuf, bool>,
+    nodes: Vec<Node>,
+    /// Every name, back to back.
+    names: Vec<u8>,
+    contents: Vec<Contents>,
+    tops: Vec<(PathBuf, NodeId)>,
+    /// Directories by parent and name hash, to follow a path without
+    /// searching each listing on the way.
+    dirs: HashMap<(NodeId, u64), NodeId>,
+    free_nodes: Vec<NodeId>,
+    free_contents: Vec<u32>,
+    /// Bytes of `names` no entry uses any more.
+    dead_names: usize,
+    /// The listing last handed out, with its order.
+    shown: Option<(NodeId, SortOrder, Arc<Vec<DirEntry>>)>,
+    /// What one owner's files add up to in each directory holding any, for
+    /// [`DirTree::owned_children`].
+    owned: Option<(u32, HashMap<NodeId, Tally>)>,
 }
 
 impl DirTree {
+    /// The tree of `children`, the listing of each directory by path. A
+    /// listing has ".." only if it is given one.
     pub fn from_children(children: HashMap<PathBuf, Vec<DirEntry>>) -> Self {
         let children = children
             .into_iter()
             .map(|(path, entries)| (path, Arc::new(entries)))
             .collect();
-        Self {
-            children,
-            sort_modes: HashMap::new(),
-        }
+        Self::arrange(children, false)
     }
 
     pub fn from_shared_children(children: HashMap<PathBuf, Arc<Vec<DirEntry>>>) -> Self {
-        Self {
-            children,
-            sort_modes: HashMap::new(),
-        }
+        Self::arrange(children, false)
     }
 
-    pub fn get_children(&mut self, path: &Path, by_name: bool) -> Arc<Vec<DirEntry>> {
-        if self.sort_modes.get(path).copied() != Some(by_name) {
-            if let Some(entries) = self.children.get_mut(path) {
-                let entries = Arc::make_mut(entries);
-                if by_name {
-                    sort_by_name(entries);
-                } else {
-                    sort_by_size(entries);
-                }
-                self.sort_modes.insert(path.to_path_buf(), by_name);
+    pub fn get_children(&mut self, path: &Path, order: SortOrder) -> Arc<Vec<DirEntry>> {
+        let Some(dir) = self.listed_at(path) else {
+            return Arc::new(Vec::new());
+        };
+        if let Some((shown, shown_order, entries)) = &self.shown {
+            if *shown == dir && *shown_order == order {
+                return Arc::clone(entries);
             }
         }
-        self.children
-            .get(path)
-            .cloned()
-            .unwrap_or_else(|| Arc::new(Vec::new()))
+        let children = self.children_of(dir);
+        let mut entries = Vec::with_capacity(children.len() + 1);
+        if self.has_up(dir) {
+            entries.push(up_entry());
+        }
+        entries.extend(children.iter().map(|&id| self.entry(id)));
+        order.sort(&mut entries);
+        let entries = Arc::new(entries);
+        self.shown = Some((dir, order, Arc::clone(&entries)));
+        entries
     }
 
-    /// Remove entry from the tree and update all parent sizes (O(depth))
-    pub fn delete_entry(&mut self, path: &PathBuf, is_dir: bool) {
-        if let Some(parent) = path.parent() {
-            let parent_buf = parent.to_path_buf();
+    /// Whether the tree holds the contents of the directory at `path`.
+    pub fn contains_dir(&self, path: &Path) -> bool {
+        self.listed_at(path).is_some()
+    }
 
-            // 1. Remove from parent's children list
-            if let Some(entries) = self.children.get_mut(&parent_buf) {
-                let entries = Arc::make_mut(entries);
-                if let Some(idx) = entries
-                    .iter()
-                    .position(|entry| Some(entry.name.as_os_str()) == path.file_name())
-                {
-                    let removed = entries.remove(idx);
-                    let size_removed = removed.size;
+    /// Every directory whose contents the tree holds.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        self.listed().into_iter().map(|(path, _)| path).collect()
+    }
 
-                    // Manual deletion is rare relative to tree construction, so
-                    // avoid an eager full-path index on every scan.
-                    let mut current_parent = parent_buf;
-                    while let Some(grandparent) = current_parent.parent() {
-                        let grandparent = grandparent.to_path_buf();
-                        if let Some(entries) = self.children.get_mut(&grandparent) {
-                            if let Some(parent_entry) =
-                                Arc::make_mut(entries).iter_mut().find(|entry| {
-                                    Some(entry.name.as_os_str()) == current_parent.file_name()
-                                })
-                            {
-                                parent_entry.size = parent_entry.size.saturating_sub(size_removed);
-                            }
-                        }
-                        current_parent = grandparent;
-                    }
-                }
-            }
-        }
+    /// The entry for `path` in its parent's listing.
+    pub fn find(&self, path: &Path) -> Option<DirEntry> {
+        self.node_at(path)
+            .filter(|&id| self.node(id).parent() != NONE)
+            .map(|id| self.entry(id))
+    }
 
-        // 3. If directory, remove its children entry mapping (optional cleanup)
-        if is_dir {
-            self.children
-                .retain(|candidate, _| !candidate.starts_with(path));
-            self.sort_modes
-                .retain(|candidate, _| !candidate.starts_with(path));
+    /// Remove entry from the tree and update all parent sizes (O(depth))
+    pub fn delete_entry(&mut self, path: &Path, is_dir: bool) {
+        let Some(id) = self.node_at(path) else {
+            return;
+        };
+        let parent = self.node(id).parent();
+        if parent == NONE || self.node(id).is_dir() != is_dir {
+            return;
         }
+        let index = self.node(parent).contents() as usize;
+        self.contents[index].children.retain(|&child| child != id);
+        let removed = self.measures(id);
+        self.free(id);
+        self.propagate(parent, removed, Totals::default());
     }
 
     pub fn get_temp_stats(&self, dir: &Path) -> (usize, usize, u64) {
         let mut totals = (0usize, 0usize, 0u64);
-        let mut stack = vec![dir.to_path_buf()];
-        while let Some(path) = stack.pop() {
-            if let Some(entries) = self.children.get(&path) {
-                for entry in entries.iter().filter(|entry| entry.name != "..") {
-                    if entry.is_temp {
-                        if entry.is_dir {
-                            totals.0 = totals.0.saturating_add(1);
-                        } else {
-                            totals.1 = totals.1.saturating_add(1);
-                        }
-                        totals.2 = totals.2.saturating_add(entry.size);
-                    } else if entry.is_dir {
-                        stack.push(path.join(&entry.name));
+        let mut stack: Vec<_> = self.listed_at(dir).into_iter().collect();
+        while let Some(dir) = stack.pop() {
+            for &id in self.children_of(dir) {
+                let node = self.node(id);
+                if node.is_temp() {
+                    if node.is_dir() {
+                        totals.0 = totals.0.saturating_add(1);
+                    } else {
+                        totals.1 = totals.1.saturating_add(1);
                     }
+                    totals.2 = totals.2.saturating_add(node.size);
+                } else if node.is_listed() {
+                    stack.push(id);
                 }
             }
         }
@@ -0,0 +1,115 @@
+//! Who owns the files of a tree: totals per owner below a directory, and
+//! listings cut down to one owner's files.
+
+use super::arena::{Node, NodeId};
+use super::builder::up_entry;
+use super::{DirEntry, DirTree, SortOrder, Tally};
+use foldhash::HashMap;
+use std::path::Path;
+
+impl DirTree {
+    /// Add up the files at any depth below `dir` by owner uid, largest on
+    /// disk first. Files whose owner the scan could not read (macOS,
+    /// Windows) are under `None`. Empty for a directory the tree has not
+    /// read.
+    pub fn owners(&self, dir: &Path) -> Vec<(Option<u32>, Tally)> {
+        let Some(start) = self.listed_at(dir) else {
+            return Vec::new();
+        };
+        let mut owners: HashMap<Option<u32>, Tally> = HashMap::default();
+        self.each_file(start, |node| {
+            if node.is_extra_link() {
+                return;
+            }
+            owners.entry(node.uid()).or_default().add(Tally {
+                files: 1,
+                size: node.size,
+                apparent_size: node.apparent,
+            });
+        });
+        let mut owners: Vec<_> = owners.into_iter().collect();
+        owners.sort_by(|(left_uid, left), (right_uid, right)| {
+            (right.size, left_uid).cmp(&(left.size, right_uid))
+        });
+        owners
+    }
+
+    /// The listing of `dir` with only what `uid` owns: their files, and
+    /// the folders that hold any of them at some depth, measured by those
+    /// files alone. The per-folder totals are worked out for the whole
+    /// tree on the first call for `uid` and kept until the tree changes.
+    pub fn owned_children(&mut self, dir: &Path, uid: u32, order: SortOrder) -> Vec<DirEntry> {
+        let Some(dir) = self.listed_at(dir) else {
+            return Vec::new();
+        };
+        if self.owned.as_ref().is_none_or(|(owner, _)| *owner != uid) {
+            self.owned = Some((uid, self.owned_totals(uid)));
+        }
+        let Some((_, totals)) = self.owned.as_ref() else {
+            return Vec::new();
+        };
+        let mut entries = Vec::new();
+        if self.has_up(dir) {
+            entries.push(up_entry());
+        }
+        for &id in self.children_of(dir) {
+            let node = self.node(id);
+            if !node.is_dir() {
+                if node.uid() == Some(uid) {
+                    entries.push(self.entry(id));
+                }
+            } else if let Some(owned) = totals.get(&id) {
+                let mut entry = self.entry(id);
+                entry.size = owned.size;
+                entry.apparent_size = owned.apparent_size;
+                entry.items = owned.files;
+                entries.push(entry);
+            }
+        }
+        order.sort(&mut entries);
+        entries
+    }
+
+    /// What the files of `uid` add up to in every read directory that
+    /// holds any, at any depth.
+    fn owned_totals(&self, uid: u32) -> HashMap<NodeId, Tally> {
+        let mut totals: HashMap<NodeId, Tally> = HashMap::default();
+        // Subdirectories before their parents, so each adds up its own.
+        for dir in self.listed_ids().into_iter().rev() {
+            let mut owned = Tally::default();
+            for &id in self.children_of(dir) {
+                let node = self.node(id);
+                if node.is_dir() {
+                    if let Some(below) = totals.get(&id) {
+                        owned.add(*below);
+                    }
+                } else if node.uid() == Some(uid) && !node.is_extra_link() {
+                    owned.add(Tally {
+                        files: 1,
+                        size: node.size,
+                        apparent_size: node.apparent,
+                    });
+                }
+            }
+            if owned.files > 0 {
+                totals.insert(dir, owned);
+            }
+        }
+        totals
+    }
+
+    /// Call `visit` with every file at any depth below the listed `dir`.
+    fn each_file(&self, dir: NodeId, mut visit: impl FnMut(&Node)) {
+        let mut stack = vec![dir];
+        while let Some(dir) = stack.pop() {
+            for &id in self.children_of(dir) {
+                let node = self.node(id);
+                if !node.is_dir() {
+                    visit(node);
+                } else if node.is_listed() {
+                    stack.push(id);
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,10 @@
+use super::entry::DirEntry;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Listings handed out by a streaming scan, in the order they were read.
+pub type Listings = Vec<(PathBuf, Arc<Vec<DirEntry>>)>;
 
 #[derive(Default)]
 pub struct ScanProgress {
@@ -10,6 +16,9 @@ pub struct ScanProgress {
     pub phase: AtomicU8,
     pub stage_current: AtomicUsize,
     pub stage_total: AtomicUsize,
+    /// Set by [`ScanProgress::stream_listings`].
+    streaming: AtomicBool,
+    listings: Mutex<Listings>,
 }
 
 impl ScanProgress {
@@ -48,6 +57,33 @@ impl ScanProgress {
         )
     }
 
+    /// Have the walk queue every directory listing for
+    /// [`ScanProgress::take_listings`] as soon as it is read, so a browser
+    /// can show the tree before the scan finishes. Call before the scan
+    /// starts.
+    pub fn stream_listings(&self) {
+        self.streaming.store(true, Ordering::Relaxed);
+    }
+
+    /// The listings read since the last call, parents before their
+    /// subdirectories. A directory's entry has the size of the directory
+    /// itself, not of its contents; [`super::DirTree::add_listing`] adds
+    /// those up.
+    pub fn take_listings(&self) -> Listings {
+        self.listings
+            .lock()
+            .map(|mut listings| std::mem::take(&mut *listings))
+            .unwrap_or_default()
+    }
+
+    pub(super) fn offer_listing(&self, dir: &Path, entries: &Arc<Vec<DirEntry>>) {
+        if self.streaming.load(Ordering::Relaxed) {
+            if let Ok(mut listings) = self.listings.lock() {
+                listings.push((dir.to_path_buf(), Arc::clone(entries)));
+            }
+        }
+    }
+
     pub fn begin_stage(&self, phase: u8, total: usize) {
         self.stage_current.store(0, Ordering::Relaxed);
         self.stage_total.store(total, Ordering::Relaxed);
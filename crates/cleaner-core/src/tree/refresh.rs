@@ -0,0 +1,196 @@
+//! Changing a tree one directory at a time: growing it while a scan streams
+//! listings in, and bringing directories up to date after they change on
+//! disk without walking the rest.
+
+use super::builder::{listing, TREE_METADATA};
+use super::progress::ScanProgress;
+use super::{DirEntry, DirTree};
+use crate::fastwalk;
+use crate::patterns::project::ProjectMatchers;
+use crate::patterns::PatternMatcher;
+use crate::protected::ProtectedPaths;
+use foldhash::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::Arc;
+
+/// A directory re-read from disk, with its new subdirectories already
+/// scanned, ready for [`DirTree::apply_refresh`]. Reading one does not touch
+/// the tree, so it can run on another thread.
+pub struct DirRefresh {
+    dir: PathBuf,
+    entries: Vec<DirEntry>,
+    /// Names of the hard links the scan counts elsewhere.
+    linked: HashSet<OsString>,
+    /// A tree for each subdirectory the tree has not read yet.
+    subtrees: HashMap<OsString, DirTree>,
+}
+
+impl DirRefresh {
+    /// Re-read `dir` in the tree built from `root`, scanning every
+    /// subdirectory not in `known`, which [`DirTree::listed_subdirs`]
+    /// gives. `None` if `dir` cannot be read: a directory that is gone is
+    /// dropped when its parent is refreshed.
+    pub fn read(
+        root: &Path,
+        dir: &Path,
+        known: &HashSet<OsString>,
+        matcher: &PatternMatcher,
+        force: bool,
+    ) -> Option<Self> {
+        let raw = fastwalk::read_dir(dir, TREE_METADATA).ok()?;
+        let config = matcher.config();
+        let linked = raw
+            .iter()
+            .filter(|entry| entry.hard_link.is_some() && !config.count_links)
+            .map(|entry| entry.name.clone())
+            .collect();
+        let protected = ProtectedPaths::new(root, force, &config.protect);
+        let device = config.root_device(root);
+        let mounts: HashSet<OsString> = match device {
+            Some(device) => raw
+                .iter()
+                .filter(|entry| entry.is_dir && !entry.is_symlink)
+                .filter(|entry| entry.device_at(&dir.join(&entry.name)) != Some(device))
+                .map(|entry| entry.name.clone())
+                .collect(),
+            None => HashSet::default(),
+        };
+        let errors = AtomicUsize::new(0);
+        let project = ProjectMatchers::new(matcher, dir, &errors).matcher_in(dir, &raw);
+        let entries = listing(dir, raw, &protected, project.as_deref().unwrap_or(matcher));
+
+        let subtrees = entries
+            .iter()
+            .filter(|entry| entry.is_dir && !known.contains(&entry.name))
+            .filter(|entry| !mounts.contains(&entry.name))
+            .map(|entry| {
+                let path = dir.join(&entry.name);
+                let subtree = DirTree::build_within(
+                    &path,
+                    root,
+                    matcher,
+                    Arc::new(ScanProgress::new()),
+                    Arc::new(AtomicBool::new(false)),
+                    force,
+                    None,
+                );
+                (entry.name.clone(), subtree)
+            })
+            .collect();
+        Some(Self {
+            dir: dir.to_path_buf(),
+            entries,
+            linked,
+            subtrees,
+        })
+    }
+
+    /// The directory that was re-read.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl DirTree {
+    /// Re-read `dir` and fold what changed into the tree, as
+    /// [`DirRefresh::read`] followed by [`DirTree::apply_refresh`]. `root`
+    /// is the root the tree was built from. Returns the directories added.
+    pub fn refresh_dir(
+        &mut self,
+        root: &Path,
+        dir: &Path,
+        matcher: &PatternMatcher,
+        force: bool,
+    ) -> Vec<PathBuf> {
+        let Some(known) = self.listed_subdirs(dir) else {
+            return Vec::new();
+        };
+        match DirRefresh::read(root, dir, &known, matcher, force) {
+            Some(refresh) => self.apply_refresh(refresh),
+            None => Vec::new(),
+        }
+    }
+
+    /// The subdirectories of `dir` the tree has read, which a refresh of
+    /// `dir` leaves alone, or `None` if `dir` itself has not been read.
+    pub fn listed_subdirs(&self, dir: &Path) -> Option<HashSet<OsString>> {
+        let id = self.listed_at(dir)?;
+        Some(
+            self.children_of(id)
+                .iter()
+                .filter(|&&child| self.node(child).is_listed())
+                .map(|&child| self.name(child).to_os_string())
+                .collect(),
+        )
+    }
+
+    /// Fold a re-read directory into the tree: files take their new sizes,
+    /// new subdirectories take their scanned trees, vanished ones are
+    /// dropped, and the directory and every ancestor move by the
+    /// difference. Returns the directories added.
+    ///
+    /// Subdirectories already in the tree keep their totals. A file that
+    /// becomes a hard link to one counted elsewhere is counted again until
+    /// the next full scan. A subdirectory that appeared in the tree after
+    /// the refresh was read stays unread until its parent is refreshed.
+    pub fn apply_refresh(&mut self, refresh: DirRefresh) -> Vec<PathBuf> {
+        let Some(id) = self.listed_at(&refresh.dir) else {
+            return Vec::new();
+        };
+        let linked = &refresh.linked;
+        let before = self.totals(id);
+        let unlisted = self.relist(id, &refresh.entries, |node, known, entry| match known {
+            Some(known) if node.is_dir() && known.is_listed() => node.carry(known),
+            // Keep a hard link that the scan found counted elsewhere at
+            // zero.
+            Some(known) if known.items == 0 && linked.contains(&entry.name) => {
+                node.clear();
+                node.items = 0;
+            }
+            _ if node.is_dir() => node.clear(),
+            _ => {}
+        });
+
+        let mut added = Vec::new();
+        for child in unlisted {
+            let name = self.name(child);
+            let Some(subtree) = refresh.subtrees.get(name) else {
+                continue;
+            };
+            let path = refresh.dir.join(name);
+            if let Some(top) = subtree.listed_at(&path) {
+                self.graft(child, subtree, top);
+                self.settle(child);
+                added.extend(subtree.dirs());
+            }
+        }
+
+        let after = self.totals(id);
+        self.propagate(id, before, after);
+        added
+    }
+
+    /// Add the listing of `dir`, as [`ScanProgress::take_listings`] hands it
+    /// out, to a tree that is still being scanned. Directory sizes count
+    /// what has been listed below them so far, so they only grow until the
+    /// scan is done; hard links are counted at every path.
+    pub fn add_listing(&mut self, root: &Path, dir: &Path, entries: &[DirEntry]) {
+        // Listings come parent first, so only the root's is not yet in
+        // the tree.
+        let id = match self.node_at(dir) {
+            Some(id) => id,
+            None => self.add_top(dir),
+        };
+        let before = self.totals(id);
+        self.relist(id, entries, |node, known, _| match known {
+            Some(known) if node.is_dir() && known.is_listed() => node.carry(known),
+            _ if node.is_dir() => node.clear(),
+            _ => {}
+        });
+        self.set_up(id, dir != root);
+        let after = self.totals(id);
+        self.propagate(id, before, after);
+    }
+}
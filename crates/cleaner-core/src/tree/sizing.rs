@@ -1,91 +1,66 @@
-use super::entry::DirEntry;
+use super::arena::NodeId;
 use super::progress::ScanProgress;
-use foldhash::HashMap;
-use std::path::{Path, PathBuf};
+use super::DirTree;
+use crate::fastwalk::HardLink;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
 
-pub(crate) fn apply_directory_sizes(
-    dir: &Path,
-    children: &mut HashMap<PathBuf, Arc<Vec<DirEntry>>>,
-    progress: &ScanProgress,
-    cancelled: &AtomicBool,
-) -> u64 {
-    struct Frame {
-        path: PathBuf,
-        entries: Arc<Vec<DirEntry>>,
-        next: usize,
-        total: u64,
-    }
-
-    let Some((path, entries)) = children.remove_entry(dir) else {
-        return 0;
-    };
-    let mut stack = vec![Frame {
-        path,
-        entries,
-        next: 0,
-        total: 0,
-    }];
-    let mut root_total = 0;
-    let mut completed = 0usize;
-
-    while !stack.is_empty() {
-        if cancelled.load(Ordering::Relaxed) {
-            progress.stage_current.store(completed, Ordering::Relaxed);
-            for frame in stack.drain(..) {
-                children.insert(frame.path, frame.entries);
-            }
-            return 0;
-        }
+/// What a directory's listing adds up to.
+#[derive(Clone, Copy, Default)]
+pub(super) struct Totals {
+    pub(super) size: u64,
+    pub(super) apparent: u64,
+    pub(super) items: u64,
+    pub(super) modified: i64,
+}
 
-        let child = {
-            let frame = stack.last_mut().expect("size stack is not empty");
-            let mut child = None;
-            while frame.next < frame.entries.len() {
-                let index = frame.next;
-                frame.next += 1;
-                let entry = &frame.entries[index];
-                if entry.is_dir && entry.name != ".." {
-                    child = Some((index, frame.path.join(&entry.name)));
-                    break;
+impl DirTree {
+    /// Size every directory that was read from its contents, deepest
+    /// first; one that was not read counts as empty. Returns false if
+    /// cancelled first.
+    pub(super) fn size_directories(
+        &mut self,
+        progress: &ScanProgress,
+        cancelled: &AtomicBool,
+    ) -> bool {
+        // Sizing in reverse finds every subdirectory already sized.
+        let order = self.listed_ids();
+        for (completed, &dir) in order.iter().rev().enumerate() {
+            if completed.is_multiple_of(1024) {
+                if cancelled.load(Ordering::Relaxed) {
+                    return false;
                 }
-                frame.total = frame.total.saturating_add(entry.size);
+                progress.stage_current.store(completed, Ordering::Relaxed);
             }
-            child
-        };
-
-        if let Some((index, child_path)) = child {
-            if let Some((path, entries)) = children.remove_entry(&child_path) {
-                stack.push(Frame {
-                    path,
-                    entries,
-                    next: 0,
-                    total: 0,
-                });
-            } else if let Some(frame) = stack.last_mut() {
-                Arc::make_mut(&mut frame.entries)[index].size = 0;
+            let contents = self.node(dir).contents() as usize;
+            for &child in &self.contents[contents].children {
+                let node = &mut self.nodes[child as usize];
+                if node.is_dir() && !node.is_listed() {
+                    node.clear();
+                }
             }
-            continue;
+            self.settle(dir);
         }
+        progress.stage_current.store(order.len(), Ordering::Relaxed);
+        true
+    }
 
-        let frame = stack.pop().expect("completed size frame exists");
-        let total = frame.total;
-        children.insert(frame.path, frame.entries);
-        completed = completed.saturating_add(1);
-        if completed.is_multiple_of(1024) {
-            progress.stage_current.store(completed, Ordering::Relaxed);
-        }
-        if let Some(parent) = stack.last_mut() {
-            let child_index = parent.next - 1;
-            Arc::make_mut(&mut parent.entries)[child_index].size = total;
-            parent.total = parent.total.saturating_add(total);
-        } else {
-            root_total = total;
+    /// Leave each hard-linked file's size and inode on its first path only,
+    /// so [`DirTree::size_directories`] counts the file once. `links` pairs
+    /// every hard-linked file's path and node with its link.
+    pub(super) fn dedup_hard_links(&mut self, mut links: Vec<(PathBuf, NodeId, HardLink)>) {
+        links.sort_unstable_by(|(left_path, _, left), (right_path, _, right)| {
+            (left.device, left.inode, left_path).cmp(&(right.device, right.inode, right_path))
+        });
+        let mut owner = None;
+        for (_, id, link) in links {
+            if owner.replace((link.device, link.inode)) != Some((link.device, link.inode)) {
+                continue;
+            }
+            let node = &mut self.nodes[id as usize];
+            node.size = 0;
+            node.apparent = 0;
+            node.items = 0;
         }
     }
-
-    progress.stage_current.store(completed, Ordering::Relaxed);
-
-    root_total
 }
@@ -1,6 +1,51 @@
 use super::entry::DirEntry;
 
+/// How [`super::DirTree::get_children`] orders a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Size,
+    ApparentSize,
+    Name,
+    /// Oldest first; entries of unknown age last.
+    Modified,
+    /// Most entries first.
+    Items,
+}
+
+impl SortOrder {
+    pub fn sort(self, entries: &mut [DirEntry]) {
+        match self {
+            Self::Size => sort_by_size(entries),
+            Self::ApparentSize => sort_by_apparent_size(entries),
+            Self::Name => sort_by_name(entries),
+            Self::Modified => sort_by_modified(entries),
+            Self::Items => sort_by_items(entries),
+        }
+    }
+}
+
 pub fn sort_by_size(entries: &mut [DirEntry]) {
+    sort_descending(entries, |entry| entry.size);
+}
+
+pub fn sort_by_apparent_size(entries: &mut [DirEntry]) {
+    sort_descending(entries, |entry| entry.apparent_size);
+}
+
+pub fn sort_by_items(entries: &mut [DirEntry]) {
+    sort_descending(entries, |entry| entry.items);
+}
+
+pub fn sort_by_modified(entries: &mut [DirEntry]) {
+    // Age is how long ago, so the oldest sorts as the largest; an unknown
+    // time (0) as the smallest.
+    sort_descending(entries, |entry| match entry.modified {
+        0 => 0,
+        modified => u64::MAX - modified.max(0) as u64,
+    });
+}
+
+fn sort_descending(entries: &mut [DirEntry], key: impl Fn(&DirEntry) -> u64) {
     entries.sort_unstable_by(|a, b| {
         if a.name == ".." {
             return std::cmp::Ordering::Less;
@@ -11,7 +56,7 @@ pub fn sort_by_size(entries: &mut [DirEntry]) {
         match (a.is_dir, b.is_dir) {
             (true, false) => std::cmp::Ordering::Less,
             (false, true) => std::cmp::Ordering::Greater,
-            _ => b.size.cmp(&a.size),
+            _ => key(b).cmp(&key(a)),
         }
     });
 }
@@ -1,8 +1,7 @@
 use super::entry::DirEntry;
 use super::progress::ScanProgress;
-use super::sizing::apply_directory_sizes;
-use super::sort::{sort_by_name, sort_by_size};
-use super::DirTree;
+use super::sort::{sort_by_items, sort_by_modified, sort_by_name, sort_by_size};
+use super::{Breakdown, Category, DirTree, SortOrder};
 use crate::config::Config;
 use crate::patterns::PatternMatcher;
 use crate::test_support::TempDir;
@@ -12,12 +11,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 fn entry(_path: PathBuf, name: &str, size: u64, is_dir: bool, is_temp: bool) -> DirEntry {
-    DirEntry {
-        name: name.into(),
-        size,
-        is_dir,
-        is_temp,
-    }
+    DirEntry::new(name, size, is_dir, is_temp)
 }
 
 #[test]
@@ -33,8 +27,7 @@ fn matcher() -> PatternMatcher {
     PatternMatcher::new(Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     }))
 }
 
@@ -92,17 +85,22 @@ fn in_place_sizing_updates_nested_entries_and_progress() {
             false,
         )],
     );
-    let mut children: HashMap<PathBuf, Arc<Vec<DirEntry>>> = children
-        .into_iter()
-        .map(|(path, entries)| (path, Arc::new(entries)))
-        .collect();
+    let mut tree = DirTree::from_children(children);
     let progress = ScanProgress::new();
-    progress.begin_stage(2, children.len());
-    let total = apply_directory_sizes(&root, &mut children, &progress, &AtomicBool::new(false));
-    assert_eq!(total, 10);
-    assert_eq!(children[&root][0].size, 8);
-    assert_eq!(children[&child][0].size, 5);
+    progress.begin_stage(2, tree.dir_count());
+    assert!(tree.size_directories(&progress, &AtomicBool::new(false)));
+    let sizes = |tree: &mut DirTree, dir: &Path| -> Vec<u64> {
+        tree.get_children(dir, SortOrder::Name)
+            .iter()
+            .map(|entry| entry.size)
+            .collect()
+    };
+    assert_eq!(sizes(&mut tree, &root), [8, 2]);
+    assert_eq!(sizes(&mut tree, &child), [5, 3]);
     assert_eq!(progress.get_stage_progress(), (3, 3));
+
+    let cancelled = AtomicBool::new(true);
+    assert!(!tree.size_directories(&progress, &cancelled));
 }
 
 #[test]
@@ -121,21 +119,17 @@ fn in_place_sizing_handles_a_wide_tree() {
         );
     }
     children.insert(root.clone(), root_entries);
-    let mut children: HashMap<PathBuf, Arc<Vec<DirEntry>>> = children
-        .into_iter()
-        .map(|(path, entries)| (path, Arc::new(entries)))
-        .collect();
+    let mut tree = DirTree::from_children(children);
     let progress = ScanProgress::new();
-    progress.begin_stage(2, children.len());
-    assert_eq!(
-        apply_directory_sizes(&root, &mut children, &progress, &AtomicBool::new(false),),
-        DIRECTORY_COUNT as u64
-    );
+    progress.begin_stage(2, tree.dir_count());
+    assert!(tree.size_directories(&progress, &AtomicBool::new(false)));
     assert_eq!(
         progress.get_stage_progress(),
         (DIRECTORY_COUNT + 1, DIRECTORY_COUNT + 1)
     );
-    assert_eq!(children[&root][0].size, 1);
+    let listing = tree.get_children(&root, SortOrder::Name);
+    assert_eq!(listing.len(), DIRECTORY_COUNT);
+    assert!(listing.iter().all(|entry| entry.size == 1));
 }
 
 #[test]
@@ -159,17 +153,179 @@ fn build_computes_sizes_temp_flags_navigation_and_progress() {
     assert_eq!(progress.get_phase(), 3);
     assert_eq!(
         progress.get_stage_progress(),
-        (tree.children.len(), tree.children.len())
+        (tree.dir_count(), tree.dir_count())
     );
-    let root = tree.get_children(temp.path(), false);
+    let root = tree.get_children(temp.path(), SortOrder::Size);
     let target = root.iter().find(|e| e.name == "target").unwrap();
     assert!(target.is_temp);
     assert_eq!(target.size, 5);
-    let src = tree.get_children(&temp.join("src"), false);
+    let src = tree.get_children(&temp.join("src"), SortOrder::Size);
     assert_eq!(src[0].name, "..");
     assert!(src.iter().find(|e| e.name == "cache.pyc").unwrap().is_temp);
 }
 
+#[cfg(unix)]
+#[test]
+fn hard_linked_files_are_sized_once_unless_links_are_counted() {
+    let temp = TempDir::new("tree-links");
+    let original = temp.write("a/data.bin", b"12345");
+    temp.mkdir("b");
+    std::fs::hard_link(&original, temp.join("b/data.bin")).unwrap();
+    let size = std::fs::metadata(&original).map_or(0, |metadata| {
+        use std::os::unix::fs::MetadataExt;
+        metadata.len().min(metadata.blocks() * 512)
+    });
+    let build = |count_links| {
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            count_links,
+            ..Config::empty()
+        }));
+        let mut tree = DirTree::build_with_progress(
+            temp.path(),
+            &matcher,
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+        );
+        let root = tree.get_children(temp.path(), SortOrder::Name);
+        root.iter()
+            .filter(|entry| entry.name != "..")
+            .map(|entry| (entry.size, entry.items))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(build(false), [(size, 2), (0, 1)]);
+    assert_eq!(build(true), [(size, 2), (size, 2)]);
+}
+
+#[cfg(unix)]
+#[test]
+fn hard_links_count_as_one_file_in_breakdowns_and_owners() {
+    let temp = TempDir::new("tree-link-files");
+    let original = temp.write("a/data.bin", b"12345");
+    temp.mkdir("b");
+    std::fs::hard_link(&original, temp.join("b/data.bin")).unwrap();
+    let mut tree = DirTree::build_with_progress(
+        temp.path(),
+        &PatternMatcher::new(Arc::new(Config::empty())),
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+    assert_eq!(tree.breakdown(temp.path()).total.files, 1);
+    let owners = tree.owners(temp.path());
+    assert_eq!(owners.iter().map(|(_, tally)| tally.files).sum::<u64>(), 1);
+    if let Some(uid) = owners[0].0 {
+        let owned = tree.owned_children(temp.path(), uid, SortOrder::Name);
+        let files: Vec<_> = owned.iter().map(|entry| entry.items).collect();
+        assert_eq!(files.iter().sum::<u64>(), 1);
+    }
+}
+
+#[test]
+fn apparent_sizes_are_summed_alongside_disk_usage() {
+    let temp = TempDir::new("tree-apparent");
+    let sparse = temp.write("images/disk.img", b"");
+    std::fs::File::options()
+        .write(true)
+        .open(&sparse)
+        .unwrap()
+        .set_len(64 << 20)
+        .unwrap();
+    temp.write("logs/app.log", b"12345");
+    let mut tree = DirTree::build_with_progress(
+        temp.path(),
+        &matcher(),
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+    let root = tree.get_children(temp.path(), SortOrder::ApparentSize);
+    assert_eq!(root[0].name, "images");
+    assert_eq!(root[0].apparent_size, 64 << 20);
+    assert_eq!(root[1].apparent_size, 5);
+    assert!(root[0].size <= root[0].apparent_size);
+    assert_eq!(root[0].size_as(true), 64 << 20);
+}
+
+#[test]
+fn build_records_entry_counts_modification_times_and_owners() {
+    let temp = TempDir::new("tree-metadata");
+    temp.write("logs/a.log", b"1");
+    temp.write("logs/nested/b.log", b"1");
+    temp.write("notes.txt", b"1");
+    let mut tree = DirTree::build_with_progress(
+        temp.path(),
+        &matcher(),
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+    let root = tree.get_children(temp.path(), SortOrder::Items);
+    assert_eq!(root[0].name, "logs");
+    // logs, a.log, nested and nested/b.log.
+    assert_eq!(root[0].items, 4);
+    assert_eq!(root[1].items, 1);
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        assert!(root[0].modified > 0);
+        assert!(root[0].modified >= root[1].modified - 5);
+        assert!(root.iter().all(|entry| entry.uid.is_some()));
+    }
+}
+
+#[test]
+fn excluded_paths_are_never_marked_temp() {
+    let temp = TempDir::new("tree-exclude");
+    temp.write("legacy/target/artifact", b"1");
+    temp.write("legacy/cache.pyc", b"1");
+    temp.write("app/target/artifact", b"1");
+    let matcher = PatternMatcher::new(Arc::new(Config {
+        directories: vec!["target".into()],
+        files: vec![".pyc".into()],
+        exclude: vec!["legacy".into()],
+        ..Config::empty()
+    }));
+    let mut tree = DirTree::build_with_progress(
+        temp.path(),
+        &matcher,
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+    let legacy = tree.get_children(&temp.join("legacy"), SortOrder::Size);
+    assert!(legacy.iter().all(|entry| !entry.is_temp));
+    let app = tree.get_children(&temp.join("app"), SortOrder::Size);
+    assert!(app
+        .iter()
+        .any(|entry| entry.name == "target" && entry.is_temp));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn one_file_system_trees_leave_other_mounts_unread() {
+    let root = PathBuf::from("/dev");
+    let shm = root.join("shm");
+    if crate::fastwalk::device_of(&root) == crate::fastwalk::device_of(&shm) {
+        return;
+    }
+    let build = |one_file_system| {
+        let matcher = PatternMatcher::new(Arc::new(Config {
+            one_file_system,
+            ..Config::empty()
+        }));
+        DirTree::build_with_progress(
+            &root,
+            &matcher,
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            true,
+        )
+    };
+    assert!(build(false).contains_dir(&shm));
+    let tree = build(true);
+    assert!(tree.contains_dir(&root));
+    assert!(!tree.contains_dir(&shm));
+}
+
 #[test]
 fn cancelled_build_returns_no_children_and_marks_done() {
     let temp = TempDir::new("tree-cancel");
@@ -182,7 +338,7 @@ fn cancelled_build_returns_no_children_and_marks_done() {
         Arc::new(AtomicBool::new(true)),
         false,
     );
-    assert!(tree.children.is_empty());
+    assert_eq!(tree.dir_count(), 0);
     assert!(progress.is_done());
 }
 
@@ -242,12 +398,290 @@ fn deleting_entry_updates_ancestors_and_directory_map() {
     children.insert(target.clone(), vec![]);
     let mut tree = DirTree::from_children(children);
     tree.delete_entry(&target, true);
-    assert!(tree.get_children(&child, false).is_empty());
-    assert_eq!(tree.get_children(&root, false)[0].size, 5);
-    assert!(!tree.children.contains_key(&target));
+    assert!(tree.get_children(&child, SortOrder::Size).is_empty());
+    assert_eq!(tree.get_children(&root, SortOrder::Size)[0].size, 5);
+    assert!(!tree.contains_dir(&target));
     tree.delete_entry(&root.join("missing"), false);
 }
 
+#[test]
+fn tree_memory_stays_within_the_per_entry_target() {
+    let root = PathBuf::from("/virtual-root");
+    let mut children = HashMap::new();
+    let mut name_bytes = 0;
+    let mut root_entries = Vec::new();
+    for dir in 0..100 {
+        let name = format!("dir-{dir:03}");
+        let files: Vec<_> = (0..100)
+            .map(|file| DirEntry::new(format!("file-{file:04}.bin"), 10, false, false))
+            .collect();
+        name_bytes += name.len() + files.iter().map(|file| file.name.len()).sum::<usize>();
+        children.insert(root.join(&name), files);
+        root_entries.push(DirEntry::new(name, 1000, true, false));
+    }
+    children.insert(root.clone(), root_entries);
+    let mut tree = DirTree::from_children(children);
+
+    assert_eq!((tree.len(), tree.dir_count()), (10_100, 101));
+    let target = tree.len() * 64 + name_bytes + tree.dir_count() * 64;
+    assert!(
+        tree.memory_usage() <= target,
+        "{} bytes for {} entries",
+        tree.memory_usage(),
+        tree.len()
+    );
+    let dir = root.join("dir-007");
+    tree.delete_entry(&dir.join("file-0003.bin"), false);
+    assert_eq!(tree.get_children(&dir, SortOrder::Name).len(), 99);
+    assert_eq!(tree.find(&dir).map(|entry| entry.size), Some(990));
+    assert_eq!(tree.len(), 10_099);
+}
+
+#[test]
+fn largest_entries_are_found_at_any_depth() {
+    let root = PathBuf::from("/virtual-root");
+    let sized = |name: &str, size: u64, apparent: u64, is_dir: bool| DirEntry {
+        apparent_size: apparent,
+        ..DirEntry::new(name, size, is_dir, false)
+    };
+    let mut children = HashMap::new();
+    children.insert(
+        root.clone(),
+        vec![
+            sized("a", 900, 900, true),
+            sized("top.iso", 500, 500, false),
+            sized("sparse.img", 10, 4000, false),
+        ],
+    );
+    children.insert(
+        root.join("a"),
+        vec![sized("b", 800, 800, true), sized("small", 100, 100, false)],
+    );
+    children.insert(
+        root.join("a/b"),
+        vec![
+            sized("deep.bin", 700, 700, false),
+            sized("tiny", 100, 100, false),
+        ],
+    );
+    let tree = DirTree::from_children(children);
+    let paths = |largest: Vec<(PathBuf, DirEntry)>| -> Vec<String> {
+        largest
+            .iter()
+            .map(|(path, _)| path.strip_prefix(&root).unwrap().display().to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        paths(tree.largest(&root, 2, false, false)),
+        ["a/b/deep.bin", "top.iso"]
+    );
+    assert_eq!(
+        paths(tree.largest(&root, 2, true, false)),
+        ["a/b", "a/b/deep.bin"]
+    );
+    assert_eq!(paths(tree.largest(&root, 1, false, true)), ["sparse.img"]);
+    assert_eq!(
+        paths(tree.largest(&root.join("a"), 10, false, false)),
+        ["a/b/deep.bin", "a/b/tiny", "a/small"]
+    );
+    assert!(tree.largest(&root, 0, true, false).is_empty());
+}
+
+#[test]
+fn breakdown_adds_up_files_by_extension_and_category() {
+    let root = PathBuf::from("/virtual-root");
+    let mut children = HashMap::new();
+    children.insert(
+        root.clone(),
+        vec![
+            DirEntry::new("logs", 30, true, false),
+            DirEntry::new("movie.MP4", 500, false, false),
+            DirEntry::new("clip.mp4", 100, false, false),
+            DirEntry::new(".bashrc", 1, false, false),
+            DirEntry::new("main.o", 40, false, true),
+        ],
+    );
+    children.insert(
+        root.join("logs"),
+        vec![
+            DirEntry::new("a.log", 20, false, false),
+            DirEntry::new("b.log", 10, false, false),
+        ],
+    );
+    let tree = DirTree::from_children(children);
+
+    let breakdown = tree.breakdown(&root);
+    assert_eq!((breakdown.total.files, breakdown.total.size), (6, 671));
+    let extensions: Vec<_> = breakdown
+        .extensions
+        .iter()
+        .map(|(extension, tally)| (extension.as_str(), tally.files, tally.size))
+        .collect();
+    assert_eq!(
+        extensions,
+        [("mp4", 2, 600), ("o", 1, 40), ("log", 2, 30), ("", 1, 1)]
+    );
+    let categories: Vec<_> = breakdown
+        .categories
+        .iter()
+        .map(|(category, tally)| (*category, tally.size))
+        .collect();
+    assert_eq!(
+        categories,
+        [
+            (Category::Media, 600),
+            (Category::BuildOutputs, 40),
+            (Category::Logs, 30),
+            (Category::Other, 1),
+        ]
+    );
+    assert_eq!(tree.breakdown(&root.join("logs")).total.files, 2);
+    assert_eq!(tree.breakdown(Path::new("/missing")), Breakdown::default());
+}
+
+#[test]
+fn owners_add_up_files_per_uid_and_filter_listings_to_one() {
+    let owned = |name: &str, size: u64, is_dir: bool, uid: Option<u32>| DirEntry {
+        uid,
+        ..DirEntry::new(name, size, is_dir, false)
+    };
+    let root = PathBuf::from("/virtual-root");
+    let mut children = HashMap::new();
+    children.insert(
+        root.clone(),
+        vec![
+            owned("shared", 700, true, Some(0)),
+            owned("theirs", 50, true, Some(1001)),
+            owned("alice.iso", 400, false, Some(1000)),
+            owned("mystery", 5, false, None),
+        ],
+    );
+    children.insert(
+        root.join("shared"),
+        vec![
+            owned("alice.tar", 300, false, Some(1000)),
+            owned("bob.tar", 400, false, Some(1001)),
+        ],
+    );
+    children.insert(
+        root.join("theirs"),
+        vec![owned("bob.log", 50, false, Some(1001))],
+    );
+    let mut tree = DirTree::from_children(children);
+
+    let owners: Vec<_> = tree
+        .owners(&root)
+        .into_iter()
+        .map(|(uid, tally)| (uid, tally.files, tally.size))
+        .collect();
+    assert_eq!(
+        owners,
+        [(Some(1000), 2, 700), (Some(1001), 2, 450), (None, 1, 5)]
+    );
+    assert_eq!(tree.owners(&root.join("theirs")).len(), 1);
+    assert!(tree.owners(Path::new("/missing")).is_empty());
+
+    let listing: Vec<_> = tree
+        .owned_children(&root, 1000, SortOrder::Size)
+        .into_iter()
+        .map(|entry| (entry.name.into_string().unwrap(), entry.size, entry.items))
+        .collect();
+    assert_eq!(
+        listing,
+        [
+            ("shared".to_string(), 300, 1),
+            ("alice.iso".to_string(), 400, 1)
+        ]
+    );
+    assert!(tree.owned_children(&root, 4242, SortOrder::Size).is_empty());
+
+    tree.delete_entry(&root.join("shared/alice.tar"), false);
+    let listing: Vec<_> = tree
+        .owned_children(&root, 1000, SortOrder::Size)
+        .into_iter()
+        .map(|entry| entry.name.into_string().unwrap())
+        .collect();
+    assert_eq!(listing, ["alice.iso"]);
+}
+
+#[test]
+fn streamed_listings_add_up_to_the_built_tree() {
+    let temp = TempDir::new("tree-stream");
+    temp.write("app/src/main.rs", b"fn main() {}");
+    temp.write("app/target/debug/app", &[0; 3000]);
+    temp.write("logs/a.log", &[0; 700]);
+    temp.write("notes.txt", b"1");
+    let progress = Arc::new(ScanProgress::new());
+    progress.stream_listings();
+    let mut built = DirTree::build_with_progress(
+        temp.path(),
+        &matcher(),
+        Arc::clone(&progress),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+
+    let listings = progress.take_listings();
+    assert_eq!(listings.len(), built.dir_count());
+    assert!(progress.take_listings().is_empty());
+    let mut partial = DirTree::from_children(HashMap::new());
+    for (dir, entries) in &listings {
+        partial.add_listing(temp.path(), dir, entries);
+    }
+    let summary = |tree: &mut DirTree, dir: &Path| -> Vec<_> {
+        tree.get_children(dir, SortOrder::Name)
+            .iter()
+            .map(|e| (e.name.clone(), e.size, e.apparent_size, e.items, e.is_temp))
+            .collect()
+    };
+    for dir in [temp.path(), &temp.join("app"), &temp.join("app/target")] {
+        assert_eq!(summary(&mut partial, dir), summary(&mut built, dir));
+    }
+}
+
+#[test]
+fn refreshing_changed_directories_matches_a_fresh_build() {
+    let temp = TempDir::new("tree-refresh");
+    temp.write("app/src/main.rs", b"fn main() {}");
+    temp.write("app/old/stale.bin", b"12345");
+    temp.write("logs/a.log", b"1");
+    let build = || {
+        DirTree::build_with_progress(
+            temp.path(),
+            &matcher(),
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+        )
+    };
+    let mut tree = build();
+
+    temp.write("app/target/debug/app", &[0; 3000]);
+    std::fs::remove_dir_all(temp.join("app/old")).unwrap();
+    temp.write("logs/a.log", &[0; 700]);
+    temp.write("logs/b.log", b"12");
+    for dir in ["app", "logs"] {
+        tree.refresh_dir(temp.path(), &temp.join(dir), &matcher(), false);
+    }
+
+    let mut fresh = build();
+    assert!(!tree.contains_dir(&temp.join("app/old")));
+    let summary = |tree: &mut DirTree, dir: &Path| -> Vec<_> {
+        tree.get_children(dir, SortOrder::Name)
+            .iter()
+            .map(|e| (e.name.clone(), e.size, e.apparent_size, e.items, e.is_temp))
+            .collect()
+    };
+    for dir in [
+        temp.path(),
+        &temp.join("app"),
+        &temp.join("app/target/debug"),
+    ] {
+        assert_eq!(summary(&mut tree, dir), summary(&mut fresh, dir));
+    }
+}
+
 #[test]
 fn sorting_keeps_parent_first_and_directories_before_files() {
     let root = PathBuf::from("/root");
@@ -275,6 +709,37 @@ fn sorting_keeps_parent_first_and_directories_before_files() {
     );
 }
 
+#[test]
+fn age_and_item_sorts_put_oldest_and_fullest_first() {
+    let root = PathBuf::from("/root");
+    let dated = |name: &str, modified: i64, items: u64| DirEntry {
+        modified,
+        items,
+        ..entry(root.join(name), name, 0, true, false)
+    };
+    let mut entries = vec![
+        dated("unknown", 0, 1),
+        dated("new", 200, 5),
+        dated("old", 100, 3),
+    ];
+    sort_by_modified(&mut entries);
+    assert_eq!(
+        entries
+            .iter()
+            .map(|e| e.name.to_string_lossy())
+            .collect::<Vec<_>>(),
+        ["old", "new", "unknown"]
+    );
+    sort_by_items(&mut entries);
+    assert_eq!(
+        entries
+            .iter()
+            .map(|e| e.name.to_string_lossy())
+            .collect::<Vec<_>>(),
+        ["new", "old", "unknown"]
+    );
+}
+
 #[test]
 #[ignore = "manual release microbenchmark"]
 fn manual_profile_path_hashers() {
@@ -338,6 +803,71 @@ fn manual_profile_tui_tree_from_env() {
         progress.get_files(),
         progress.get_bytes(),
         progress.get_errors(),
-        tree.children.len()
+        tree.dir_count()
+    );
+    let memory = tree.memory_usage();
+    println!(
+        "tui tree memory: bytes={memory} entries={} bytes_per_entry={:.1}",
+        tree.len(),
+        memory as f64 / tree.len().max(1) as f64
+    );
+    #[cfg(target_os = "linux")]
+    if let Some(peak) = std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+            Some(line.trim_start_matches("VmHWM:").trim().to_string())
+        })
+    {
+        println!("process peak rss: {peak}");
+    }
+}
+
+#[test]
+fn project_configs_flag_temp_entries_like_the_scanner() {
+    let temp = TempDir::new("tree-project");
+    temp.write(
+        "repo/.cleaner.toml",
+        b"[patterns]\ndirectories = { extend = ['out'] }\n[exclude]\npaths = ['keep']\n",
+    );
+    temp.write("repo/out/a", b"1");
+    temp.write("repo/keep/target/a", b"1");
+    temp.write("repo/target/a", b"1");
+    temp.write("other/out/a", b"1");
+    let build = |root: &Path| {
+        DirTree::build_with_progress(
+            root,
+            &matcher(),
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+        )
+    };
+    let temp_names = |tree: &mut DirTree, dir: &Path| -> Vec<_> {
+        tree.get_children(dir, SortOrder::Name)
+            .iter()
+            .filter(|entry| entry.is_temp)
+            .map(|entry| entry.name.clone())
+            .collect()
+    };
+
+    let mut tree = build(temp.path());
+    assert_eq!(temp_names(&mut tree, &temp.join("repo")), ["out", "target"]);
+    assert!(temp_names(&mut tree, &temp.join("repo/keep")).is_empty());
+    assert!(temp_names(&mut tree, &temp.join("other")).is_empty());
+    assert_eq!(tree.get_temp_stats(temp.path()).0, 2);
+
+    // Starting below the project and refreshing inside it both keep its rules.
+    let mut below = build(&temp.join("repo/keep"));
+    assert!(temp_names(&mut below, &temp.join("repo/keep")).is_empty());
+    temp.write("repo/out2/a", b"1");
+    temp.write(
+        "repo/.cleaner.toml",
+        b"[patterns]\ndirectories = { extend = ['out', 'out2'] }\n",
+    );
+    tree.refresh_dir(temp.path(), &temp.join("repo"), &matcher(), false);
+    assert_eq!(
+        temp_names(&mut tree, &temp.join("repo")),
+        ["out", "out2", "target"]
     );
 }
@@ -0,0 +1,60 @@
+//! The largest entries anywhere below a directory, without opening it one
+//! level at a time.
+
+use super::{DirEntry, DirTree};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::{Path, PathBuf};
+
+impl DirTree {
+    /// The `count` largest files at any depth below `dir`, largest first,
+    /// with their paths. With `leaf_dirs`, directories that hold no other
+    /// directory are ranked too, by their total. `apparent` ranks by length
+    /// instead of disk usage.
+    pub fn largest(
+        &self,
+        dir: &Path,
+        count: usize,
+        leaf_dirs: bool,
+        apparent: bool,
+    ) -> Vec<(PathBuf, DirEntry)> {
+        let Some(start) = self.listed_at(dir) else {
+            return Vec::new();
+        };
+        // The smallest of the best so far is on top, ready to give way.
+        let mut best = BinaryHeap::with_capacity(count.saturating_add(1));
+        let mut stack = vec![start];
+        while let Some(dir) = stack.pop() {
+            for &id in self.children_of(dir) {
+                let node = self.node(id);
+                let ranked = if !node.is_dir() {
+                    true
+                } else if node.is_listed() {
+                    stack.push(id);
+                    leaf_dirs
+                        && !self
+                            .children_of(id)
+                            .iter()
+                            .any(|&child| self.node(child).is_dir())
+                } else {
+                    false
+                };
+                if ranked && count > 0 {
+                    let size = if apparent { node.apparent } else { node.size };
+                    best.push(Reverse((size, id)));
+                    if best.len() > count {
+                        best.pop();
+                    }
+                }
+            }
+        }
+        let mut largest: Vec<_> = best
+            .into_iter()
+            .map(|Reverse((_, id))| (self.path_of(id), self.entry(id)))
+            .collect();
+        largest.sort_unstable_by(|(left_path, left), (right_path, right)| {
+            (right.size_as(apparent), left_path).cmp(&(left.size_as(apparent), right_path))
+        });
+        largest
+    }
+}
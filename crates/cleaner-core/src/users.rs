@@ -0,0 +1,83 @@
+//! User names for owner uids, from the password file.
+
+use std::collections::HashMap;
+
+/// The password file [`UserNames::load`] reads.
+#[cfg(unix)]
+const PASSWD: &str = "/etc/passwd";
+
+/// Names of the users the system knows, by uid.
+#[derive(Debug, Clone, Default)]
+pub struct UserNames {
+    names: HashMap<u32, String>,
+}
+
+impl UserNames {
+    /// The users in `/etc/passwd`; none where there is no such file, as on
+    /// Windows, or it cannot be read. Users only a directory service knows
+    /// stay nameless.
+    pub fn load() -> Self {
+        #[cfg(unix)]
+        {
+            std::fs::read_to_string(PASSWD)
+                .map(|text| Self::parse(&text))
+                .unwrap_or_default()
+        }
+        #[cfg(not(unix))]
+        {
+            Self::default()
+        }
+    }
+
+    /// The users of a password file: `name:password:uid:...` lines. When
+    /// two names share a uid the first one wins, as with `getpwuid`.
+    pub fn parse(text: &str) -> Self {
+        let mut names = HashMap::new();
+        for line in text.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split(':');
+            let (Some(name), Some(_), Some(uid)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            if let (false, Ok(uid)) = (name.is_empty(), uid.parse::<u32>()) {
+                names.entry(uid).or_insert_with(|| name.to_string());
+            }
+        }
+        Self { names }
+    }
+
+    pub fn get(&self, uid: u32) -> Option<&str> {
+        self.names.get(&uid).map(String::as_str)
+    }
+
+    /// The name of `uid`, or the number itself for a user without one.
+    pub fn name_or_uid(&self, uid: u32) -> String {
+        self.get(uid)
+            .map_or_else(|| uid.to_string(), str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwd_lines_map_uids_to_the_first_name() {
+        let names = UserNames::parse(
+            "# local users\n\
+             root:x:0:0:root:/root:/bin/bash\n\
+             toor:x:0:0:root again:/root:/bin/sh\n\
+             alice:x:1000:1000:Alice:/home/alice:/bin/zsh\n\
+             broken:x:not-a-uid:0::/:/bin/false\n\
+             short\n",
+        );
+        assert_eq!(names.get(0), Some("root"));
+        assert_eq!(names.get(1000), Some("alice"));
+        assert_eq!(names.name_or_uid(1000), "alice");
+        assert_eq!(names.name_or_uid(4242), "4242");
+        assert_eq!(names.names.len(), 2);
+    }
+}
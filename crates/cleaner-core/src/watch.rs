@@ -0,0 +1,148 @@
+//! Which directories of a tree changed on disk, so a browser can re-read
+//! just those with [`crate::tree::DirRefresh`]. Uses inotify on Linux;
+//! elsewhere [`Watcher::new`] fails.
+
+use std::path::PathBuf;
+
+/// What happened since the last [`Watcher::changes`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changes {
+    /// Directories whose listing or files changed, each once.
+    pub dirs: Vec<PathBuf>,
+    /// The kernel dropped events; only a full rescan is accurate again.
+    pub overflowed: bool,
+}
+
+/// Watches directories for entries created, deleted, moved or written.
+pub struct Watcher {
+    #[cfg(target_os = "linux")]
+    inotify: rustix::fd::OwnedFd,
+    /// The directory each watch descriptor stands for.
+    #[cfg(target_os = "linux")]
+    dirs: foldhash::HashMap<i32, PathBuf>,
+    #[cfg(target_os = "linux")]
+    buffer: Vec<std::mem::MaybeUninit<u8>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    pub fn new() -> Result<Self, String> {
+        use rustix::fs::inotify::{self, CreateFlags};
+        let inotify = inotify::init(CreateFlags::CLOEXEC | CreateFlags::NONBLOCK)
+            .map_err(|error| format!("Cannot watch for changes: {error}"))?;
+        Ok(Self {
+            inotify,
+            dirs: foldhash::HashMap::default(),
+            buffer: vec![std::mem::MaybeUninit::uninit(); 64 * 1024],
+        })
+    }
+
+    /// Watch each of `dirs`, skipping any that are gone or unreadable. Fails
+    /// once the per-user watch limit is reached; the directories watched
+    /// until then stay watched.
+    pub fn watch_all<'a>(
+        &mut self,
+        dirs: impl IntoIterator<Item = &'a PathBuf>,
+    ) -> Result<(), String> {
+        use rustix::fs::inotify::{self, WatchFlags};
+        use rustix::io::Errno;
+        let flags = WatchFlags::CREATE
+            | WatchFlags::DELETE
+            | WatchFlags::MODIFY
+            | WatchFlags::MOVED_FROM
+            | WatchFlags::MOVED_TO
+            | WatchFlags::ONLYDIR
+            | WatchFlags::DONT_FOLLOW
+            | WatchFlags::EXCL_UNLINK;
+        for dir in dirs {
+            match inotify::add_watch(&self.inotify, dir.as_path(), flags) {
+                Ok(wd) => {
+                    self.dirs.insert(wd, dir.clone());
+                }
+                Err(Errno::NOSPC) => {
+                    return Err(format!(
+                        "Watching only {} directories: the inotify limit is reached \
+                         (raise fs.inotify.max_user_watches)",
+                        self.dirs.len()
+                    ))
+                }
+                Err(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Drain the events waiting, without blocking.
+    pub fn changes(&mut self) -> Result<Changes, String> {
+        use rustix::fs::inotify::{ReadFlags, Reader};
+        use rustix::io::Errno;
+        let mut changes = Changes::default();
+        let mut seen = foldhash::HashSet::default();
+        let mut reader = Reader::new(&self.inotify, &mut self.buffer);
+        loop {
+            let event = match reader.next() {
+                Ok(event) => event,
+                Err(Errno::AGAIN | Errno::INTR) => break,
+                Err(error) => return Err(format!("Stopped watching for changes: {error}")),
+            };
+            let flags = event.events();
+            if flags.contains(ReadFlags::QUEUE_OVERFLOW) {
+                changes.overflowed = true;
+            } else if flags.contains(ReadFlags::IGNORED) {
+                self.dirs.remove(&event.wd());
+            } else if let Some(dir) = self.dirs.get(&event.wd()) {
+                if seen.insert(event.wd()) {
+                    changes.dirs.push(dir.clone());
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    pub fn new() -> Result<Self, String> {
+        Err("Watching for changes is only supported on Linux".to_string())
+    }
+
+    pub fn watch_all<'a>(
+        &mut self,
+        dirs: impl IntoIterator<Item = &'a PathBuf>,
+    ) -> Result<(), String> {
+        let _ = dirs;
+        Ok(())
+    }
+
+    pub fn changes(&mut self) -> Result<Changes, String> {
+        Ok(Changes::default())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn reports_each_changed_directory_once() {
+        let temp = TempDir::new("watch");
+        temp.write("a/old.txt", b"1");
+        temp.write("b/keep.txt", b"1");
+        let dirs = [temp.join("a"), temp.join("b")];
+        let mut watcher = Watcher::new().unwrap();
+        watcher.watch_all(&dirs).unwrap();
+        assert_eq!(watcher.changes().unwrap(), Changes::default());
+
+        temp.write("a/new.txt", b"22");
+        std::fs::remove_file(temp.join("a/old.txt")).unwrap();
+        let changes = watcher.changes().unwrap();
+        assert_eq!(changes.dirs, [temp.join("a")]);
+        assert!(!changes.overflowed);
+
+        std::fs::remove_dir_all(temp.join("b")).unwrap();
+        let changes = watcher.changes().unwrap();
+        assert_eq!(changes.dirs, [temp.join("b")]);
+        assert_eq!(watcher.dirs.len(), 1);
+    }
+}
@@ -1,32 +1,135 @@
-use super::state::{CleanState, DeleteState, RebuildState, SortMode};
-use super::App;
-use cleaner_core::deleter::Deleter;
+use super::state::{Baseline, CleanState, DeleteState, RebuildState, SortMode};
+use super::{change, App};
+use cleaner_core::deleter::{Deleter, Disposal};
+use cleaner_core::journal::Record;
+use cleaner_core::patterns::PatternMatcher;
 use cleaner_core::pool::SCAN_POOL;
+use cleaner_core::remove;
 use cleaner_core::scanner::Scanner;
 use cleaner_core::stats::Stats;
-use cleaner_core::tree::{self, DirTree};
+use cleaner_core::trash::Trash;
+use cleaner_core::tree::{self, DirTree, ScanIndex, ScanProgress, SortOrder};
 use crossbeam_channel::bounded;
+use std::cmp::Reverse;
 use std::ffi::OsStr;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::thread;
+use std::time::SystemTime;
 
 impl App {
     pub fn toggle_sort(&mut self) {
         let selected_name = self.selected_entry().map(|entry| entry.name.clone());
         self.sort_mode = match self.sort_mode {
             SortMode::Size => SortMode::Name,
-            SortMode::Name => SortMode::Size,
+            SortMode::Name => SortMode::Age,
+            SortMode::Age => SortMode::Items,
+            SortMode::Items => SortMode::Size,
         };
         self.load_current_dir_with_selection(selected_name.as_deref());
     }
 
+    /// Flip the browser between disk usage and apparent size. Both are
+    /// already in the tree, so nothing is rescanned.
+    pub fn toggle_apparent_size(&mut self) {
+        let selected_name = self.selected_entry().map(|entry| entry.name.clone());
+        self.apparent_size = !self.apparent_size;
+        self.set_status(if self.apparent_size {
+            "Sizes: apparent (file lengths)"
+        } else {
+            "Sizes: disk usage"
+        });
+        self.load_current_dir_with_selection(selected_name.as_deref());
+    }
+
+    /// Make `tree`, an earlier scan of the same root, what the growth view
+    /// compares with.
+    pub fn compare_with(&mut self, tree: DirTree, saved: Option<SystemTime>) {
+        self.baseline = Some(Baseline { tree, saved });
+    }
+
+    /// Show how each entry changed since the previous session's scan,
+    /// biggest growth first.
+    pub fn toggle_growth(&mut self) {
+        if self.baseline.is_none() {
+            self.set_status("No earlier scan to compare with: run with --index twice");
+            return;
+        }
+        let selected_name = self.selected_entry().map(|entry| entry.name.clone());
+        self.growth = !self.growth;
+        self.load_current_dir_with_selection(selected_name.as_deref());
+    }
+
+    /// List only the files of the selected entry's owner, and the folders
+    /// holding them, or everyone's again.
+    pub fn toggle_owner(&mut self) {
+        if self.owner.take().is_some() {
+            self.clear_status();
+            self.reload_keeping_selection();
+            return;
+        }
+        let uid = self
+            .selected_entry()
+            .filter(|entry| entry.name != "..")
+            .and_then(|entry| entry.uid);
+        match uid {
+            Some(uid) => self.show_owner(uid),
+            None => self.set_status("The owner of the selected entry is not known"),
+        }
+    }
+
+    /// List only the files of `uid`, and the folders holding them.
+    pub(super) fn show_owner(&mut self, uid: u32) {
+        self.owner = Some(uid);
+        self.set_status(format!(
+            "Showing only {}'s files: o shows everyone's",
+            self.user_name(uid)
+        ));
+        self.reload_keeping_selection();
+    }
+
+    fn reload_keeping_selection(&mut self) {
+        let selected_name = self.selected_entry().map(|entry| entry.name.clone());
+        self.load_current_dir_with_selection(selected_name.as_deref());
+    }
+
+    /// Switch Delete and Clean between permanent deletion and the trash. A
+    /// quarantine chosen on the command line switches to permanent deletion.
+    pub fn toggle_trash(&mut self) {
+        if self.is_busy() {
+            return;
+        }
+        self.disposal = match self.disposal {
+            Disposal::Trash(_) | Disposal::Quarantine(_) => {
+                self.set_status("Delete and Clean now delete permanently");
+                Disposal::Delete
+            }
+            Disposal::Delete => match Trash::from_env() {
+                Ok(trash) => {
+                    self.set_status(format!(
+                        "Delete and Clean now move to the trash ({})",
+                        trash.home().display()
+                    ));
+                    Disposal::Trash(Arc::new(trash))
+                }
+                Err(error) => {
+                    self.set_status(error);
+                    Disposal::Delete
+                }
+            },
+        };
+    }
+
     pub fn toggle_delete_confirm(&mut self) {
         if self.is_busy() {
             return;
         }
+        if self.owner.is_some() {
+            self.set_status("Delete and Clean act on everyone's files: press o to list them first");
+            return;
+        }
         if !self.entries.is_empty() {
             let entry = &self.entries[self.selected];
             if entry.name != ".." {
@@ -40,6 +143,10 @@ impl App {
         if self.is_busy() {
             return;
         }
+        if self.owner.is_some() {
+            self.set_status("Delete and Clean act on everyone's files: press o to list them first");
+            return;
+        }
         self.confirm_clean = !self.confirm_clean;
         self.confirm_delete = false;
         self.clean_preview = if self.confirm_clean {
@@ -55,15 +162,32 @@ impl App {
 
     pub(crate) fn load_current_dir_with_selection(&mut self, select_name: Option<&OsStr>) {
         if let Some(ref mut tree) = self.tree {
-            let by_name = self.sort_mode == SortMode::Name;
-            self.entries = tree.get_children(&self.current_path, by_name);
+            let order = match self.sort_mode {
+                SortMode::Name => SortOrder::Name,
+                SortMode::Age => SortOrder::Modified,
+                SortMode::Items => SortOrder::Items,
+                SortMode::Size if self.apparent_size => SortOrder::ApparentSize,
+                SortMode::Size => SortOrder::Size,
+            };
+            self.entries = match self.owner {
+                Some(uid) => Arc::new(tree.owned_children(&self.current_path, uid, order)),
+                None => tree.get_children(&self.current_path, order),
+            };
+            if let (true, Some(baseline)) = (self.growth, &self.baseline) {
+                let mut entries = self.entries.to_vec();
+                entries.sort_by_cached_key(|entry| {
+                    let change = change(&baseline.tree, &self.current_path, entry);
+                    (entry.name != "..", Reverse(change.size_delta()))
+                });
+                self.entries = Arc::new(entries);
+            }
 
             // Compute total size for current dir (excluding ".." and parent refs)
             self.total_size = self
                 .entries
                 .iter()
                 .filter(|e| e.name != "..")
-                .map(|e| e.size)
+                .map(|e| e.size_as(self.apparent_size))
                 .sum();
 
             // Try to preserve or find selection
@@ -95,7 +219,27 @@ impl App {
         self.load_current_dir();
     }
 
+    /// Save an index of the tree at `path` on every rebuild. `unverified`
+    /// is an index the tree was just loaded from without looking at disk;
+    /// a rebuild starts at once to bring both up to date. Without one, the
+    /// tree was scanned and saved already.
+    pub fn use_index(&mut self, path: PathBuf, unverified: Option<ScanIndex>) {
+        self.index_path = Some(path);
+        self.index_rotated = unverified.is_none();
+        if let Some(index) = unverified {
+            if let Some(tree) = self.tree.clone() {
+                self.compare_with(tree, index.saved());
+            }
+            self.spawn_rebuild("Index refreshed".to_string(), Some(index));
+        }
+    }
+
     pub(crate) fn start_rebuild(&mut self, completion_message: String) {
+        self.spawn_rebuild(completion_message, None);
+    }
+
+    /// Rebuild in the background, reusing what is unchanged since `previous`.
+    fn spawn_rebuild(&mut self, completion_message: String, previous: Option<ScanIndex>) {
         let root = self.root.clone();
         let matcher = Arc::clone(&self.matcher);
         let force = self.force;
@@ -105,8 +249,19 @@ impl App {
         let worker_cancelled = Arc::clone(&cancelled);
         let restore_path = self.current_path.clone();
         let restore_name = self.selected_entry().map(|entry| entry.name.clone());
+        let index_path = self.index_path.clone();
+        let rotate = index_path.is_some() && !std::mem::replace(&mut self.index_rotated, true);
         let handle = thread::spawn(move || {
-            DirTree::build_with_progress(&root, &matcher, worker_progress, worker_cancelled, force)
+            scan_tree(
+                &root,
+                &matcher,
+                worker_progress,
+                worker_cancelled,
+                force,
+                index_path.as_deref(),
+                rotate,
+                previous.as_ref(),
+            )
         });
         self.rebuild_state = Some(RebuildState {
             handle,
@@ -131,15 +286,22 @@ impl App {
     /// Check for completed deletion/clean and clear expired status
     pub fn tick(&mut self) {
         self.tick_deep();
+        self.tick_scan();
+        self.tick_watch();
         // Check if deletion completed
         if let Some(state) = self.delete_state.take() {
             if state.handle.is_finished() {
                 let deleted_name = state.entry_name.clone();
 
-                match state.handle.join() {
-                    Ok(Ok(())) => {
+                let result = state
+                    .handle
+                    .join()
+                    .unwrap_or_else(|_| Err("deletion thread panicked".to_string()));
+                match &result {
+                    Ok(()) => {
                         self.set_status(format!(
-                            "Deleted: {} ({})",
+                            "{}: {} ({})",
+                            self.deleted_label(),
                             state.entry_name.to_string_lossy(),
                             humansize::format_size(state.entry_size, humansize::BINARY)
                         ));
@@ -150,13 +312,16 @@ impl App {
 
                         self.load_current_dir_with_selection(Some(deleted_name.as_os_str()));
                     }
-                    Ok(Err(e)) => {
+                    Err(e) => {
                         self.set_status(format!("Error: {}", e));
                     }
-                    Err(_) => {
-                        self.set_status("Error: deletion thread panicked".to_string());
-                    }
                 }
+                self.journal_delete(
+                    &state.entry_path,
+                    state.is_dir,
+                    state.entry_size,
+                    result.as_ref().err().map(String::as_str),
+                );
             } else {
                 self.delete_state = Some(state);
             }
@@ -187,12 +352,13 @@ impl App {
         if let Some(state) = self.rebuild_state.take() {
             if state.handle.is_finished() {
                 match state.handle.join() {
-                    Ok(tree) => {
+                    Ok((tree, index_error)) => {
                         self.tree = Some(tree);
+                        self.rewatch();
                         if self
                             .tree
                             .as_ref()
-                            .is_some_and(|tree| tree.children.contains_key(&state.restore_path))
+                            .is_some_and(|tree| tree.contains_dir(&state.restore_path))
                         {
                             self.current_path = state.restore_path;
                         } else {
@@ -201,7 +367,10 @@ impl App {
                             self.selected = 0;
                         }
                         self.load_current_dir_with_selection(state.restore_name.as_deref());
-                        self.set_status(state.completion_message);
+                        self.set_status(match index_error {
+                            Some(error) => format!("{} ({error})", state.completion_message),
+                            None => state.completion_message,
+                        });
                     }
                     Err(_) => self.set_status("Error: rebuild thread panicked".to_string()),
                 }
@@ -220,7 +389,36 @@ impl App {
     }
 
     pub(crate) fn remove_dir_fast(path: PathBuf) -> Result<(), String> {
-        std::fs::remove_dir_all(&path).map_err(|e| e.to_string())
+        remove::remove_tree(&path, false, None).into_result()
+    }
+
+    /// The status label for a finished delete.
+    fn deleted_label(&self) -> String {
+        match self.disposal.destination() {
+            Some(destination) => format!("Moved to {destination}"),
+            None => "Deleted".to_string(),
+        }
+    }
+
+    /// Record a finished manual delete in the journal, if there is one. A
+    /// journal that cannot be written replaces the status line.
+    fn journal_delete(&mut self, path: &Path, is_dir: bool, bytes: u64, error: Option<&str>) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let record = Record {
+            path,
+            kind: if is_dir { "dir" } else { "file" },
+            bytes: if error.is_some() { 0 } else { bytes },
+            rule: None,
+            dry_run: false,
+            disposal: self.disposal.name(),
+            source: "tui-delete",
+            error,
+        };
+        if let Err(error) = journal.write([record]) {
+            self.set_status(error);
+        }
     }
 
     /// Start async deletion
@@ -255,8 +453,24 @@ impl App {
                 return;
             }
 
+            if self.disposal.destination().is_some() {
+                let disposal = self.disposal.clone();
+                let worker_path = path.clone();
+                let handle = thread::spawn(move || disposal.dispose(&worker_path, entry_size));
+                self.delete_state = Some(DeleteState {
+                    handle,
+                    entry_name,
+                    entry_path: path,
+                    is_dir,
+                    entry_size,
+                });
+                self.confirm_delete = false;
+                return;
+            }
+
             if !is_dir {
-                match fs::remove_file(&path) {
+                let result = fs::remove_file(&path).map_err(|error| error.to_string());
+                match &result {
                     Ok(()) => {
                         self.set_status(format!(
                             "Deleted: {} ({})",
@@ -270,6 +484,12 @@ impl App {
                     }
                     Err(error) => self.set_status(format!("Error: {error}")),
                 }
+                self.journal_delete(
+                    &path,
+                    false,
+                    entry_size,
+                    result.as_ref().err().map(String::as_str),
+                );
                 self.confirm_delete = false;
                 return;
             }
@@ -300,6 +520,10 @@ impl App {
         let worker_pool = cleaner_core::pool::build_worker_pool(num_threads, "cleaner-worker");
         let cancelled = Arc::new(AtomicBool::new(false));
         let worker_cancelled = Arc::clone(&cancelled);
+        let disposal = self.disposal.clone();
+        let journal = self.journal.clone();
+        let device = config.root_device(&root);
+        let count_links = config.count_links;
 
         let handle = thread::spawn(move || {
             let stats = Arc::new(Stats::new());
@@ -310,7 +534,15 @@ impl App {
             let scan_handle =
                 thread::spawn(move || scanner.scan_with_cancel(tx, &worker_cancelled));
 
-            let deleter = Deleter::with_pool(Arc::clone(&stats), false, false, worker_pool);
+            let mut deleter = Deleter::with_pool(Arc::clone(&stats), false, false, worker_pool)
+                .disposal(disposal)
+                .count_links(count_links);
+            if let Some(journal) = journal {
+                deleter = deleter.journal(journal, "tui-clean");
+            }
+            if let Some(device) = device {
+                deleter = deleter.one_file_system(device);
+            }
             deleter.process(rx);
             if let Ok(summary) = scan_handle.join() {
                 stats.add_errors(summary.errors);
@@ -360,3 +592,27 @@ impl App {
         }
     }
 }
+
+/// Build the tree of `root`. With `index_path`, the walk reuses what is
+/// unchanged since `previous` and is saved there as the next index; the
+/// error is why it could not be. `rotate` keeps the index it replaces as
+/// the previous one, which only a session's first save does.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn scan_tree(
+    root: &Path,
+    matcher: &PatternMatcher,
+    progress: Arc<ScanProgress>,
+    cancelled: Arc<AtomicBool>,
+    force: bool,
+    index_path: Option<&Path>,
+    rotate: bool,
+    previous: Option<&ScanIndex>,
+) -> (DirTree, Option<String>) {
+    let Some(index_path) = index_path else {
+        let tree = DirTree::build_with_progress(root, matcher, progress, cancelled, force);
+        return (tree, None);
+    };
+    let (tree, index) = DirTree::build_indexed(root, matcher, progress, cancelled, force, previous);
+    let error = index.and_then(|index| index.save(index_path, rotate).err());
+    (tree, error)
+}
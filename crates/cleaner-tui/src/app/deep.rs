@@ -248,10 +248,11 @@ impl App {
 
         let home = state.home.clone();
         let sink = Arc::clone(&state.errors);
+        let journal = self.journal.clone();
         state.phase = DeepPhase::Running;
         state.typed.clear();
         state.run_handle = Some(thread::spawn(move || {
-            sysclean::run(marked, &home, false, sink)
+            sysclean::run(marked, &home, false, sink, journal)
         }));
     }
 
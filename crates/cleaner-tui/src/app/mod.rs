@@ -3,20 +3,33 @@
 mod actions;
 mod deep;
 mod navigation;
+mod owners;
+mod scan;
 mod state;
+mod top;
+mod types;
+mod watch;
 
 #[cfg(test)]
 mod tests;
 
+pub(crate) use actions::scan_tree;
 pub use deep::{visible_rows, DESTRUCTIVE_WORD};
-pub use state::{CleanState, DeepPhase, DeepState, DeleteState, RebuildState, SortMode};
+pub use state::{
+    Baseline, CleanState, DeepPhase, DeepState, DeleteState, OwnersState, RebuildState, Refreshing,
+    SortMode, TopState, TypesState, WatchState,
+};
+pub use top::TOP_COUNT;
 
-use cleaner_core::get_disk_usage;
+use cleaner_core::deleter::Disposal;
+use cleaner_core::journal::Journal;
 use cleaner_core::patterns::PatternMatcher;
-use cleaner_core::tree::{DirEntry, DirTree};
-use std::path::PathBuf;
+use cleaner_core::tree::{Change, DirEntry, DirTree, Measure, ScanProgress};
+use cleaner_core::UserNames;
+use cleaner_core::{get_disk_usage, get_inode_usage};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Instant, SystemTime};
 
 pub struct App {
     pub root: PathBuf,
@@ -25,6 +38,10 @@ pub struct App {
     pub entries: Arc<Vec<DirEntry>>,
     pub selected: usize,
     pub sort_mode: SortMode,
+    /// Show and sort by length instead of disk usage; toggled with `a`.
+    pub apparent_size: bool,
+    /// Show and sort by change since [`App::baseline`]; toggled with `+`.
+    pub growth: bool,
     pub confirm_delete: bool,
     pub confirm_clean: bool,
     pub status_message: Option<String>,
@@ -32,28 +49,60 @@ pub struct App {
     pub total_size: u64,
     pub disk_total: u64,
     pub disk_free: u64,
+    /// Inode counts for the same filesystem; 0 where it has no inode limit.
+    pub inode_total: u64,
+    pub inode_free: u64,
     pub force: bool,
+    /// What Delete and Clean do with their targets; toggled with `2`.
+    pub disposal: Disposal,
     /// `Some` while the Deep Clean view is open. The browser renders instead
     /// when this is `None`.
     pub deep: Option<DeepState>,
+    /// `Some` while the Top panel is open, which also renders instead of
+    /// the browser.
+    pub top: Option<TopState>,
+    /// `Some` while the Types overlay is open over the browser.
+    pub types: Option<TypesState>,
+    /// `Some` while the Owners overlay is open over the browser.
+    pub owners: Option<OwnersState>,
+    /// Only this uid's files are listed while set; toggled with `o` or
+    /// picked in the Owners overlay.
+    pub owner: Option<u32>,
+    /// Names for the owner column.
+    users: UserNames,
+    /// Where Delete, Clean and Deep Clean record what they removed.
+    journal: Option<Arc<Journal>>,
+    /// The tree from the previous session's index, once there is a newer one.
+    baseline: Option<Baseline>,
+    /// Where every rebuild saves the tree's index; set with `--index`.
+    index_path: Option<PathBuf>,
+    /// Whether this session already kept the last session's index as the
+    /// previous one; later saves only replace the latest.
+    index_rotated: bool,
     matcher: Arc<PatternMatcher>,
     tree: Option<DirTree>,
     delete_state: Option<DeleteState>,
     clean_state: Option<CleanState>,
     rebuild_state: Option<RebuildState>,
+    /// `Some` while `--watch` keeps the tree current.
+    watch: Option<WatchState>,
+    /// `Some` while the first scan is still filling in the tree.
+    scan: Option<Arc<ScanProgress>>,
     clean_preview: Option<(usize, usize, u64)>,
 }
 
 impl App {
     #[allow(dead_code)]
     pub fn new(root: PathBuf, matcher: Arc<PatternMatcher>, force: bool) -> Self {
-        Self {
+        let mut app = Self {
             current_path: root.clone(),
             root,
             path_stack: Vec::new(),
             entries: Arc::new(Vec::new()),
             selected: 0,
             sort_mode: SortMode::Size,
+            apparent_size: matcher.config().apparent_size,
+            growth: false,
             confirm_delete: false,
             confirm_clean: false,
             status_message: None,
@@ -61,15 +110,33 @@ impl App {
             total_size: 0,
             disk_total: 0,
             disk_free: 0,
+            inode_total: 0,
+            inode_free: 0,
             force,
+            // `cli::run` has already rejected a disposal that cannot be set
+            // up.
+            disposal: Disposal::for_config(&matcher.config()).unwrap_or_default(),
             deep: None,
+            top: None,
+            types: None,
+            owners: None,
+            owner: None,
+            users: UserNames::load(),
+            journal: None,
+            baseline: None,
+            index_path: None,
+            index_rotated: false,
             matcher,
             tree: None,
             delete_state: None,
             clean_state: None,
             rebuild_state: None,
+            watch: None,
+            scan: None,
             clean_preview: None,
-        }
+        };
+        app.open_journal();
+        app
     }
 
     pub fn new_with_tree(
@@ -85,6 +152,8 @@ impl App {
             entries: Arc::new(Vec::new()),
             selected: 0,
             sort_mode: SortMode::Size,
+            apparent_size: matcher.config().apparent_size,
+            growth: false,
             confirm_delete: false,
             confirm_clean: false,
             status_message: None,
@@ -92,19 +161,45 @@ impl App {
             total_size: 0,
             disk_total: 0,
             disk_free: 0,
+            inode_total: 0,
+            inode_free: 0,
             force,
+            // `cli::run` has already rejected a disposal that cannot be set
+            // up.
+            disposal: Disposal::for_config(&matcher.config()).unwrap_or_default(),
             deep: None,
+            top: None,
+            types: None,
+            owners: None,
+            owner: None,
+            users: UserNames::load(),
+            journal: None,
+            baseline: None,
+            index_path: None,
+            index_rotated: false,
             matcher,
             tree: Some(tree),
             delete_state: None,
             clean_state: None,
             rebuild_state: None,
+            watch: None,
+            scan: None,
             clean_preview: None,
         };
+        app.open_journal();
         app.load_current_dir();
         app
     }
 
+    /// Open the configured journal. One that cannot be opened leaves deletes
+    /// unrecorded and says so on the status line.
+    fn open_journal(&mut self) {
+        match Journal::for_config(&self.matcher.config()) {
+            Ok(journal) => self.journal = journal,
+            Err(error) => self.set_status(format!("Journal disabled: {error}")),
+        }
+    }
+
     /// Check if currently deleting or cleaning
     pub fn is_busy(&self) -> bool {
         self.delete_state.is_some()
@@ -136,6 +231,26 @@ impl App {
         self.entries.get(self.selected)
     }
 
+    /// The name of user `uid`, or the number where it has none.
+    pub fn user_name(&self, uid: u32) -> String {
+        self.users.name_or_uid(uid)
+    }
+
+    /// How `entry` of the current folder changed since the baseline; `None`
+    /// outside the growth view.
+    pub fn growth_of(&self, entry: &DirEntry) -> Option<Change> {
+        let baseline = self.baseline.as_ref().filter(|_| self.growth)?;
+        (entry.name != "..").then(|| change(&baseline.tree, &self.current_path, entry))
+    }
+
+    /// When the baseline's index was saved, while the growth view is on.
+    pub fn growth_since(&self) -> Option<Option<SystemTime>> {
+        self.baseline
+            .as_ref()
+            .filter(|_| self.growth)
+            .map(|baseline| baseline.saved)
+    }
+
     pub fn update_disk_usage(&mut self) {
         if let Some((total, free)) = get_disk_usage(self.current_path.as_path()) {
             self.disk_total = total;
@@ -144,6 +259,8 @@ impl App {
             self.disk_total = 0;
             self.disk_free = 0;
         }
+        (self.inode_total, self.inode_free) =
+            get_inode_usage(self.current_path.as_path()).unwrap_or_default();
     }
 
     pub fn set_status(&mut self, msg: impl Into<String>) {
@@ -163,6 +280,16 @@ impl App {
     }
 }
 
+/// How `entry` of `dir` differs from its counterpart in `baseline`.
+fn change(baseline: &DirTree, dir: &Path, entry: &DirEntry) -> Change {
+    let path = dir.join(&entry.name);
+    Change {
+        old: baseline.find(&path).as_ref().map(Measure::from),
+        new: Some(Measure::from(entry)),
+        path,
+    }
+}
+
 impl Drop for App {
     fn drop(&mut self) {
         if let Some(mut state) = self.deep.take() {
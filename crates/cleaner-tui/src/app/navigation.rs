@@ -1,5 +1,6 @@
 use super::App;
 use std::ffi::OsStr;
+use std::path::Path;
 
 impl App {
     pub fn move_up(&mut self) {
@@ -63,4 +64,25 @@ impl App {
         self.confirm_delete = false;
         self.confirm_clean = false;
     }
+
+    /// Open the folder holding `path`, a path below the root, with `path`
+    /// selected. Going back climbs one folder at a time to the root.
+    pub fn reveal(&mut self, path: &Path) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        let Ok(below_root) = parent.strip_prefix(&self.root) else {
+            return;
+        };
+        self.path_stack.clear();
+        let mut current = self.root.clone();
+        for component in below_root.components() {
+            self.path_stack.push(current.clone());
+            current.push(component);
+        }
+        self.current_path = current;
+        self.load_current_dir_with_selection(Some(name));
+        self.confirm_delete = false;
+        self.confirm_clean = false;
+    }
 }
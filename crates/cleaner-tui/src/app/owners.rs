@@ -0,0 +1,59 @@
+//! The Owners overlay: whose files fill the current folder, to list only
+//! one owner's.
+
+use super::state::OwnersState;
+use super::App;
+
+impl App {
+    /// Open the Owners overlay on the current folder, on the owner of the
+    /// selected entry when it has one.
+    pub fn open_owners(&mut self) {
+        if self.is_busy() || self.deep.is_some() || self.top.is_some() {
+            return;
+        }
+        let Some(tree) = self.tree.as_ref() else {
+            return;
+        };
+        let owners = tree.owners(&self.current_path);
+        let selected = self.selected_entry().and_then(|entry| entry.uid);
+        let cursor = owners
+            .iter()
+            .position(|(uid, _)| uid.is_some() && *uid == selected)
+            .unwrap_or(0);
+        self.owners = Some(OwnersState {
+            dir: self.current_path.clone(),
+            owners,
+            cursor,
+        });
+        self.clear_status();
+    }
+
+    /// True while the Owners overlay is open.
+    pub fn in_owners(&self) -> bool {
+        self.owners.is_some()
+    }
+
+    pub fn close_owners(&mut self) {
+        self.owners = None;
+    }
+
+    pub fn owners_move(&mut self, delta: isize) {
+        if let Some(state) = self.owners.as_mut() {
+            let last = state.owners.len().saturating_sub(1);
+            state.cursor = state.cursor.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// List only the files of the owner under the cursor and close the
+    /// overlay.
+    pub fn owners_pick(&mut self) {
+        let Some(state) = self.owners.take() else {
+            return;
+        };
+        match state.owners.get(state.cursor) {
+            Some((Some(uid), _)) => self.show_owner(*uid),
+            Some((None, _)) => self.set_status("The owner of these files is not known"),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Browsing while the first scan runs: the tree grows as the walk lists
+//! directories, and the finished tree replaces it in place.
+
+use super::App;
+use cleaner_core::patterns::PatternMatcher;
+use cleaner_core::tree::{DirTree, ScanProgress};
+use foldhash::{HashMap, HashMapExt};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+impl App {
+    /// A browser over the scan `progress` reports on. It starts empty and
+    /// shows each directory as soon as the walk has listed it.
+    pub fn scanning(
+        root: PathBuf,
+        matcher: Arc<PatternMatcher>,
+        progress: Arc<ScanProgress>,
+        force: bool,
+    ) -> Self {
+        progress.stream_listings();
+        let mut app =
+            Self::new_with_tree(root, matcher, DirTree::from_children(HashMap::new()), force);
+        app.scan = Some(progress);
+        app
+    }
+
+    /// True until [`App::finish_scan`]: the tree is partial and directory
+    /// sizes only count what has been listed so far.
+    pub fn is_provisional(&self) -> bool {
+        self.scan.is_some()
+    }
+
+    /// The scan still filling in the tree.
+    pub fn scan_progress(&self) -> Option<&ScanProgress> {
+        self.scan.as_deref()
+    }
+
+    /// Swap the partial tree for the finished one, staying in the same
+    /// folder on the same entry where they still exist.
+    pub fn finish_scan(&mut self, tree: DirTree) {
+        self.scan = None;
+        let selected = self.selected_entry().map(|entry| entry.name.clone());
+        self.tree = Some(tree);
+        if !self
+            .tree
+            .as_ref()
+            .is_some_and(|tree| tree.contains_dir(&self.current_path))
+        {
+            self.current_path = self.root.clone();
+            self.path_stack.clear();
+        }
+        self.load_current_dir_with_selection(selected.as_deref());
+    }
+
+    /// Add what the scan listed since the last tick. Called from
+    /// [`App::tick`].
+    pub(crate) fn tick_scan(&mut self) {
+        let (Some(progress), Some(tree)) = (self.scan.as_ref(), self.tree.as_mut()) else {
+            return;
+        };
+        let listings = progress.take_listings();
+        if listings.is_empty() {
+            return;
+        }
+        for (dir, entries) in listings {
+            tree.add_listing(&self.root, &dir, &entries);
+        }
+        let selected = self.selected_entry().map(|entry| entry.name.clone());
+        self.load_current_dir_with_selection(selected.as_deref());
+    }
+}
@@ -1,16 +1,23 @@
 use cleaner_core::sysclean::{Candidate, RunReport, Target};
-use cleaner_core::tree::{self, DirTree};
+use cleaner_core::tree::{self, Breakdown, DirEntry, DirRefresh, DirTree, Tally};
+use cleaner_core::watch::Watcher;
+use crossbeam_channel::Receiver;
 use std::collections::HashSet;
 use std::ffi::OsString;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
+use std::time::{Instant, SystemTime};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortMode {
     Size,
     Name,
+    /// Oldest first.
+    Age,
+    /// Most entries first, to find what is eating inodes.
+    Items,
 }
 
 /// Deletion state for async deletion
@@ -28,8 +35,41 @@ pub struct CleanState {
     pub cancelled: Arc<AtomicBool>,
 }
 
+/// An earlier tree of the same root for the growth view to compare with.
+pub struct Baseline {
+    pub tree: DirTree,
+    /// When its index was saved.
+    pub saved: Option<SystemTime>,
+}
+
+/// Live updates from disk while `--watch` is on.
+pub struct WatchState {
+    /// Watches the tree's directories; `None` while `pending` sets it up.
+    pub watcher: Option<Watcher>,
+    /// Watches every directory of a new tree, and says why it stopped short.
+    pub pending: Option<JoinHandle<(Watcher, Option<String>)>>,
+    /// Directories that changed and are yet to be re-read.
+    pub changed: HashSet<PathBuf>,
+    /// Directories being re-read in the background.
+    pub refreshing: Option<Refreshing>,
+    /// The kernel dropped events, so the tree needs a rebuild once nothing
+    /// else is running.
+    pub overflowed: bool,
+    /// When changed directories were last re-read.
+    pub applied: Instant,
+}
+
+/// Changed directories re-read on a worker thread, which sends each back
+/// as it is read, parents first.
+pub struct Refreshing {
+    /// The directories not yet folded into the tree.
+    pub dirs: Vec<PathBuf>,
+    pub results: Receiver<DirRefresh>,
+}
+
 pub struct RebuildState {
-    pub handle: JoinHandle<DirTree>,
+    /// The new tree, and why its index could not be saved.
+    pub handle: JoinHandle<(DirTree, Option<String>)>,
     pub completion_message: String,
     pub progress: Arc<tree::ScanProgress>,
     pub cancelled: Arc<AtomicBool>,
@@ -37,6 +77,34 @@ pub struct RebuildState {
     pub restore_name: Option<OsString>,
 }
 
+/// The Top panel: the largest entries anywhere below a folder.
+pub struct TopState {
+    /// The folder the ranking covers.
+    pub dir: PathBuf,
+    pub items: Vec<(PathBuf, DirEntry)>,
+    pub cursor: usize,
+    /// Rank folders that hold no other folder too; toggled with `d`.
+    pub leaf_dirs: bool,
+}
+
+/// The Types overlay: how much of a folder each kind of file takes up.
+pub struct TypesState {
+    /// The folder the breakdown covers.
+    pub dir: PathBuf,
+    pub breakdown: Breakdown,
+    /// First line shown.
+    pub scroll: usize,
+}
+
+/// The Owners overlay: whose files fill a folder, to pick one owner's from.
+pub struct OwnersState {
+    /// The folder the totals cover.
+    pub dir: PathBuf,
+    /// Largest on disk first; `None` for files whose owner is not known.
+    pub owners: Vec<(Option<u32>, Tally)>,
+    pub cursor: usize,
+}
+
 /// Where the Deep Clean view is in its lifecycle.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DeepPhase {
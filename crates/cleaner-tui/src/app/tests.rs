@@ -1,12 +1,16 @@
 use super::state::{DeleteState, SortMode};
 use super::App;
 use cleaner_core::config::Config;
+use cleaner_core::deleter::Disposal;
+use cleaner_core::journal::Journal;
 use cleaner_core::patterns::PatternMatcher;
 use cleaner_core::test_support::TempDir;
-use cleaner_core::tree::{DirEntry, DirTree};
+use cleaner_core::trash::Trash;
+use cleaner_core::tree::{DirEntry, DirTree, ScanIndex, ScanProgress};
 use foldhash::{HashMap, HashMapExt};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -15,18 +19,12 @@ fn matcher() -> Arc<PatternMatcher> {
     Arc::new(PatternMatcher::new(Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     })))
 }
 
 fn entry(_path: PathBuf, name: &str, size: u64, is_dir: bool, is_temp: bool) -> DirEntry {
-    DirEntry {
-        name: name.into(),
-        size,
-        is_dir,
-        is_temp,
-    }
+    DirEntry::new(name, size, is_dir, is_temp)
 }
 
 fn app_with_tree(temp: &TempDir) -> App {
@@ -86,6 +84,11 @@ fn navigation_selection_sorting_and_confirmations_work() {
     app.toggle_sort();
     assert_eq!(app.sort_mode, SortMode::Name);
     app.toggle_sort();
+    assert_eq!(app.sort_mode, SortMode::Age);
+    app.toggle_sort();
+    assert_eq!(app.sort_mode, SortMode::Items);
+    assert_eq!(app.entries[0].name, "folder");
+    app.toggle_sort();
     assert_eq!(app.sort_mode, SortMode::Size);
 
     select(&mut app, "cache.pyc");
@@ -98,6 +101,32 @@ fn navigation_selection_sorting_and_confirmations_work() {
     assert_eq!(app.current_temp_stats(), (0, 2, 11));
 }
 
+#[test]
+fn apparent_size_toggle_resorts_without_rescanning() {
+    let temp = TempDir::new("app-apparent");
+    let root = temp.path().to_path_buf();
+    let mut children = HashMap::new();
+    children.insert(
+        root.clone(),
+        vec![
+            DirEntry {
+                apparent_size: 1 << 20,
+                ..entry(temp.join("disk.img"), "disk.img", 4096, false, false)
+            },
+            entry(temp.join("dense.bin"), "dense.bin", 8192, false, false),
+        ],
+    );
+    let mut app = App::new_with_tree(root, matcher(), DirTree::from_children(children), false);
+    assert_eq!(app.entries[0].name, "dense.bin");
+    assert_eq!(app.total_size, 4096 + 8192);
+
+    app.toggle_apparent_size();
+    assert!(app.apparent_size);
+    assert_eq!(app.entries[0].name, "disk.img");
+    assert_eq!(app.total_size, (1 << 20) + 8192);
+    assert_eq!(app.selected_entry().unwrap().name, "dense.bin");
+}
+
 #[test]
 fn entering_and_leaving_directory_restores_selection() {
     let temp = TempDir::new("app-enter");
@@ -234,6 +263,63 @@ fn directory_deletion_helper_and_error_status_are_covered() {
     );
 }
 
+#[cfg(unix)]
+#[test]
+fn trash_mode_moves_deleted_and_cleaned_entries_to_the_trash() {
+    let temp = TempDir::new("app-trash");
+    let bin = TempDir::new("app-trash-bin");
+    temp.mkdir("folder");
+    temp.write("cache.pyc", b"123");
+    let mut app = app_with_tree(&temp);
+    app.disposal = Disposal::Trash(Arc::new(Trash::new(bin.join("Trash"))));
+    select(&mut app, "cache.pyc");
+    app.delete_selected();
+    wait_until_idle(&mut app);
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Moved to the trash: cache.pyc (3 B)")
+    );
+    assert!(bin.join("Trash/files/cache.pyc").is_file());
+
+    temp.write("folder/target/artifact", b"12345");
+    app.clean_current();
+    wait_until_idle(&mut app);
+    assert!(!temp.join("folder/target").exists());
+    assert!(bin.join("Trash/files/target/artifact").is_file());
+
+    app.toggle_trash();
+    assert!(matches!(app.disposal, Disposal::Delete));
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("Delete and Clean now delete permanently")
+    );
+}
+
+#[test]
+fn journal_records_manual_deletes_and_cleans() {
+    let temp = TempDir::new("app-journal");
+    let logs = TempDir::new("app-journal-logs");
+    temp.mkdir("folder");
+    temp.write("cache.pyc", b"123");
+    temp.write("folder/target/artifact", b"12345");
+    let mut app = app_with_tree(&temp);
+    app.journal = Some(Arc::new(Journal::open(&logs.join("audit.jsonl")).unwrap()));
+    select(&mut app, "cache.pyc");
+    app.delete_selected();
+    wait_until_idle(&mut app);
+    app.clean_current();
+    wait_until_idle(&mut app);
+    assert!(!temp.join("folder/target").exists());
+
+    let content = fs::read_to_string(logs.join("audit.jsonl")).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2, "{content}");
+    assert!(lines[0].contains("\"type\":\"file\",\"bytes\":3,\"rule\":null"));
+    assert!(lines[0].contains("\"source\":\"tui-delete\",\"error\":null"));
+    assert!(lines[1].contains("\"type\":\"dir\",\"bytes\":5,\"rule\":\"target\""));
+    assert!(lines[1].contains("\"source\":\"tui-clean\""));
+}
+
 #[test]
 fn clean_current_removes_default_patterns_and_rebuilds() {
     let temp = TempDir::new("app-clean");
@@ -259,6 +345,162 @@ fn clean_current_removes_default_patterns_and_rebuilds() {
     assert_eq!(app.status_message.as_deref(), Some("Refreshed"));
 }
 
+#[test]
+fn indexed_rebuilds_save_the_index_and_verify_a_loaded_tree() {
+    let temp = TempDir::new("app-index");
+    temp.write("data/old.bin", b"12345");
+    let cache = TempDir::new("app-index-cache");
+    let index_path = cache.join("tree.idx");
+    let mut app = App::new(temp.path().to_path_buf(), matcher(), false);
+    app.scan_current_dir();
+    app.use_index(index_path.clone(), None);
+    assert!(!app.is_rebuilding());
+    app.refresh();
+    wait_until_idle(&mut app);
+    assert_eq!(app.status_message.as_deref(), Some("Refreshed"));
+    let index = ScanIndex::load(&index_path, temp.path()).unwrap();
+    // The session's scan saved first, so the refresh kept no previous one.
+    let previous = ScanIndex::previous_path(&index_path);
+    assert!(!previous.exists());
+
+    temp.write("data/new.bin", b"1");
+    let tree = DirTree::build_from_index(
+        &index,
+        &matcher(),
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+    let mut app = App::new_with_tree(temp.path().to_path_buf(), matcher(), tree, false);
+    assert_eq!(app.entries[0].items, 2);
+    app.toggle_growth();
+    assert!(!app.growth);
+    app.use_index(index_path, Some(index));
+    assert!(app.is_rebuilding());
+    wait_until_idle(&mut app);
+    assert_eq!(app.status_message.as_deref(), Some("Index refreshed"));
+    assert_eq!(app.entries[0].items, 3);
+    // A new session keeps the last one's index, and keeps it through
+    // further rebuilds.
+    app.refresh();
+    wait_until_idle(&mut app);
+    let kept = ScanIndex::load(&previous, temp.path()).unwrap();
+    let saved = DirTree::build_from_index(
+        &kept,
+        &matcher(),
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    )
+    .find(&temp.join("data"))
+    .unwrap();
+    assert_eq!(saved.items, 2);
+
+    app.toggle_growth();
+    assert!(app.growth);
+    let change = app.growth_of(&app.entries[0]).unwrap();
+    assert_eq!(change.items_delta(), 1);
+    app.toggle_growth();
+    assert!(app.growth_of(&app.entries[0]).is_none());
+}
+
+#[test]
+fn scanning_browser_grows_with_the_walk_and_keeps_its_place() {
+    let temp = TempDir::new("app-scanning");
+    temp.write("logs/a.log", &[0; 2000]);
+    temp.write("logs/nested/b.log", &[0; 3000]);
+    temp.write("notes.txt", b"1");
+    let progress = Arc::new(ScanProgress::new());
+    let mut app = App::scanning(
+        temp.path().to_path_buf(),
+        matcher(),
+        Arc::clone(&progress),
+        false,
+    );
+    assert!(app.is_provisional());
+    assert!(app.entries.is_empty());
+    let tree = DirTree::build_with_progress(
+        temp.path(),
+        &matcher(),
+        progress,
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+
+    app.tick();
+    assert_eq!(app.entries[0].name, "logs");
+    assert_eq!(app.entries[0].items, 4);
+    app.enter();
+    app.selected = app.entries.iter().position(|e| e.name == "a.log").unwrap();
+    app.finish_scan(tree);
+    assert!(!app.is_provisional());
+    assert_eq!(app.current_path, temp.join("logs"));
+    assert_eq!(app.selected_entry().unwrap().name, "a.log");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watching_folds_changes_on_disk_into_the_tree() {
+    let temp = TempDir::new("app-watch");
+    temp.write("logs/a.log", b"1");
+    let mut app = App::new(temp.path().to_path_buf(), matcher(), false);
+    app.scan_current_dir();
+    app.start_watching();
+    for _ in 0..200 {
+        app.tick();
+        if app.is_watching() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(app.is_watching());
+    // Re-reads run on a worker, and new folders are read once more after
+    // they are watched, so tick until nothing is left to do.
+    let settle = |app: &mut App| {
+        for _ in 0..400 {
+            app.watch.as_mut().unwrap().applied = Instant::now() - Duration::from_secs(2);
+            app.tick();
+            let state = app.watch.as_ref().unwrap();
+            if state.refreshing.is_none() && state.changed.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    };
+
+    temp.write("logs/b.log", &[0; 5000]);
+    temp.write("cache/x.bin", b"12");
+    settle(&mut app);
+    let items = |app: &App, name: &str| {
+        app.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.items)
+    };
+    assert_eq!(items(&app, "logs"), Some(3));
+    assert_eq!(items(&app, "cache"), Some(2));
+
+    // The new folder is watched too, and a folder that goes away while
+    // open leaves the browser in its parent.
+    temp.write("cache/y.bin", b"3");
+    settle(&mut app);
+    assert_eq!(items(&app, "cache"), Some(3));
+    app.selected = app.entries.iter().position(|e| e.name == "logs").unwrap();
+    app.enter();
+    fs::remove_dir_all(temp.join("logs")).unwrap();
+    settle(&mut app);
+    assert_eq!(app.current_path, temp.path());
+    assert_eq!(items(&app, "logs"), None);
+
+    // Setting up the watches of a rebuilt tree misses nothing: the old
+    // watcher stays until the new one is ready.
+    app.rewatch();
+    assert!(app.is_watching());
+    temp.write("cache/z.bin", b"4");
+    settle(&mut app);
+    assert_eq!(items(&app, "cache"), Some(4));
+}
+
 #[test]
 fn tick_expires_old_status_and_disk_usage_handles_valid_path() {
     let temp = TempDir::new("app-status");
@@ -270,6 +512,7 @@ fn tick_expires_old_status_and_disk_usage_handles_valid_path() {
     app.update_disk_usage();
     #[cfg(any(unix, windows))]
     assert!(app.disk_total > 0);
+    assert!(app.inode_free <= app.inode_total);
     app.set_status("test");
     app.clear_status();
     assert!(app.status_message.is_none());
@@ -545,3 +788,149 @@ fn deep_collapsing_a_section_hides_its_rows_but_keeps_marks() {
         2
     );
 }
+
+#[test]
+fn owner_filter_lists_only_one_users_files_and_blocks_deletion() {
+    let owned = |name: &str, size: u64, is_dir: bool, uid: u32| DirEntry {
+        uid: Some(uid),
+        ..DirEntry::new(name, size, is_dir, false)
+    };
+    let root = PathBuf::from("/virtual-root");
+    let mut children = HashMap::new();
+    children.insert(
+        root.clone(),
+        vec![
+            owned("shared", 900, true, 0),
+            owned("bob.iso", 600, false, 1001),
+            owned("alice.iso", 200, false, 1000),
+        ],
+    );
+    children.insert(
+        root.join("shared"),
+        vec![
+            owned("alice.tar", 100, false, 1000),
+            owned("bob.tar", 800, false, 1001),
+        ],
+    );
+    let mut app = App::new_with_tree(
+        root.clone(),
+        matcher(),
+        DirTree::from_children(children),
+        false,
+    );
+    app.selected = app
+        .entries
+        .iter()
+        .position(|entry| entry.name == "alice.iso")
+        .unwrap();
+
+    app.toggle_owner();
+    assert_eq!(app.owner, Some(1000));
+    let listed: Vec<_> = app
+        .entries
+        .iter()
+        .map(|entry| (entry.name.to_str().unwrap(), entry.size))
+        .collect();
+    assert_eq!(listed, [("shared", 100), ("alice.iso", 200)]);
+    assert_eq!(app.total_size, 300);
+    assert_eq!(app.selected_entry().unwrap().name, "alice.iso");
+
+    app.toggle_delete_confirm();
+    assert!(!app.confirm_delete);
+    app.toggle_clean_confirm();
+    assert!(!app.confirm_clean);
+
+    app.toggle_owner();
+    assert_eq!(app.owner, None);
+    assert_eq!(app.entries.len(), 3);
+    assert_eq!(app.selected_entry().unwrap().name, "alice.iso");
+
+    app.open_owners();
+    let owners: Vec<_> = app
+        .owners
+        .as_ref()
+        .unwrap()
+        .owners
+        .iter()
+        .map(|(uid, _)| *uid)
+        .collect();
+    assert_eq!(owners, [Some(1001), Some(1000)]);
+    assert_eq!(app.owners.as_ref().unwrap().cursor, 1);
+    app.owners_move(-1);
+    app.owners_pick();
+    assert!(!app.in_owners());
+    assert_eq!(app.owner, Some(1001));
+    let listed: Vec<_> = app
+        .entries
+        .iter()
+        .map(|entry| (entry.name.to_str().unwrap(), entry.size))
+        .collect();
+    assert_eq!(listed, [("shared", 800), ("bob.iso", 600)]);
+}
+
+#[test]
+fn top_panel_ranks_the_largest_files_and_jumps_to_them() {
+    let root = PathBuf::from("/virtual-root");
+    let mut children = HashMap::new();
+    children.insert(
+        root.clone(),
+        vec![
+            entry(root.join("a"), "a", 900, true, false),
+            entry(root.join("top.iso"), "top.iso", 500, false, false),
+        ],
+    );
+    children.insert(
+        root.join("a"),
+        vec![
+            entry(root.clone(), "..", 0, true, false),
+            entry(root.join("a/b"), "b", 800, true, false),
+            entry(root.join("a/small"), "small", 100, false, false),
+        ],
+    );
+    children.insert(
+        root.join("a/b"),
+        vec![
+            entry(root.join("a"), "..", 0, true, false),
+            entry(root.join("a/b/deep.bin"), "deep.bin", 700, false, false),
+            entry(root.join("a/b/tiny"), "tiny", 100, false, false),
+        ],
+    );
+    let mut app = App::new_with_tree(
+        root.clone(),
+        matcher(),
+        DirTree::from_children(children),
+        false,
+    );
+
+    app.open_top();
+    let ranked = |app: &App| -> Vec<PathBuf> {
+        let state = app.top.as_ref().unwrap();
+        state.items.iter().map(|(path, _)| path.clone()).collect()
+    };
+    assert_eq!(
+        ranked(&app),
+        [
+            root.join("a/b/deep.bin"),
+            root.join("top.iso"),
+            root.join("a/b/tiny"),
+            root.join("a/small"),
+        ]
+    );
+    app.top_toggle_leaf_dirs();
+    assert_eq!(ranked(&app)[0], root.join("a/b"));
+    app.top_toggle_leaf_dirs();
+
+    app.top_move(-1);
+    app.top_go_bottom();
+    app.top_move(-3);
+    assert_eq!(app.top.as_ref().unwrap().cursor, 0);
+    app.top_jump();
+    assert!(!app.in_top());
+    assert_eq!(app.current_path, root.join("a/b"));
+    assert_eq!(app.selected_entry().unwrap().name, "deep.bin");
+    app.go_back();
+    assert_eq!(app.current_path, root.join("a"));
+    assert_eq!(app.selected_entry().unwrap().name, "b");
+    app.go_back();
+    assert_eq!(app.current_path, root);
+}
@@ -0,0 +1,80 @@
+//! The Top panel: the largest files, and optionally leaf folders, anywhere
+//! below the current folder, each a jump away.
+
+use super::state::TopState;
+use super::App;
+
+/// How many entries the Top panel ranks.
+pub const TOP_COUNT: usize = 100;
+
+impl App {
+    /// Open the Top panel on the current folder.
+    pub fn open_top(&mut self) {
+        if self.is_busy() || self.deep.is_some() {
+            return;
+        }
+        self.top = Some(TopState {
+            dir: self.current_path.clone(),
+            items: Vec::new(),
+            cursor: 0,
+            leaf_dirs: false,
+        });
+        self.rank_top();
+        self.clear_status();
+    }
+
+    /// True while the Top panel is open.
+    pub fn in_top(&self) -> bool {
+        self.top.is_some()
+    }
+
+    pub fn close_top(&mut self) {
+        self.top = None;
+    }
+
+    pub fn top_toggle_leaf_dirs(&mut self) {
+        if let Some(state) = self.top.as_mut() {
+            state.leaf_dirs = !state.leaf_dirs;
+        }
+        self.rank_top();
+    }
+
+    fn rank_top(&mut self) {
+        let (Some(state), Some(tree)) = (self.top.as_mut(), self.tree.as_ref()) else {
+            return;
+        };
+        state.items = tree.largest(&state.dir, TOP_COUNT, state.leaf_dirs, self.apparent_size);
+        state.cursor = 0;
+    }
+
+    pub fn top_move(&mut self, delta: isize) {
+        let Some(state) = self.top.as_mut() else {
+            return;
+        };
+        let last = state.items.len().saturating_sub(1);
+        state.cursor = state.cursor.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn top_go_top(&mut self) {
+        if let Some(state) = self.top.as_mut() {
+            state.cursor = 0;
+        }
+    }
+
+    pub fn top_go_bottom(&mut self) {
+        if let Some(state) = self.top.as_mut() {
+            state.cursor = state.items.len().saturating_sub(1);
+        }
+    }
+
+    /// Leave the panel for the folder holding the entry under the cursor,
+    /// with that entry selected.
+    pub fn top_jump(&mut self) {
+        let Some(state) = self.top.take() else {
+            return;
+        };
+        if let Some((path, _)) = state.items.get(state.cursor) {
+            self.reveal(path);
+        }
+    }
+}
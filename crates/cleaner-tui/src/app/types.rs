@@ -0,0 +1,43 @@
+//! The Types overlay: the current folder's files by category and by
+//! extension, over the browser.
+
+use super::state::TypesState;
+use super::App;
+
+impl App {
+    /// Open the Types overlay on the current folder.
+    pub fn open_types(&mut self) {
+        if self.is_busy() || self.deep.is_some() || self.top.is_some() {
+            return;
+        }
+        let Some(tree) = self.tree.as_ref() else {
+            return;
+        };
+        let mut breakdown = tree.breakdown(&self.current_path);
+        breakdown.sort(self.apparent_size);
+        self.types = Some(TypesState {
+            dir: self.current_path.clone(),
+            breakdown,
+            scroll: 0,
+        });
+        self.clear_status();
+    }
+
+    /// True while the Types overlay is open.
+    pub fn in_types(&self) -> bool {
+        self.types.is_some()
+    }
+
+    pub fn close_types(&mut self) {
+        self.types = None;
+    }
+
+    /// Scroll the overlay by `delta` lines; the renderer keeps the last
+    /// line in view.
+    pub fn types_scroll(&mut self, delta: isize) {
+        if let Some(state) = self.types.as_mut() {
+            let last = state.breakdown.extensions.len().saturating_sub(1);
+            state.scroll = state.scroll.saturating_add_signed(delta).min(last);
+        }
+    }
+}
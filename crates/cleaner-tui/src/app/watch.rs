@@ -0,0 +1,195 @@
+//! Keeping the tree current with `--watch`: directories that change on disk
+//! are re-read in place instead of rescanning everything. The reading, and
+//! the scan of any new subdirectories, happens on a worker thread; the UI
+//! thread only folds the results into the tree.
+
+use super::state::{Refreshing, WatchState};
+use super::App;
+use cleaner_core::tree::DirRefresh;
+use cleaner_core::watch::Watcher;
+use crossbeam_channel::{unbounded, TryRecvError};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often changed directories are re-read, so one that is written to
+/// all the time costs a listing per interval rather than per write.
+const APPLY_INTERVAL: Duration = Duration::from_secs(1);
+
+impl App {
+    /// Follow changes on disk from now on.
+    pub fn start_watching(&mut self) {
+        self.watch = Some(WatchState {
+            watcher: None,
+            pending: None,
+            changed: HashSet::new(),
+            refreshing: None,
+            overflowed: false,
+            applied: Instant::now(),
+        });
+        self.rewatch();
+    }
+
+    /// True while `--watch` is on and the tree is being watched.
+    pub fn is_watching(&self) -> bool {
+        self.watch
+            .as_ref()
+            .is_some_and(|state| state.watcher.is_some())
+    }
+
+    /// Watch every directory of the current tree, in the background since a
+    /// large tree takes a while. Called again whenever a rebuild replaces
+    /// the tree. The old watcher keeps collecting changes until the new one
+    /// is ready, and re-reads still in flight are queued again, since they
+    /// may predate the new tree.
+    pub(crate) fn rewatch(&mut self) {
+        let (Some(state), Some(tree)) = (self.watch.as_mut(), self.tree.as_ref()) else {
+            return;
+        };
+        if let Some(refreshing) = state.refreshing.take() {
+            state.changed.extend(refreshing.dirs);
+        }
+        match Watcher::new() {
+            Ok(mut watcher) => {
+                let dirs = tree.dirs();
+                state.pending = Some(thread::spawn(move || {
+                    let error = watcher.watch_all(&dirs).err();
+                    (watcher, error)
+                }));
+            }
+            Err(error) => {
+                self.watch = None;
+                self.set_status(error);
+            }
+        }
+    }
+
+    /// Collect what changed, re-read it in the background once per
+    /// [`APPLY_INTERVAL`], and fold in whatever has been read. Called from
+    /// [`App::tick`].
+    pub(crate) fn tick_watch(&mut self) {
+        let (busy, rebuilding) = (self.is_busy(), self.is_rebuilding());
+        let Some(state) = self.watch.as_mut() else {
+            return;
+        };
+        let mut message = None;
+        if let Some(watcher) = state.watcher.as_mut() {
+            match watcher.changes() {
+                Ok(changes) => {
+                    state.overflowed |= changes.overflowed;
+                    state.changed.extend(changes.dirs);
+                }
+                Err(error) => {
+                    state.watcher = None;
+                    message = Some(error);
+                }
+            }
+        }
+        if state.pending.as_ref().is_some_and(JoinHandle::is_finished) {
+            if let Some(Ok((watcher, error))) = state.pending.take().map(JoinHandle::join) {
+                state.watcher = Some(watcher);
+                message = message.or(error);
+            }
+        }
+
+        let mut refreshed = false;
+        let mut finished = false;
+        if let (Some(refreshing), Some(tree)) = (state.refreshing.as_mut(), self.tree.as_mut()) {
+            loop {
+                match refreshing.results.try_recv() {
+                    Ok(refresh) => {
+                        refreshing.dirs.retain(|dir| dir != refresh.dir());
+                        let added = tree.apply_refresh(refresh);
+                        if let Some(watcher) = state.watcher.as_mut() {
+                            if let Err(error) = watcher.watch_all(&added) {
+                                message = Some(error);
+                            }
+                        }
+                        // Read new directories again now they are watched,
+                        // in case they changed after they were scanned.
+                        state.changed.extend(added);
+                        refreshed = true;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if finished {
+            state.refreshing = None;
+        }
+
+        if state.overflowed {
+            if !busy {
+                state.overflowed = false;
+                state.changed.clear();
+                state.refreshing = None;
+                self.start_rebuild("Refreshed: too many changes to follow".to_string());
+            }
+        } else if !rebuilding
+            && state.refreshing.is_none()
+            && !state.changed.is_empty()
+            && state.applied.elapsed() >= APPLY_INTERVAL
+        {
+            state.applied = Instant::now();
+            if let Some(tree) = self.tree.as_ref() {
+                let mut dirs: Vec<PathBuf> = state.changed.drain().collect();
+                // Parents first: a parent that lost a subdirectory drops it, and
+                // the subdirectory's own refresh then finds nothing to do.
+                dirs.sort_by_key(|dir| dir.components().count());
+                let jobs: Vec<_> = dirs
+                    .iter()
+                    .filter_map(|dir| Some((dir.clone(), tree.listed_subdirs(dir)?)))
+                    .collect();
+                let (tx, results) = unbounded();
+                let (root, matcher, force) =
+                    (self.root.clone(), Arc::clone(&self.matcher), self.force);
+                thread::spawn(move || {
+                    for (dir, known) in jobs {
+                        let Some(refresh) = DirRefresh::read(&root, &dir, &known, &matcher, force)
+                        else {
+                            continue;
+                        };
+                        if tx.send(refresh).is_err() {
+                            return;
+                        }
+                    }
+                });
+                state.refreshing = Some(Refreshing { dirs, results });
+            }
+        }
+        if refreshed {
+            self.reload_after_refresh();
+        }
+        if let Some(message) = message {
+            self.set_status(message);
+        }
+    }
+
+    /// Show the refreshed tree, climbing out of the current folder if it is
+    /// gone.
+    fn reload_after_refresh(&mut self) {
+        let mut selected = self.selected_entry().map(|entry| entry.name.clone());
+        while !self
+            .tree
+            .as_ref()
+            .is_some_and(|tree| tree.contains_dir(&self.current_path))
+        {
+            let Some(parent) = self.path_stack.pop() else {
+                break;
+            };
+            selected = self.current_path.file_name().map(OsStr::to_os_string);
+            self.current_path = parent;
+        }
+        self.load_current_dir_with_selection(selected.as_deref());
+        if self.confirm_clean {
+            self.clean_preview = Some(self.compute_current_temp_stats());
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use cleaner_core::age::AgePolicy;
+use cleaner_core::patterns::glob;
 use cleaner_core::pool;
 use std::path::PathBuf;
 
@@ -15,11 +17,149 @@ pub fn parse_thread_count(value: &str) -> Result<usize, String> {
     Ok(count)
 }
 
+pub fn parse_exclude(value: &str) -> Result<String, String> {
+    glob::to_subtree_regex(value)?;
+    Ok(value.to_string())
+}
+
+/// Parse an age such as `7d`, `12h`, `30m`, `90s` or `2w` into seconds. A
+/// bare number is days.
+pub fn parse_age(value: &str) -> Result<u64, String> {
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let scale = match unit {
+        "" | "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "h" => 60 * 60,
+        "m" => 60,
+        "s" => 1,
+        _ => {
+            return Err(format!(
+                "unknown unit in '{value}' (expected s, m, h, d or w)"
+            ))
+        }
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|count| count.checked_mul(scale))
+        .ok_or_else(|| format!("invalid age '{value}' (e.g. 7d, 12h)"))
+}
+
+/// Subcommands that do something other than scan.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Put quarantined items back where they came from; lists the quarantine without arguments
+    Restore {
+        /// Quarantine id, or a path: everything quarantined from it or below it
+        #[arg(value_name = "ID|PATH")]
+        targets: Vec<String>,
+    },
+    /// Permanently delete quarantined items to free their space
+    Purge {
+        /// Only purge items quarantined at least this long ago (e.g. 7d, 12h)
+        #[arg(long = "older-than", value_name = "AGE", value_parser = parse_age, required_unless_present = "all")]
+        older_than: Option<u64>,
+        /// Purge everything in the quarantine
+        #[arg(long = "all", conflicts_with = "older_than")]
+        all: bool,
+    },
+    /// Show which directories grew or shrank between two scan indexes (see --index)
+    Diff {
+        /// Older index file; or a directory scanned with --index, to compare its last two scans
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+        /// Newer index file
+        #[arg(value_name = "NEW")]
+        new: Option<PathBuf>,
+        /// How many directories to list
+        #[arg(short = 'n', long = "limit", value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+    /// List the largest files anywhere under a folder (default: home directory)
+    Top {
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+        /// How many entries to list
+        #[arg(short = 'n', long = "limit", value_name = "N", default_value_t = 20)]
+        limit: usize,
+        /// Rank folders that hold no other folder alongside the files
+        #[arg(long = "dirs")]
+        dirs: bool,
+    },
+    /// Show how much of a folder each kind of file takes up (default: home directory)
+    Stats {
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+        /// Also list the largest file extensions
+        #[arg(long = "by-ext")]
+        by_ext: bool,
+        /// How many extensions to list
+        #[arg(short = 'n', long = "limit", value_name = "N", default_value_t = 20)]
+        limit: usize,
+        /// Print the report as JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Show how much of a folder each user owns (default: home directory)
+    Owners {
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+        /// How many owners to list
+        #[arg(short = 'n', long = "limit", value_name = "N", default_value_t = 20)]
+        limit: usize,
+        /// Print the report as JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+}
+
+/// Parse a size such as `30G`, `512M`, `1.5T` or `1048576` into bytes.
+/// Units are binary: `G`, `GB` and `GiB` all mean 2^30 bytes.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let split = value
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = unit.to_ascii_uppercase();
+    let unit = unit
+        .strip_suffix("IB")
+        .or_else(|| unit.strip_suffix('B'))
+        .unwrap_or(&unit);
+    let shift = match unit {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        "P" => 50,
+        _ => {
+            return Err(format!(
+                "unknown unit in '{value}' (expected K, M, G, T or P)"
+            ))
+        }
+    };
+    let bytes = number
+        .parse::<f64>()
+        .ok()
+        .map(|count| count * (1u64 << shift) as f64);
+    match bytes {
+        Some(bytes) if bytes.is_finite() && bytes < u64::MAX as f64 => Ok(bytes.round() as u64),
+        _ => Err(format!("invalid size '{value}' (e.g. 30G, 512M)")),
+    }
+}
+
 /// High-performance folder cleaner for development temp files
 #[derive(Parser, Debug)]
 #[command(name = "cleaner")]
 #[command(author, version, about = "Fastest disk scanner and cleaner", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Target folder to scan (positional or use -f/--folder)
     #[arg(index = 1)]
     pub path: Option<PathBuf>,
@@ -36,6 +176,26 @@ pub struct Args {
     #[arg(short = 'y', long = "confirm", default_value = "false")]
     pub confirm: bool,
 
+    /// With --confirm, move matches to the trash instead of deleting them permanently
+    #[arg(long = "trash", default_value = "false")]
+    pub trash: bool,
+
+    /// With --confirm, move matches into cleaner's quarantine (see `cleaner restore` and `cleaner purge`)
+    #[arg(long = "quarantine", default_value = "false", conflicts_with = "trash")]
+    pub quarantine: bool,
+
+    /// Append a JSON Lines audit record of every matched item to FILE, dry runs included
+    #[arg(long = "journal", value_name = "FILE")]
+    pub journal: Option<PathBuf>,
+
+    /// Clean only until SIZE is free on the target's filesystem, oldest and largest matches first (e.g. 30G; checked once per -j items, so it may overshoot by that many)
+    #[arg(long = "target-free", value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["trash", "quarantine"])]
+    pub target_free: Option<u64>,
+
+    /// Clean only until SIZE more than now is free, oldest and largest matches first (e.g. 20G; checked once per -j items, so it may overshoot by that many)
+    #[arg(long = "free-bytes", value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["target_free", "trash", "quarantine"])]
+    pub free_bytes: Option<u64>,
+
     /// Verbose output - show all matched paths
     #[arg(short = 'v', long = "verbose", default_value = "false")]
     pub verbose: bool,
@@ -48,6 +208,10 @@ pub struct Args {
     #[arg(long = "days")]
     pub days: Option<u64>,
 
+    /// How the age of a match is measured for --days: dir-mtime, newest-descendant or atime
+    #[arg(long = "age-policy", value_name = "POLICY", value_parser = AgePolicy::parse)]
+    pub age_policy: Option<AgePolicy>,
+
     /// Output results in JSON format (scripting/devops mode)
     #[arg(long = "json", default_value = "false")]
     pub json: bool,
@@ -56,13 +220,37 @@ pub struct Args {
     #[arg(long = "force", default_value = "false")]
     pub force: bool,
 
-    /// Optional legacy index flag (ignored)
+    /// Never clean this path glob or anything below it (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB", value_parser = parse_exclude)]
+    pub exclude: Vec<String>,
+
+    /// Only clean matches that the enclosing git repository ignores (never tracked files)
+    #[arg(long = "gitignored-only", default_value = "false")]
+    pub gitignored_only: bool,
+
+    /// Stay on the filesystem of the scanned folder: other mounts are neither scanned nor cleaned
+    #[arg(short = 'x', long = "one-file-system", default_value = "false")]
+    pub one_file_system: bool,
+
+    /// Count a hard-linked file at every link instead of once (sizes and bytes freed)
+    #[arg(long = "count-links", default_value = "false")]
+    pub count_links: bool,
+
+    /// Report file lengths, like `du --apparent-size`, instead of disk usage (press `a` in the TUI)
+    #[arg(long = "apparent-size", default_value = "false")]
+    pub apparent_size: bool,
+
+    /// Save the TUI scan to an index and open from it next time, re-reading only changed directories
     #[arg(long = "index", default_value = "false")]
     pub index: bool,
 
-    /// Optional legacy rebuild-index flag (ignored)
+    /// Ignore the saved TUI index and scan from scratch, then save a new one
     #[arg(long = "rebuild-index", default_value = "false")]
     pub rebuild_index: bool,
+
+    /// Keep the TUI current as files change, re-reading only the directories that do (Linux)
+    #[arg(long = "watch", default_value = "false")]
+    pub watch: bool,
 }
 
 pub fn resolve_folder(args: &Args) -> PathBuf {
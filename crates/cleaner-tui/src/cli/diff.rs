@@ -0,0 +1,127 @@
+//! `cleaner diff`: which directories grew between two scan indexes.
+
+use crate::ui::{ago, signed_size, unix_now};
+use cleaner_core::config::Config;
+use cleaner_core::patterns::PatternMatcher;
+use cleaner_core::tree::{diff_dirs, DirTree, ScanIndex, ScanProgress};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+/// Compare two indexes and return the process exit code.
+pub fn run(old: &Path, new: Option<&Path>, limit: usize) -> i32 {
+    match compare(old, new, limit) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{} {}", "Error:".red().bold(), error);
+            1
+        }
+    }
+}
+
+/// The index files to compare: two given ones, or the previous and the
+/// latest index of a scanned directory.
+fn index_files(old: &Path, new: Option<&Path>) -> Result<(PathBuf, PathBuf), String> {
+    match new {
+        Some(new) => Ok((old.to_path_buf(), new.to_path_buf())),
+        None if old.is_dir() => {
+            let latest = ScanIndex::path_for(old)?;
+            Ok((ScanIndex::previous_path(&latest), latest))
+        }
+        None => Err(format!(
+            "{} is not a directory; give two index files to compare",
+            old.display()
+        )),
+    }
+}
+
+fn compare(old: &Path, new: Option<&Path>, limit: usize) -> Result<(), String> {
+    let (old_path, new_path) = index_files(old, new)?;
+    let missing = |path: &Path, error: String| {
+        if path.exists() || new.is_some() {
+            error
+        } else {
+            format!(
+                "No earlier scan of {} to compare with; run `cleaner --index {}` twice",
+                old.display(),
+                old.display()
+            )
+        }
+    };
+    let old_index = ScanIndex::read(&old_path).map_err(|e| missing(&old_path, e))?;
+    let new_index = ScanIndex::read(&new_path).map_err(|e| missing(&new_path, e))?;
+    if old_index.root() != new_index.root() {
+        return Err(format!(
+            "The indexes are for different folders: {} and {}",
+            old_index.root().display(),
+            new_index.root().display()
+        ));
+    }
+
+    let root = new_index.root().to_path_buf();
+    let changes = diff_dirs(&tree_of(&old_index), &tree_of(&new_index), &root);
+    println!(
+        "{} {} ({} → {})",
+        "Changes in".bright_white().bold(),
+        root.display(),
+        saved_ago(&old_index),
+        saved_ago(&new_index)
+    );
+    if changes.is_empty() {
+        println!("Nothing changed");
+        return Ok(());
+    }
+    for change in changes.iter().take(limit) {
+        let size = signed_size(change.size_delta());
+        let size = if change.size_delta() > 0 {
+            size.red()
+        } else {
+            size.green()
+        };
+        let state = match (change.old, change.new) {
+            (None, _) => " (new)",
+            (_, None) => " (removed)",
+            _ => "",
+        };
+        println!(
+            "{size:>12} {:>+12}  {}{state}",
+            change.items_delta(),
+            change.path.display()
+        );
+    }
+    println!(
+        "{} directories changed{}",
+        changes.len(),
+        if changes.len() > limit {
+            format!(", showing the {limit} that grew most (-n for more)")
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
+
+/// The tree an index records. Only sizes and counts are compared, so no
+/// cleaning patterns apply.
+fn tree_of(index: &ScanIndex) -> DirTree {
+    let matcher = PatternMatcher::new(Arc::new(Config::empty()));
+    DirTree::build_from_index(
+        index,
+        &matcher,
+        Arc::new(ScanProgress::new()),
+        Arc::new(AtomicBool::new(false)),
+        false,
+    )
+}
+
+fn saved_ago(index: &ScanIndex) -> String {
+    index
+        .saved()
+        .and_then(|saved| saved.duration_since(UNIX_EPOCH).ok())
+        .map_or_else(
+            || "unknown".to_string(),
+            |saved| ago(saved.as_secs() as i64, unix_now()),
+        )
+}
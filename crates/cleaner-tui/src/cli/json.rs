@@ -1,15 +1,24 @@
+use cleaner_core::age::AgePolicy;
+use cleaner_core::protected::ProtectedPaths;
 use std::path::Path;
 
+pub use cleaner_core::journal::json_escape;
+
 pub fn json_escape_path(path: &Path) -> String {
-    path.to_string_lossy()
-        .replace('\\', "\\\\")
-        .replace('"', "\\\"")
+    json_escape(&path.to_string_lossy())
+}
+
+fn json_path_array<'a>(paths: impl Iterator<Item = &'a Path>) -> String {
+    let items: Vec<String> = paths
+        .map(|path| format!("\"{}\"", json_escape_path(path)))
+        .collect();
+    format!("[{}]", items.join(","))
 }
 
 pub fn output_json_error(message: &str) {
     println!(
         "{{\"success\":false,\"error\":\"{}\"}}",
-        message.replace('\\', "\\\\").replace('"', "\\\"")
+        json_escape(message)
     );
 }
 
@@ -21,20 +30,55 @@ pub struct JsonResults<'a> {
     pub directories: usize,
     pub files: usize,
     pub bytes: u64,
+    /// Whether `bytes` are file lengths rather than disk usage.
+    pub apparent_size: bool,
     pub errors: usize,
+    /// What live runs do with matches (`delete`, `trash` or `quarantine`).
+    pub disposal: &'static str,
+    pub protected: &'a ProtectedPaths,
+    pub days: Option<u64>,
+    pub age_policy: AgePolicy,
+    pub free_goal: Option<FreeGoalReport>,
+}
+
+/// How a `--target-free`/`--free-bytes` run ended.
+pub struct FreeGoalReport {
+    pub target_free: u64,
+    /// Free bytes after the run; for a dry run, what would be free.
+    pub free: u64,
+    pub reached: bool,
 }
 
 pub fn output_json_results(results: &JsonResults) {
     let mode = if !results.confirm { "dry-run" } else { "live" };
     println!(
-        "{{\"success\":true,\"mode\":\"{}\",\"target\":\"{}\",\"scanned_entries\":{},\"time_ms\":{},\"deleted_directories\":{},\"deleted_files\":{},\"bytes_freed\":{},\"errors\":{}}}",
+        "{{\"success\":true,\"mode\":\"{}\",\"disposal\":\"{}\",\"target\":\"{}\",\"scanned_entries\":{},\"time_ms\":{},\"deleted_directories\":{},\"deleted_files\":{},\"bytes_freed\":{},\"sizes\":\"{}\",\"errors\":{},\"protected\":{},\"unprotected\":{},\"days\":{},\"age_policy\":\"{}\",\"free_goal\":{}}}",
         mode,
+        results.disposal,
         json_escape_path(results.folder),
         results.scanned_count,
         results.elapsed_ms,
         results.directories,
         results.files,
         results.bytes,
-        results.errors
+        if results.apparent_size {
+            "apparent"
+        } else {
+            "disk"
+        },
+        results.errors,
+        json_path_array(results.protected.protected()),
+        json_path_array(results.protected.unprotected()),
+        results
+            .days
+            .map_or_else(|| "null".to_string(), |days| days.to_string()),
+        results.age_policy.name(),
+        results.free_goal.as_ref().map_or_else(
+            || "null".to_string(),
+            |goal| format!(
+                "{{\"target_free\":{},\"free\":{},\"reached\":{}}}",
+                goal.target_free, goal.free, goal.reached
+            )
+        )
     );
 }
@@ -1,25 +1,51 @@
 //! Command-line interface orchestration for batch scanning, reporting, and TUI launch.
 
 mod args;
+mod diff;
 mod json;
+mod owners;
+mod quarantine;
 mod reporter;
+mod scan;
+mod stats;
+mod top;
 
 #[cfg(test)]
 mod tests;
 
-pub use args::{parse_thread_count, resolve_folder, Args};
+pub use args::{parse_age, parse_exclude, parse_thread_count, resolve_folder, Args, Command};
 pub use json::{json_escape_path, output_json_error, output_json_results, JsonResults};
 pub use reporter::run_cli_scan;
 
 use clap::Parser;
-use cleaner_core::config::Config;
+use cleaner_core::config::{self, Config};
+use cleaner_core::deleter::{Disposal, FreeGoal};
+use cleaner_core::journal::Journal;
 use cleaner_core::pool;
 use colored::Colorize;
 use std::sync::Arc;
 
 pub fn run() {
     let args = Args::parse();
-    let is_interactive = !args.json && !args.confirm;
+    if let Some(command) = &args.command {
+        std::process::exit(match command {
+            Command::Diff { old, new, limit } => diff::run(old, new.as_deref(), *limit),
+            Command::Top { path, limit, dirs } => top::run(&args, path.as_deref(), *limit, *dirs),
+            Command::Stats {
+                path,
+                by_ext,
+                limit,
+                json,
+            } => stats::run(&args, path.as_deref(), *by_ext, *limit, *json),
+            Command::Owners { path, limit, json } => {
+                owners::run(&args, path.as_deref(), *limit, *json)
+            }
+            Command::Restore { targets } => quarantine::restore(targets),
+            Command::Purge { older_than, .. } => quarantine::purge(older_than.unwrap_or(0)),
+        });
+    }
+    let is_interactive =
+        !args.json && !args.confirm && args.target_free.is_none() && args.free_bytes.is_none();
 
     // Resolve folder: positional > --folder > home directory
     let folder = resolve_folder(&args);
@@ -71,7 +97,66 @@ pub fn run() {
     if let Some(days) = args.days {
         config.days = Some(days);
     }
+    if let Some(policy) = args.age_policy {
+        config.age_policy = policy;
+    }
     config.force = args.force;
+    config.gitignored_only = args.gitignored_only;
+    config.one_file_system = args.one_file_system;
+    config.count_links = args.count_links;
+    config.apparent_size = args.apparent_size;
+    config.trash = args.trash;
+    config.quarantine = args.quarantine;
+    if let Some(journal) = &args.journal {
+        config.journal = Some(journal.clone());
+    }
+    // A relative `--exclude` names a path below the scanned folder.
+    config.exclude.extend(
+        args.exclude
+            .iter()
+            .map(|rule| config::anchor_exclude(rule, &folder)),
+    );
+
+    let one_file_system = if config.one_file_system && config.root_device(&folder).is_none() {
+        Err(format!(
+            "--one-file-system: cannot read the device of {}",
+            folder.display()
+        ))
+    } else {
+        Ok(())
+    };
+    if let Err(error) = one_file_system
+        .and_then(|_| Disposal::for_config(&config))
+        .and_then(|_| Journal::for_config(&config))
+    {
+        if args.json {
+            output_json_error(&error);
+        } else {
+            eprintln!("{} {}", "Error:".red().bold(), error);
+        }
+        std::process::exit(1);
+    }
+    // Dry runs delete nothing, not even from the quarantine.
+    if is_interactive || args.confirm {
+        quarantine::expire(&config, args.json);
+    }
+
+    let goal = match (args.target_free, args.free_bytes) {
+        (Some(bytes), _) => Some(FreeGoal::target_free(&folder, bytes)),
+        (_, Some(bytes)) => Some(FreeGoal::free_bytes(&folder, bytes)),
+        (None, None) => None,
+    };
+    let goal = match goal.transpose() {
+        Ok(goal) => goal,
+        Err(error) => {
+            if args.json {
+                output_json_error(&error);
+            } else {
+                eprintln!("{} {}", "Error:".red().bold(), error);
+            }
+            std::process::exit(1);
+        }
+    };
 
     let config = Arc::new(config);
 
@@ -81,12 +166,17 @@ pub fn run() {
 
     // Interactive TUI mode by default when run without folder/path arguments
     if is_interactive {
-        if let Err(e) = crate::session::run(folder, config, args.index, args.rebuild_index) {
+        let opts = crate::session::StartOpts {
+            index_enabled: args.index,
+            rebuild_index: args.rebuild_index,
+            watch: args.watch,
+        };
+        if let Err(e) = crate::session::run(folder, config, opts) {
             eprintln!("{} TUI error: {}", "Error:".red().bold(), e);
             std::process::exit(1);
         }
         return;
     }
 
-    run_cli_scan(&args, &folder, config, num_threads);
+    run_cli_scan(&args, &folder, config, num_threads, goal);
 }
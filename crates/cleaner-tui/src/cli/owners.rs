@@ -0,0 +1,117 @@
+//! `cleaner owners`: how much of a folder each user owns.
+
+use super::args::Args;
+use super::json::{json_escape, json_escape_path, output_json_error};
+use super::scan::{scan, Scanned};
+use cleaner_core::tree::Tally;
+use cleaner_core::UserNames;
+use colored::Colorize;
+use std::path::Path;
+
+/// Scan `folder`, report its files by owner and return the process exit
+/// code. Owners are named from `/etc/passwd`.
+pub fn run(args: &Args, folder: Option<&Path>, limit: usize, json: bool) -> i32 {
+    let scanned = match scan(args, folder) {
+        Ok(scanned) => scanned,
+        Err(error) => {
+            if json {
+                output_json_error(&error);
+            } else {
+                eprintln!("{} {}", "Error:".red().bold(), error);
+            }
+            return 1;
+        }
+    };
+    let Scanned {
+        folder,
+        tree,
+        apparent,
+        ..
+    } = scanned;
+    let mut owners = tree.owners(&folder);
+    if apparent {
+        owners.sort_by(|(left_uid, left), (right_uid, right)| {
+            (right.apparent_size, left_uid).cmp(&(left.apparent_size, right_uid))
+        });
+    }
+    let names = UserNames::load();
+    if json {
+        print_json(&folder, &owners, &names, apparent);
+    } else {
+        print_table(&folder, &owners, &names, limit, apparent);
+    }
+    0
+}
+
+fn owner_name(names: &UserNames, uid: Option<u32>) -> String {
+    uid.map_or_else(|| "(unknown)".to_string(), |uid| names.name_or_uid(uid))
+}
+
+fn print_table(
+    folder: &Path,
+    owners: &[(Option<u32>, Tally)],
+    names: &UserNames,
+    limit: usize,
+    apparent: bool,
+) {
+    let files: u64 = owners.iter().map(|(_, tally)| tally.files).sum();
+    let total: u64 = owners
+        .iter()
+        .map(|(_, tally)| tally.size_as(apparent))
+        .sum();
+    println!(
+        "{} {} ({files} files, {})",
+        "Owners in".bright_white().bold(),
+        folder.display(),
+        humansize::format_size(total, humansize::BINARY)
+    );
+    for (uid, tally) in owners.iter().take(limit) {
+        let size = tally.size_as(apparent);
+        let share = if total == 0 {
+            0.0
+        } else {
+            size as f64 * 100.0 / total as f64
+        };
+        println!(
+            "  {:<18} {:>10} {share:>6.1}% {:>10} files",
+            owner_name(names, *uid),
+            humansize::format_size(size, humansize::BINARY),
+            tally.files
+        );
+    }
+    if owners.len() > limit {
+        println!(
+            "showing the {limit} largest of {} owners (-n for more)",
+            owners.len()
+        );
+    }
+}
+
+fn print_json(folder: &Path, owners: &[(Option<u32>, Tally)], names: &UserNames, apparent: bool) {
+    let files: u64 = owners.iter().map(|(_, tally)| tally.files).sum();
+    let bytes: u64 = owners
+        .iter()
+        .map(|(_, tally)| tally.size_as(apparent))
+        .sum();
+    let owners: Vec<String> = owners
+        .iter()
+        .map(|(uid, tally)| {
+            let user = uid.and_then(|uid| names.get(uid)).map_or_else(
+                || "null".to_string(),
+                |name| format!("\"{}\"", json_escape(name)),
+            );
+            format!(
+                "{{\"uid\":{},\"user\":{user},\"files\":{},\"bytes\":{}}}",
+                uid.map_or_else(|| "null".to_string(), |uid| uid.to_string()),
+                tally.files,
+                tally.size_as(apparent)
+            )
+        })
+        .collect();
+    println!(
+        "{{\"success\":true,\"target\":\"{}\",\"sizes\":\"{}\",\"files\":{files},\"bytes\":{bytes},\"owners\":[{}]}}",
+        json_escape_path(folder),
+        if apparent { "apparent" } else { "disk" },
+        owners.join(",")
+    );
+}
@@ -0,0 +1,119 @@
+//! `cleaner restore` and `cleaner purge`: undoing and expiring quarantined cleans.
+
+use crate::ui::{ago, unix_now};
+use cleaner_core::config::Config;
+use cleaner_core::quarantine::Quarantine;
+use colored::Colorize;
+
+/// `cleaner restore`: list the quarantine without `targets`, otherwise put
+/// them back. Returns the process exit code.
+pub fn restore(targets: &[String]) -> i32 {
+    exit_code(Quarantine::from_env().and_then(|quarantine| {
+        if targets.is_empty() {
+            list(&quarantine)
+        } else {
+            restore_targets(&quarantine, targets)
+        }
+    }))
+}
+
+/// `cleaner purge`: delete for good what was quarantined at least
+/// `older_than` seconds ago. Returns the process exit code.
+pub fn purge(older_than: u64) -> i32 {
+    exit_code(Quarantine::from_env().and_then(|quarantine| purge_expired(&quarantine, older_than)))
+}
+
+/// Delete what outlived the configured retention, when a run that may
+/// quarantine starts. Failures are reported but do not stop the run.
+pub fn expire(config: &Config, json: bool) {
+    let Some(days) = config.quarantine_days.filter(|_| config.quarantine) else {
+        return;
+    };
+    let expired = Quarantine::from_env()
+        .and_then(|quarantine| quarantine.expire(days.saturating_mul(24 * 60 * 60)));
+    match expired {
+        Ok((0, _)) => {}
+        Ok(_) if json => {}
+        Ok((items, bytes)) => eprintln!(
+            "{} {items} quarantined items older than {days} days, {}",
+            "Purged".green(),
+            humansize::format_size(bytes, humansize::BINARY)
+        ),
+        Err(error) if !json => eprintln!("{} {error}", "Warning:".yellow().bold()),
+        Err(_) => {}
+    }
+}
+
+fn exit_code(result: Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{} {}", "Error:".red().bold(), error);
+            1
+        }
+    }
+}
+
+fn list(quarantine: &Quarantine) -> Result<(), String> {
+    let entries = quarantine.entries()?;
+    if entries.is_empty() {
+        println!("Quarantine is empty ({})", quarantine.root().display());
+        return Ok(());
+    }
+    let now = unix_now();
+    for entry in &entries {
+        println!(
+            "{}  {:>8}  {:>10}  {}",
+            entry.id.bright_white().bold(),
+            ago(entry.quarantined, now),
+            humansize::format_size(entry.size, humansize::BINARY),
+            entry.original.display()
+        );
+    }
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    println!(
+        "{} items, {} (restore with `cleaner restore <ID|PATH>`)",
+        entries.len(),
+        humansize::format_size(total, humansize::BINARY)
+    );
+    Ok(())
+}
+
+fn restore_targets(quarantine: &Quarantine, targets: &[String]) -> Result<(), String> {
+    let mut failures = 0usize;
+    for target in targets {
+        let entries = quarantine.find(target)?;
+        if entries.is_empty() {
+            eprintln!(
+                "{} nothing in quarantine matches '{target}'",
+                "Error:".red()
+            );
+            failures += 1;
+        }
+        for entry in entries {
+            match quarantine.restore(&entry) {
+                Ok(()) => println!("{} {}", "Restored".green(), entry.original.display()),
+                Err(error) => {
+                    eprintln!("{} {error}", "Error:".red());
+                    failures += 1;
+                }
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(format!("{failures} restores failed"));
+    }
+    Ok(())
+}
+
+fn purge_expired(quarantine: &Quarantine, older_than: u64) -> Result<(), String> {
+    match quarantine.expire(older_than)? {
+        (0, _) => println!("Nothing to purge"),
+        (items, bytes) => println!(
+            "{} {items} items, {}",
+            "Purged".green(),
+            humansize::format_size(bytes, humansize::BINARY)
+        ),
+    }
+    Ok(())
+}
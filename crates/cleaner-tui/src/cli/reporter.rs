@@ -1,19 +1,28 @@
 use super::args::Args;
-use super::json::output_json_results;
+use super::json::{output_json_error, output_json_results};
+use cleaner_core::age::AgePolicy;
 use cleaner_core::config::Config;
-use cleaner_core::deleter::Deleter;
+use cleaner_core::deleter::{Deleter, Disposal, FreeGoal};
+use cleaner_core::journal::Journal;
 use cleaner_core::pool;
+use cleaner_core::protected::ProtectedPaths;
 use cleaner_core::scanner::Scanner;
 use cleaner_core::stats::Stats;
 use colored::Colorize;
-use crossbeam_channel::bounded;
+use crossbeam_channel::{bounded, unbounded};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::Path;
 use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
 
-pub fn print_banner(args: &Args, folder: &Path, config: &Config, num_threads: usize) {
+pub fn print_banner(
+    args: &Args,
+    folder: &Path,
+    config: &Config,
+    num_threads: usize,
+    goal: Option<&FreeGoal>,
+) {
     println!();
     println!(
         "{}",
@@ -41,6 +50,17 @@ pub fn print_banner(args: &Args, folder: &Path, config: &Config, num_threads: us
             "Mode:".bright_yellow().bold(),
             "DRY RUN (no files will be deleted)".yellow()
         );
+    } else if config.quarantine || config.trash {
+        let destination = if config.quarantine {
+            "quarantine"
+        } else {
+            "the trash"
+        };
+        println!(
+            "  {} {}",
+            "Mode:".bright_red().bold(),
+            format!("LIVE (matches will be moved to {destination})").red()
+        );
     } else {
         println!(
             "  {} {}",
@@ -51,21 +71,92 @@ pub fn print_banner(args: &Args, folder: &Path, config: &Config, num_threads: us
 
     println!("  {} {}", "Target:".bright_white().bold(), folder.display());
 
-    if let Some(ref config_path) = args.config {
+    if !config.sources.is_empty() {
+        let sources: Vec<String> = config
+            .sources
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
         println!(
             "  {} {}",
             "Config:".bright_white().bold(),
-            config_path.display()
+            sources.join(" < ")
         );
     }
 
     println!("  {} {}", "Threads:".bright_white().bold(), num_threads);
 
     if let Some(days) = config.days {
+        let measure = match config.age_policy {
+            AgePolicy::DirMtime => "modified",
+            AgePolicy::NewestDescendant => "modified anywhere inside",
+            AgePolicy::Atime => "accessed",
+        };
         println!(
-            "  {} {} days (items modified within this time are safe)",
+            "  {} {} days (items {measure} within this time are safe; policy: {})",
             "Filter:".bright_white().bold(),
-            days
+            days,
+            config.age_policy.name()
+        );
+    }
+
+    if let Some(goal) = goal {
+        println!(
+            "  {} stop once {} is free ({} now; oldest and largest matches first)",
+            "Goal:".bright_white().bold(),
+            humansize::format_size(goal.target_free, humansize::BINARY),
+            humansize::format_size(goal.initial_free, humansize::BINARY)
+        );
+    }
+
+    if !config.exclude.is_empty() {
+        println!(
+            "  {} {}",
+            "Excluded:".bright_white().bold(),
+            config.exclude.join(", ")
+        );
+    }
+
+    if !config.protect.add.is_empty() || config.force {
+        let builtins = if config.force {
+            "built-in list disabled by --force"
+        } else {
+            "plus built-in list"
+        };
+        let added = if config.protect.add.is_empty() {
+            "none".to_string()
+        } else {
+            config.protect.add.join(", ")
+        };
+        println!(
+            "  {} {added} ({builtins})",
+            "Protected:".bright_white().bold()
+        );
+    }
+    if !config.protect.remove.is_empty() && !config.force {
+        println!(
+            "  {} {}",
+            "Unprotected:".bright_white().bold(),
+            config.protect.remove.join(", ")
+        );
+    }
+
+    if config.gitignored_only {
+        println!(
+            "  {} only paths ignored by git (tracked files are never touched)",
+            "Git:".bright_white().bold()
+        );
+    }
+    if config.apparent_size {
+        println!(
+            "  {} apparent (file lengths, not disk usage)",
+            "Sizes:".bright_white().bold()
+        );
+    }
+    if config.one_file_system {
+        println!(
+            "  {} other filesystems are neither scanned nor cleaned",
+            "Mounts:".bright_white().bold()
         );
     }
 
@@ -81,16 +172,33 @@ pub fn print_banner(args: &Args, folder: &Path, config: &Config, num_threads: us
         "Files:".dimmed(),
         config.files.join(", ").dimmed()
     );
+    if !config.markers.is_empty() {
+        let markers: Vec<String> = config
+            .markers
+            .iter()
+            .map(|rule| format!("{} [{}]", rule.directory, rule.markers.join("|")))
+            .collect();
+        println!(
+            "    {} {}",
+            "Markers:".dimmed(),
+            markers.join(", ").dimmed()
+        );
+    }
     println!();
 }
 
-pub fn run_cli_scan(args: &Args, folder: &Path, config: Arc<Config>, num_threads: usize) {
+pub fn run_cli_scan(
+    args: &Args,
+    folder: &Path,
+    config: Arc<Config>,
+    num_threads: usize,
+    goal: Option<FreeGoal>,
+) {
     if !args.json {
-        print_banner(args, folder, &config, num_threads);
+        print_banner(args, folder, &config, num_threads, goal.as_ref());
     }
 
     let stats = Arc::new(Stats::new());
-    let (tx, rx) = bounded(1024);
     let start = Instant::now();
 
     let pb = if !args.json {
@@ -113,18 +221,53 @@ pub fn run_cli_scan(args: &Args, folder: &Path, config: Arc<Config>, num_threads
         Arc::clone(&worker_pool),
         Arc::clone(&config),
     );
-    let scan_handle = thread::spawn(move || scanner.scan(tx));
 
-    let deleter = Deleter::with_pool(
+    // `cli::run` has already rejected a disposal that cannot be set up.
+    let disposal = Disposal::for_config(&config).unwrap_or_default();
+    let disposal_name = disposal.name();
+    let destination = disposal.destination();
+    let mut deleter = Deleter::with_pool(
         Arc::clone(&stats),
         !args.confirm,
         args.verbose && !args.json,
         worker_pool,
-    );
-
-    deleter.process(rx);
+    )
+    .disposal(disposal)
+    .count_links(config.count_links)
+    .apparent_size(config.apparent_size);
+    match Journal::for_config(&config) {
+        Ok(Some(journal)) => deleter = deleter.journal(journal, "scan"),
+        Ok(None) => {}
+        Err(error) => {
+            if args.json {
+                output_json_error(&error);
+            } else {
+                eprintln!("{} {}", "Error:".red().bold(), error);
+            }
+            std::process::exit(1);
+        }
+    }
+    if let Some(device) = config.root_device(folder) {
+        deleter = deleter.one_file_system(device);
+    }
 
-    let scan_summary = scan_handle.join().unwrap();
+    let scan_summary = if let Some(goal) = goal.clone() {
+        // Ranking needs every match, so the scan finishes before anything
+        // is deleted.
+        let (ranked, summary) = scanner.scan_ranked();
+        let (tx, rx) = unbounded();
+        for item in ranked {
+            let _ = tx.send(item);
+        }
+        drop(tx);
+        deleter.until_free(goal).process(rx);
+        summary
+    } else {
+        let (tx, rx) = bounded(1024);
+        let scan_handle = thread::spawn(move || scanner.scan(tx));
+        deleter.process(rx);
+        scan_handle.join().unwrap()
+    };
     stats.add_errors(scan_summary.errors);
     let scanned_count = scan_summary.entries;
 
@@ -133,6 +276,20 @@ pub fn run_cli_scan(args: &Args, folder: &Path, config: Arc<Config>, num_threads
     }
 
     let elapsed = start.elapsed();
+    let goal_report = goal.and_then(
+        |goal| match goal.current_free(!args.confirm, stats.bytes()) {
+            Ok(free) => Some(super::json::FreeGoalReport {
+                target_free: goal.target_free,
+                free,
+                reached: free >= goal.target_free,
+            }),
+            Err(error) => {
+                eprintln!("{} {}", "Error:".red().bold(), error);
+                stats.add_errors(1);
+                None
+            }
+        },
+    );
 
     if args.json {
         output_json_results(&super::json::JsonResults {
@@ -143,7 +300,13 @@ pub fn run_cli_scan(args: &Args, folder: &Path, config: Arc<Config>, num_threads
             directories: stats.directories(),
             files: stats.files(),
             bytes: stats.bytes(),
+            apparent_size: config.apparent_size,
             errors: stats.error_count(),
+            disposal: disposal_name,
+            protected: &ProtectedPaths::new(folder, config.force, &config.protect),
+            days: config.days,
+            age_policy: config.age_policy,
+            free_goal: goal_report,
         });
         return;
     }
@@ -168,6 +331,20 @@ pub fn run_cli_scan(args: &Args, folder: &Path, config: Arc<Config>, num_threads
             "Would free:".yellow(),
             humansize::format_size(stats.bytes(), humansize::BINARY)
         );
+    } else if let Some(destination) = destination {
+        let label = format!("Moved to {destination}:");
+        let freed_when = if config.quarantine {
+            "freed by `cleaner purge`"
+        } else {
+            "freed once the trash is emptied"
+        };
+        println!("    {} {} directories", label.green(), stats.directories());
+        println!("    {} {} files", label.green(), stats.files());
+        println!(
+            "    {} {} ({freed_when})",
+            "Moved:".green(),
+            humansize::format_size(stats.bytes(), humansize::BINARY)
+        );
     } else {
         println!(
             "    {} {} directories",
@@ -182,6 +359,24 @@ pub fn run_cli_scan(args: &Args, folder: &Path, config: Arc<Config>, num_threads
         );
     }
 
+    if let Some(report) = &goal_report {
+        let (label, outcome) = if report.reached {
+            ("Goal:".green(), "reached")
+        } else {
+            ("Goal:".red(), "not reached")
+        };
+        let free_when = if args.confirm {
+            "free"
+        } else {
+            "would be free"
+        };
+        println!(
+            "    {label} {outcome} ({} {free_when}, target {})",
+            humansize::format_size(report.free, humansize::BINARY),
+            humansize::format_size(report.target_free, humansize::BINARY)
+        );
+    }
+
     if stats.error_count() > 0 {
         println!(
             "    {} {} (permission denied or in use)",
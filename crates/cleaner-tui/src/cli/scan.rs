@@ -0,0 +1,51 @@
+//! Scanning a folder into a tree for the report subcommands, `cleaner top`
+//! and `cleaner stats`.
+
+use super::args::Args;
+use cleaner_core::config::Config;
+use cleaner_core::patterns::PatternMatcher;
+use cleaner_core::tree::{DirTree, ScanProgress};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// A scanned folder and how to size it.
+pub struct Scanned {
+    pub folder: PathBuf,
+    pub tree: DirTree,
+    pub progress: Arc<ScanProgress>,
+    /// Report lengths rather than disk usage.
+    pub apparent: bool,
+}
+
+/// Scan `folder`, or the home directory, with the config file and the
+/// sizing flags of `args`.
+pub fn scan(args: &Args, folder: Option<&Path>) -> Result<Scanned, String> {
+    let folder = folder
+        .map(Path::to_path_buf)
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    if !folder.is_dir() {
+        return Err(format!("Not a directory: {}", folder.display()));
+    }
+    let folder = folder.canonicalize().unwrap_or(folder);
+    let mut config = Config::try_load(args.config.as_deref())?;
+    config.count_links = args.count_links;
+    config.one_file_system = args.one_file_system;
+    let apparent = args.apparent_size || config.apparent_size;
+
+    let progress = Arc::new(ScanProgress::new());
+    let tree = DirTree::build_with_progress(
+        &folder,
+        &PatternMatcher::new(Arc::new(config)),
+        Arc::clone(&progress),
+        Arc::new(AtomicBool::new(false)),
+        args.force,
+    );
+    Ok(Scanned {
+        folder,
+        tree,
+        progress,
+        apparent,
+    })
+}
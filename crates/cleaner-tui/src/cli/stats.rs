@@ -0,0 +1,129 @@
+//! `cleaner stats`: how much of a folder each kind of file takes up.
+
+use super::args::Args;
+use super::json::{json_escape, json_escape_path, output_json_error};
+use super::scan::{scan, Scanned};
+use cleaner_core::tree::{Breakdown, Tally};
+use colored::Colorize;
+use std::path::Path;
+
+/// Scan `folder`, report its files by category, and by extension with
+/// `by_ext`, then return the process exit code.
+pub fn run(args: &Args, folder: Option<&Path>, by_ext: bool, limit: usize, json: bool) -> i32 {
+    let scanned = match scan(args, folder) {
+        Ok(scanned) => scanned,
+        Err(error) => {
+            if json {
+                output_json_error(&error);
+            } else {
+                eprintln!("{} {}", "Error:".red().bold(), error);
+            }
+            return 1;
+        }
+    };
+    let Scanned {
+        folder,
+        tree,
+        apparent,
+        ..
+    } = scanned;
+    let mut breakdown = tree.breakdown(&folder);
+    breakdown.sort(apparent);
+    if json {
+        print_json(&folder, &breakdown, by_ext, apparent);
+    } else {
+        print_table(&folder, &breakdown, by_ext, limit, apparent);
+    }
+    0
+}
+
+fn print_table(folder: &Path, breakdown: &Breakdown, by_ext: bool, limit: usize, apparent: bool) {
+    let total = breakdown.total.size_as(apparent);
+    println!(
+        "{} {} ({} files, {})",
+        "File types in".bright_white().bold(),
+        folder.display(),
+        breakdown.total.files,
+        humansize::format_size(total, humansize::BINARY)
+    );
+    for (category, tally) in &breakdown.categories {
+        println!("{}", row(category.label(), tally, total, apparent));
+    }
+    if !by_ext {
+        return;
+    }
+    println!("{}", "Extensions".bright_white().bold());
+    for (extension, tally) in breakdown.extensions.iter().take(limit) {
+        let name = if extension.is_empty() {
+            "(none)".to_string()
+        } else {
+            format!(".{extension}")
+        };
+        println!("{}", row(&name, tally, total, apparent));
+    }
+    if breakdown.extensions.len() > limit {
+        println!(
+            "showing the {limit} largest of {} extensions (-n for more)",
+            breakdown.extensions.len()
+        );
+    }
+}
+
+fn row(name: &str, tally: &Tally, total: u64, apparent: bool) -> String {
+    let size = tally.size_as(apparent);
+    let share = if total == 0 {
+        0.0
+    } else {
+        size as f64 * 100.0 / total as f64
+    };
+    format!(
+        "  {name:<18} {:>10} {share:>6.1}% {:>10} files",
+        humansize::format_size(size, humansize::BINARY),
+        tally.files
+    )
+}
+
+fn print_json(folder: &Path, breakdown: &Breakdown, by_ext: bool, apparent: bool) {
+    let tally = |tally: &Tally| {
+        format!(
+            "\"files\":{},\"bytes\":{}",
+            tally.files,
+            tally.size_as(apparent)
+        )
+    };
+    let categories: Vec<String> = breakdown
+        .categories
+        .iter()
+        .map(|(category, counted)| {
+            format!(
+                "{{\"category\":\"{}\",{}}}",
+                category.name(),
+                tally(counted)
+            )
+        })
+        .collect();
+    let extensions = if by_ext {
+        let extensions: Vec<String> = breakdown
+            .extensions
+            .iter()
+            .map(|(extension, counted)| {
+                format!(
+                    "{{\"extension\":\"{}\",\"category\":\"{}\",{}}}",
+                    json_escape(extension),
+                    cleaner_core::tree::Category::of(extension).name(),
+                    tally(counted)
+                )
+            })
+            .collect();
+        format!(",\"extensions\":[{}]", extensions.join(","))
+    } else {
+        String::new()
+    };
+    println!(
+        "{{\"success\":true,\"target\":\"{}\",\"sizes\":\"{}\",{},\"categories\":[{}]{extensions}}}",
+        json_escape_path(folder),
+        if apparent { "apparent" } else { "disk" },
+        tally(&breakdown.total),
+        categories.join(",")
+    );
+}
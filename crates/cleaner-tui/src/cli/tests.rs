@@ -1,6 +1,9 @@
-use super::args::{parse_thread_count, resolve_folder, Args};
-use super::json::json_escape_path;
+use super::args::{
+    parse_age, parse_exclude, parse_size, parse_thread_count, resolve_folder, Args, Command,
+};
+use super::json::{json_escape, json_escape_path};
 use clap::Parser;
+use cleaner_core::age::AgePolicy;
 use std::path::{Path, PathBuf};
 
 #[test]
@@ -39,8 +42,12 @@ fn positional_folder_takes_priority() {
 }
 
 #[test]
-fn escapes_paths_for_json_strings() {
+fn escapes_paths_and_names_for_json_strings() {
     assert_eq!(json_escape_path(Path::new("a\\b\"c")), "a\\\\b\\\"c");
+    assert_eq!(
+        json_escape("Ann \"Q\"\tSmith\n\u{1}"),
+        "Ann \\\"Q\\\"\\tSmith\\n\\u0001"
+    );
 }
 
 #[test]
@@ -50,3 +57,102 @@ fn rejects_zero_and_excessive_thread_counts() {
     assert_eq!(parse_thread_count("8").unwrap(), 8);
     assert!(parse_thread_count("invalid").is_err());
 }
+
+#[test]
+fn exclude_flag_repeats_and_rejects_malformed_globs() {
+    let args = Args::try_parse_from([
+        "cleaner",
+        "--exclude",
+        "~/src/legacy/build",
+        "--exclude",
+        "**/vendor",
+    ])
+    .unwrap();
+    assert_eq!(args.exclude, ["~/src/legacy/build", "**/vendor"]);
+    assert!(Args::try_parse_from(["cleaner", "--exclude", "/a/[b"]).is_err());
+    assert!(parse_exclude("{a,b").is_err());
+}
+
+#[test]
+fn age_policy_flag_accepts_known_names_only() {
+    let args = Args::try_parse_from(["cleaner", "--age-policy", "newest-descendant"]).unwrap();
+    assert_eq!(args.age_policy, Some(AgePolicy::NewestDescendant));
+    assert!(Args::try_parse_from(["cleaner", "--age-policy", "ctime"]).is_err());
+}
+
+#[test]
+fn quarantine_subcommands_and_ages_parse() {
+    assert_eq!(parse_age("7d").unwrap(), 7 * 86_400);
+    assert_eq!(parse_age("7").unwrap(), 7 * 86_400);
+    assert_eq!(parse_age("12h").unwrap(), 12 * 3600);
+    assert_eq!(parse_age("2w").unwrap(), 14 * 86_400);
+    assert!(parse_age("7y").is_err());
+    assert!(parse_age("d").is_err());
+
+    let args = Args::try_parse_from(["cleaner", "purge", "--older-than", "30m"]).unwrap();
+    assert!(matches!(
+        args.command,
+        Some(Command::Purge {
+            older_than: Some(1800),
+            all: false
+        })
+    ));
+    let args = Args::try_parse_from(["cleaner", "purge", "--all"]).unwrap();
+    assert!(matches!(
+        args.command,
+        Some(Command::Purge {
+            older_than: None,
+            all: true
+        })
+    ));
+    assert!(Args::try_parse_from(["cleaner", "purge"]).is_err());
+    assert!(Args::try_parse_from(["cleaner", "purge", "--all", "--older-than", "1d"]).is_err());
+    let args = Args::try_parse_from(["cleaner", "restore", "123-1", "/src/app"]).unwrap();
+    assert!(matches!(args.command, Some(Command::Restore { targets }) if targets.len() == 2));
+    assert!(Args::try_parse_from(["cleaner", "restore", "--confirm"]).is_err());
+    assert!(Args::try_parse_from(["cleaner", "--trash", "--quarantine"]).is_err());
+    let args = Args::try_parse_from(["cleaner", "--quarantine", "somewhere"]).unwrap();
+    assert!(args.quarantine && args.command.is_none());
+}
+
+#[test]
+fn free_space_sizes_parse_and_exclude_moving_disposals() {
+    assert_eq!(parse_size("30G").unwrap(), 30 << 30);
+    assert_eq!(parse_size("512MiB").unwrap(), 512 << 20);
+    assert_eq!(parse_size("1.5k").unwrap(), 1536);
+    assert_eq!(parse_size("100").unwrap(), 100);
+    assert_eq!(parse_size("2TB").unwrap(), 2 << 40);
+    assert!(parse_size("30X").is_err());
+    assert!(parse_size("G").is_err());
+
+    let args = Args::try_parse_from(["cleaner", "--target-free", "30G"]).unwrap();
+    assert_eq!(args.target_free, Some(30 << 30));
+    assert!(
+        Args::try_parse_from(["cleaner", "--target-free", "1G", "--free-bytes", "1G"]).is_err()
+    );
+    assert!(Args::try_parse_from(["cleaner", "--free-bytes", "1G", "--trash"]).is_err());
+    assert!(Args::try_parse_from(["cleaner", "--target-free", "1G", "--quarantine"]).is_err());
+}
+
+#[test]
+fn one_file_system_has_a_short_flag() {
+    assert!(
+        Args::try_parse_from(["cleaner", "-x"])
+            .unwrap()
+            .one_file_system
+    );
+    assert!(
+        Args::try_parse_from(["cleaner", "--one-file-system"])
+            .unwrap()
+            .one_file_system
+    );
+    assert!(!Args::try_parse_from(["cleaner"]).unwrap().one_file_system);
+}
+
+#[test]
+fn size_flags_default_to_unique_disk_usage() {
+    let args = Args::try_parse_from(["cleaner"]).unwrap();
+    assert!(!args.apparent_size && !args.count_links);
+    let args = Args::try_parse_from(["cleaner", "--apparent-size", "--count-links"]).unwrap();
+    assert!(args.apparent_size && args.count_links);
+}
@@ -0,0 +1,53 @@
+//! `cleaner top`: the largest files anywhere under a folder.
+
+use super::args::Args;
+use super::scan::{scan, Scanned};
+use colored::Colorize;
+use std::path::Path;
+
+/// Scan `folder`, list its largest entries and return the process exit
+/// code. The scan honors the config file and the sizing flags of `args`.
+pub fn run(args: &Args, folder: Option<&Path>, limit: usize, dirs: bool) -> i32 {
+    match list(args, folder, limit, dirs) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{} {}", "Error:".red().bold(), error);
+            1
+        }
+    }
+}
+
+fn list(args: &Args, folder: Option<&Path>, limit: usize, dirs: bool) -> Result<(), String> {
+    let Scanned {
+        folder,
+        tree,
+        progress,
+        apparent,
+    } = scan(args, folder)?;
+    let largest = tree.largest(&folder, limit, dirs, apparent);
+    println!(
+        "{} {}",
+        if dirs {
+            "Largest files and leaf folders in"
+        } else {
+            "Largest files in"
+        }
+        .bright_white()
+        .bold(),
+        folder.display()
+    );
+    for (path, entry) in &largest {
+        let size = humansize::format_size(entry.size_as(apparent), humansize::BINARY);
+        if entry.is_dir {
+            println!("{size:>10}  {}", format!("{}/", path.display()).blue());
+        } else {
+            println!("{size:>10}  {}", path.display());
+        }
+    }
+    println!(
+        "{} files in {} folders scanned",
+        progress.get_files(),
+        progress.get_dirs()
+    );
+    Ok(())
+}
@@ -29,6 +29,9 @@ impl Session {
                     self.phase = Phase::Exited;
                     return Outcome::Exit;
                 }
+                if let Phase::Scanning { browser, .. } = &mut self.phase {
+                    handle_scanning_key(browser, key.code);
+                }
                 Outcome::Continue
             }
             Phase::Ready(app) => {
@@ -45,27 +48,75 @@ impl Session {
     }
 }
 
+/// Browse the partial tree while the scan runs. Anything that changes disk
+/// waits for the real sizes.
+fn handle_scanning_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Enter
+        | KeyCode::Right
+        | KeyCode::Left
+        | KeyCode::Backspace
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::Char('k' | 'j' | 'l' | 'h' | 'g' | 'G' | '3' | 's' | 'a') => {
+            handle_ready_key(app, code);
+        }
+        _ => app.set_status("Still scanning: browse now, delete and clean once sizes are final"),
+    }
+}
+
 pub(crate) fn handle_ready_key(app: &mut App, code: KeyCode) -> Option<Outcome> {
     // Deep Clean owns the keyboard while it is open, so its checkbox keys never
     // fall through to the browser's delete/clean bindings.
     if app.in_deep() {
         return handle_deep_key(app, code);
     }
+    if app.in_top() {
+        return Some(handle_top_key(app, code));
+    }
+    if app.in_types() {
+        return Some(handle_types_key(app, code));
+    }
+    if app.in_owners() {
+        return Some(handle_owners_key(app, code));
+    }
 
     match code {
         KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('0') => Some(Outcome::Exit),
         KeyCode::Char('1') => {
             app.status_message = Some(
-                "Keys: ↑↓/jk nav  Enter/l open  ←/h back  3/s sort  4 deep clean  5/c clean  6/d delete  7/r refresh  0/q quit"
+                "Keys: ↑↓/jk nav  Enter/l open  ←/h back  2/t trash  3/s sort  a apparent size  o owner only  O owners  + growth  4 deep clean  5/c clean  6/d delete  7/r refresh  8 top  9 types  0/q quit"
                     .into(),
             );
             app.status_time = Some(Instant::now());
             Some(Outcome::Continue)
         }
+        KeyCode::Char('2') | KeyCode::Char('t') => {
+            app.toggle_trash();
+            Some(Outcome::Continue)
+        }
         KeyCode::Char('3') | KeyCode::Char('s') => {
             app.toggle_sort();
             Some(Outcome::Continue)
         }
+        KeyCode::Char('a') => {
+            app.toggle_apparent_size();
+            Some(Outcome::Continue)
+        }
+        KeyCode::Char('+') => {
+            app.toggle_growth();
+            Some(Outcome::Continue)
+        }
+        KeyCode::Char('o') => {
+            app.toggle_owner();
+            Some(Outcome::Continue)
+        }
+        KeyCode::Char('O') => {
+            app.open_owners();
+            Some(Outcome::Continue)
+        }
         KeyCode::Char('4') => {
             app.open_deep();
             Some(Outcome::Continue)
@@ -82,7 +133,14 @@ pub(crate) fn handle_ready_key(app: &mut App, code: KeyCode) -> Option<Outcome>
             app.refresh();
             Some(Outcome::Continue)
         }
-        KeyCode::Char('2') | KeyCode::Char('8') | KeyCode::Char('9') => Some(Outcome::Continue),
+        KeyCode::Char('8') => {
+            app.open_top();
+            Some(Outcome::Continue)
+        }
+        KeyCode::Char('9') => {
+            app.open_types();
+            Some(Outcome::Continue)
+        }
         KeyCode::Up | KeyCode::Char('k') => {
             app.move_up();
             Some(Outcome::Continue)
@@ -127,6 +185,55 @@ pub(crate) fn handle_ready_key(app: &mut App, code: KeyCode) -> Option<Outcome>
     }
 }
 
+/// Key handling inside the Top panel, which, like Deep Clean, closes
+/// rather than quits. Its keys are always on the status line.
+fn handle_top_key(app: &mut App, code: KeyCode) -> Outcome {
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('0') | KeyCode::Char('8') => {
+            app.close_top()
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.top_move(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.top_move(1),
+        KeyCode::PageUp => app.top_move(-10),
+        KeyCode::PageDown => app.top_move(10),
+        KeyCode::Home | KeyCode::Char('g') => app.top_go_top(),
+        KeyCode::End | KeyCode::Char('G') => app.top_go_bottom(),
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => app.top_jump(),
+        KeyCode::Char('d') => app.top_toggle_leaf_dirs(),
+        _ => {}
+    }
+    Outcome::Continue
+}
+
+/// Key handling inside the Types overlay, which closes rather than quits.
+fn handle_types_key(app: &mut App, code: KeyCode) -> Outcome {
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('0') | KeyCode::Char('9') => {
+            app.close_types()
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.types_scroll(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.types_scroll(1),
+        KeyCode::PageUp => app.types_scroll(-10),
+        KeyCode::PageDown => app.types_scroll(10),
+        _ => {}
+    }
+    Outcome::Continue
+}
+
+/// Key handling inside the Owners overlay, which closes rather than quits.
+fn handle_owners_key(app: &mut App, code: KeyCode) -> Outcome {
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('0') | KeyCode::Char('O') => {
+            app.close_owners()
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.owners_move(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.owners_move(1),
+        KeyCode::Enter => app.owners_pick(),
+        _ => {}
+    }
+    Outcome::Continue
+}
+
 /// Key handling inside the Deep Clean view.
 ///
 /// Returns `Some(Outcome::Exit)` for nothing: leaving Deep Clean returns to the
@@ -10,14 +10,16 @@ mod tests;
 pub use runner::run;
 pub use types::{CleanOffer, Outcome, StartOpts};
 
-use crate::app::App;
+use types::Scanned;
+
+use crate::app::{scan_tree, App};
 use crate::ui::{self, Chrome};
 use cleaner_core::config::Config;
 use cleaner_core::patterns::PatternMatcher;
 use cleaner_core::sysclean::Target;
-use cleaner_core::tree::{DirTree, ScanProgress};
+use cleaner_core::tree::{DirTree, ScanIndex, ScanProgress};
 use ratatui::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::thread;
@@ -28,7 +30,7 @@ pub struct Session {
 }
 
 impl Session {
-    pub fn start(root: PathBuf, config: Arc<Config>, _opts: StartOpts) -> Self {
+    pub fn start(root: PathBuf, config: Arc<Config>, opts: StartOpts) -> Self {
         let matcher = Arc::new(PatternMatcher::new(Arc::clone(&config)));
         let force = config.force;
         let progress = Arc::new(ScanProgress::new());
@@ -37,13 +39,15 @@ impl Session {
         let cancelled_clone = Arc::clone(&cancelled);
         let root_clone = root.clone();
         let matcher_clone = Arc::clone(&matcher);
+        let browser = App::scanning(root.clone(), matcher, Arc::clone(&progress), force);
         let scan_handle = Some(thread::spawn(move || {
-            DirTree::build_with_progress(
+            startup_scan(
                 &root_clone,
                 &matcher_clone,
                 progress_clone,
                 cancelled_clone,
                 force,
+                opts,
             )
         }));
 
@@ -53,8 +57,8 @@ impl Session {
                 progress,
                 cancelled,
                 scan_handle,
-                matcher,
-                force,
+                browser: Box::new(browser),
+                watch: opts.watch,
             },
         }
     }
@@ -64,26 +68,42 @@ impl Session {
             types::Phase::Scanning {
                 progress,
                 scan_handle,
-                root,
-                matcher,
-                force,
+                browser,
                 ..
             } => {
+                browser.tick();
                 if !progress.is_done() {
                     return;
                 }
                 let Some(handle) = scan_handle.take() else {
                     return;
                 };
-                let dir_tree = match handle.join() {
-                    Ok(tree) => tree,
+                let scanned = match handle.join() {
+                    Ok(scanned) => scanned,
                     Err(_) => {
                         self.phase = types::Phase::Exited;
                         return;
                     }
                 };
-                let app = App::new_with_tree(root.clone(), Arc::clone(matcher), dir_tree, *force);
-                self.phase = types::Phase::Ready(Box::new(app));
+                let types::Phase::Scanning {
+                    browser: mut app,
+                    watch,
+                    ..
+                } = std::mem::replace(&mut self.phase, types::Phase::Exited)
+                else {
+                    return;
+                };
+                app.finish_scan(scanned.tree);
+                if let Some(path) = scanned.index_path {
+                    app.use_index(path, scanned.unverified);
+                }
+                if let Some(error) = scanned.index_error {
+                    app.set_status(format!("Index not saved: {error}"));
+                }
+                if watch {
+                    app.start_watching();
+                }
+                self.phase = types::Phase::Ready(app);
             }
             types::Phase::Ready(app) => app.tick(),
             types::Phase::Exited => {}
@@ -93,8 +113,18 @@ impl Session {
     /// Draw with full standalone chrome (digit bar) or content-only for Abyss embed.
     pub fn draw(&self, frame: &mut Frame, area: Rect, chrome: Chrome) {
         match &self.phase {
-            types::Phase::Scanning { root, progress, .. } => {
-                ui::draw_scan_progress(frame, area, root, progress)
+            // Until the root is listed there is nothing to browse.
+            types::Phase::Scanning {
+                root,
+                progress,
+                browser,
+                ..
+            } => {
+                if browser.entries.is_empty() {
+                    ui::draw_scan_progress(frame, area, root, progress)
+                } else {
+                    ui::render_in(frame, browser, area, chrome)
+                }
             }
             types::Phase::Ready(app) => ui::render_in(frame, app, area, chrome),
             types::Phase::Exited => {}
@@ -216,3 +246,60 @@ impl Session {
         }
     }
 }
+
+/// The first tree of a session. With the index on, it comes straight from
+/// the saved index when that loads, and is checked against disk once the
+/// browser is up.
+fn startup_scan(
+    root: &Path,
+    matcher: &PatternMatcher,
+    progress: Arc<ScanProgress>,
+    cancelled: Arc<AtomicBool>,
+    force: bool,
+    opts: StartOpts,
+) -> Scanned {
+    let index_path = if opts.index_enabled || opts.rebuild_index {
+        Some(ScanIndex::path_for(root))
+    } else {
+        None
+    };
+    let index_path = match index_path.transpose() {
+        Ok(path) => path,
+        Err(error) => {
+            let tree = DirTree::build_with_progress(root, matcher, progress, cancelled, force);
+            return Scanned {
+                tree,
+                index_path: None,
+                unverified: None,
+                index_error: Some(error),
+            };
+        }
+    };
+    if let Some(path) = index_path.as_deref().filter(|_| !opts.rebuild_index) {
+        if let Ok(index) = ScanIndex::load(path, root) {
+            let tree = DirTree::build_from_index(&index, matcher, progress, cancelled, force);
+            return Scanned {
+                tree,
+                index_path,
+                unverified: Some(index),
+                index_error: None,
+            };
+        }
+    }
+    let (tree, index_error) = scan_tree(
+        root,
+        matcher,
+        progress,
+        cancelled,
+        force,
+        index_path.as_deref(),
+        true,
+        None,
+    );
+    Scanned {
+        tree,
+        index_path,
+        unverified: None,
+        index_error,
+    }
+}
@@ -15,12 +15,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 /// Run the interactive TUI as a standalone app (owns the terminal).
-pub fn run(
-    root: PathBuf,
-    config: Arc<Config>,
-    index_enabled: bool,
-    rebuild_index: bool,
-) -> std::io::Result<()> {
+pub fn run(root: PathBuf, config: Arc<Config>, opts: StartOpts) -> std::io::Result<()> {
     fn cleanup_terminal() {
         let _ = disable_raw_mode();
         let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
@@ -39,14 +34,7 @@ pub fn run(
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut session = Session::start(
-        root,
-        config,
-        StartOpts {
-            index_enabled,
-            rebuild_index,
-        },
-    );
+    let mut session = Session::start(root, config, opts);
 
     let result = (|| -> io::Result<()> {
         loop {
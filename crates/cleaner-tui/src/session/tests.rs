@@ -47,8 +47,7 @@ fn session_lifecycle_scanning_to_ready_and_drawing() {
     let config = Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     });
 
     let mut session = Session::start(temp.path().to_path_buf(), config, StartOpts::default());
@@ -90,7 +89,7 @@ fn session_scan_cancellation() {
     let temp = TempDir::new("session-cancel");
     temp.write("file.txt", b"hello");
 
-    let config = Arc::new(Config::default());
+    let config = Arc::new(Config::empty());
     let mut session = Session::start(temp.path().to_path_buf(), config, StartOpts::default());
 
     let outcome = session.handle_event(key(KeyCode::Esc));
@@ -107,8 +106,7 @@ fn session_key_events_in_ready_state() {
     let config = Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     });
 
     let mut session = Session::start(temp.path().to_path_buf(), config, StartOpts::default());
@@ -158,9 +156,10 @@ fn session_key_events_in_ready_state() {
         Outcome::Continue
     );
 
-    // Number keys and vim keys. '4' is excluded: it opens Deep Clean, which
-    // takes over the keyboard, and is covered by its own test below.
-    for k in ['2', '8', '9', 'j', 'k', 'g', 'G', '3', '5', '6', '7'] {
+    // Number keys and vim keys. '4', '8' and '9' are excluded: they open
+    // Deep Clean, the Top panel and the Types overlay, which take over the
+    // keyboard and are covered by their own tests.
+    for k in ['2', 'j', 'k', 'g', 'G', '3', '5', '6', '7'] {
         assert_eq!(
             session.handle_event(key(KeyCode::Char(k))),
             Outcome::Continue
@@ -194,8 +193,7 @@ fn session_clean_offer_empty_and_run_clean() {
     let config = Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     });
 
     let mut session = Session::start(temp.path().to_path_buf(), config, StartOpts::default());
@@ -218,8 +216,7 @@ fn deep_clean_opens_on_four_and_returns_on_escape() {
     let config = Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     });
 
     let mut session = Session::start(temp.path().to_path_buf(), config, StartOpts::default());
@@ -256,8 +253,7 @@ fn no_elevated_work_is_offered_by_default() {
     let config = Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     });
 
     let mut session = Session::start(temp.path().to_path_buf(), config, StartOpts::default());
@@ -1,6 +1,5 @@
 use crate::app::App;
-use cleaner_core::patterns::PatternMatcher;
-use cleaner_core::tree::{DirTree, ScanProgress};
+use cleaner_core::tree::{DirTree, ScanIndex, ScanProgress};
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -9,8 +8,23 @@ use std::thread::JoinHandle;
 /// Options for starting a cleaner session.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct StartOpts {
+    /// Open from the saved scan index when there is one, and keep it saved.
     pub index_enabled: bool,
+    /// Ignore the saved index and scan from scratch; implies `index_enabled`.
     pub rebuild_index: bool,
+    /// Keep the tree current as directories change on disk.
+    pub watch: bool,
+}
+
+/// What the startup scan hands to the browser.
+pub(crate) struct Scanned {
+    pub tree: DirTree,
+    /// Where the index is saved, when it is on.
+    pub index_path: Option<PathBuf>,
+    /// The index `tree` was loaded from, not yet checked against disk.
+    pub unverified: Option<ScanIndex>,
+    /// Why the index could not be located or saved.
+    pub index_error: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -39,9 +53,10 @@ pub(crate) enum Phase {
         root: PathBuf,
         progress: Arc<ScanProgress>,
         cancelled: Arc<AtomicBool>,
-        scan_handle: Option<JoinHandle<DirTree>>,
-        matcher: Arc<PatternMatcher>,
-        force: bool,
+        scan_handle: Option<JoinHandle<Scanned>>,
+        /// Browses the directories listed so far.
+        browser: Box<App>,
+        watch: bool,
     },
     Ready(Box<App>),
     Exited,
@@ -8,14 +8,14 @@ use ratatui::{
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ActionButton {
     Help,
-    Empty2,
+    Trash,
     Sort,
     Deep,
     Clean,
     Delete,
     Refresh,
-    Empty8,
-    Empty9,
+    Top,
+    Types,
     Quit,
 }
 
@@ -23,14 +23,14 @@ impl ActionButton {
     pub fn key(self) -> char {
         match self {
             Self::Help => '1',
-            Self::Empty2 => '2',
+            Self::Trash => '2',
             Self::Sort => '3',
             Self::Deep => '4',
             Self::Clean => '5',
             Self::Delete => '6',
             Self::Refresh => '7',
-            Self::Empty8 => '8',
-            Self::Empty9 => '9',
+            Self::Top => '8',
+            Self::Types => '9',
             Self::Quit => '0',
         }
     }
@@ -38,31 +38,29 @@ impl ActionButton {
     pub fn label(self) -> &'static str {
         match self {
             Self::Help => "Help",
-            Self::Empty2 | Self::Empty8 | Self::Empty9 => "",
+            Self::Trash => "Trash",
             Self::Sort => "Sort",
             Self::Deep => "Deep",
             Self::Clean => "Clean",
             Self::Delete => "Delete",
             Self::Refresh => "Refresh",
+            Self::Top => "Top",
+            Self::Types => "Types",
             Self::Quit => "Quit",
         }
     }
-
-    pub fn disabled(self) -> bool {
-        matches!(self, Self::Empty2 | Self::Empty8 | Self::Empty9)
-    }
 }
 
 pub const BUTTONS: [ActionButton; 10] = [
     ActionButton::Help,
-    ActionButton::Empty2,
+    ActionButton::Trash,
     ActionButton::Sort,
     ActionButton::Deep,
     ActionButton::Clean,
     ActionButton::Delete,
     ActionButton::Refresh,
-    ActionButton::Empty8,
-    ActionButton::Empty9,
+    ActionButton::Top,
+    ActionButton::Types,
     ActionButton::Quit,
 ];
 
@@ -84,27 +82,18 @@ pub fn render_buttons(f: &mut Frame, area: Rect) {
             break;
         }
         let rect = Rect::new(x, area.y, width, 1);
-        let disabled = button.disabled();
         let label = button.label();
         let content = Line::from(vec![
             Span::styled(
                 button.key().to_string(),
-                if disabled {
-                    Style::new().fg(Color::DarkGray).bg(Color::Black)
-                } else {
-                    Style::new().fg(Color::White).bg(Color::Black)
-                },
+                Style::new().fg(Color::White).bg(Color::Black),
             ),
             Span::styled(
                 pad_right(
                     &fit(label, width.saturating_sub(1) as usize),
                     width.saturating_sub(1) as usize,
                 ),
-                if disabled {
-                    Style::new().fg(Color::DarkGray).bg(Color::Cyan)
-                } else {
-                    Style::new().fg(Color::Black).bg(Color::Cyan)
-                },
+                Style::new().fg(Color::Black).bg(Color::Cyan),
             ),
         ]);
         f.render_widget(Paragraph::new(content), rect);
@@ -10,6 +10,15 @@ pub fn status_line(app: &App) -> Option<String> {
     if let Some(state) = app.deep.as_ref() {
         return super::deep::status(state);
     }
+    if let Some(state) = app.top.as_ref() {
+        return Some(super::top::status(state));
+    }
+    if app.in_types() {
+        return Some(super::types::status());
+    }
+    if app.in_owners() {
+        return Some(super::owners::status());
+    }
     if let Some((phase, current, total)) = app.rebuild_progress() {
         let stage = match phase {
             0 => "scanning",
@@ -32,8 +41,12 @@ pub fn status_line(app: &App) -> Option<String> {
     if app.confirm_clean {
         let (dirs, files, bytes) = app.current_temp_stats();
         let size_str = humansize::format_size(bytes, humansize::BINARY);
+        let verb = match app.disposal.destination() {
+            Some(destination) => format!("Move all temp to {destination} from"),
+            None => "Clean all temp in".to_string(),
+        };
         return Some(format!(
-            "Clean all temp in '{}'? (y/n) — {} folders, {} files, {}",
+            "{verb} '{}'? (y/n) — {} folders, {} files, {}",
             app.current_path
                 .file_name()
                 .map(|n| n.to_string_lossy().to_string())
@@ -45,26 +58,55 @@ pub fn status_line(app: &App) -> Option<String> {
     }
     if app.confirm_delete {
         return Some(if let Some(entry) = app.selected_entry() {
-            format!(
-                "Delete '{}'? (y/n) — {} will be freed",
-                entry.name.to_string_lossy(),
-                humansize::format_size(entry.size, humansize::BINARY)
-            )
+            let size = humansize::format_size(entry.size, humansize::BINARY);
+            if let Some(destination) = app.disposal.destination() {
+                format!(
+                    "Move '{}' to {destination}? (y/n) — {size}",
+                    entry.name.to_string_lossy()
+                )
+            } else {
+                format!(
+                    "Delete '{}'? (y/n) — {size} will be freed",
+                    entry.name.to_string_lossy()
+                )
+            }
         } else {
             "Delete? (y/n)".into()
         });
     }
-    app.status_message.clone()
+    app.status_message.clone().or_else(|| {
+        let progress = app.scan_progress()?;
+        Some(if progress.get_phase() == 0 {
+            format!(
+                "Scanning: {} folders, {} files so far; folder sizes (~) are still growing",
+                progress.get_dirs(),
+                progress.get_files()
+            )
+        } else {
+            "Scanning: adding up folder sizes...".to_string()
+        })
+    })
 }
 
 pub fn render_header(f: &mut Frame, app: &App, area: Rect) {
     let title_width = area.width.saturating_sub(4) as usize;
     let path = truncate_middle(&app.current_path.to_string_lossy(), title_width);
     let total_size = humansize::format_size(app.total_size, humansize::BINARY);
-    let sort_str = match app.sort_mode {
-        SortMode::Size => "size",
-        SortMode::Name => "name",
+    let sort_str = match (app.growth_since(), app.sort_mode) {
+        (Some(saved), _) => saved
+            .and_then(|saved| saved.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or_else(
+                || "growth".to_string(),
+                |saved| format!("growth in {}", age(saved.as_secs() as i64, unix_now())),
+            ),
+        (None, SortMode::Size) if app.apparent_size => "apparent size".to_string(),
+        (None, SortMode::Size) => "size".to_string(),
+        (None, SortMode::Name) => "name".to_string(),
+        (None, SortMode::Age) => "age".to_string(),
+        (None, SortMode::Items) => "count".to_string(),
     };
+    let sizes = if app.apparent_size { " (apparent)" } else { "" };
+    let provisional = if app.is_provisional() { "~" } else { "" };
 
     let disk_info = if app.disk_total > 0 {
         let disk_used = app.disk_total.saturating_sub(app.disk_free);
@@ -76,10 +118,37 @@ pub fn render_header(f: &mut Frame, app: &App, area: Rect) {
         String::new()
     };
 
+    let inode_info = if app.inode_total > 0 {
+        let used = app.inode_total.saturating_sub(app.inode_free);
+        let used_pct = (used as f64 / app.inode_total as f64) * 100.0;
+        format!(
+            " │ Inodes: {}/{} ({used_pct:.1}%)",
+            count(used),
+            count(app.inode_total)
+        )
+    } else {
+        String::new()
+    };
+
+    let disposal = app
+        .disposal
+        .destination()
+        .map(|destination| format!(" │ Moves to {destination}"))
+        .unwrap_or_default();
+    let owner = app
+        .owner
+        .map(|uid| format!(" │ Owner: {}", app.user_name(uid)))
+        .unwrap_or_default();
+    let watching = if app.is_watching() {
+        " │ Watching"
+    } else {
+        ""
+    };
+
     let inner_width = area.width.saturating_sub(2) as usize;
     let stats = fit(
         &format!(
-            " Folder: {total_size} │ Sort: {sort_str}{disk_info} │ {} items",
+            " Folder: {provisional}{total_size}{sizes} │ Sort: {sort_str}{owner}{disposal}{watching}{disk_info}{inode_info} │ {} items",
             app.entries.len()
         ),
         inner_width,
@@ -115,15 +184,22 @@ pub fn render_list(f: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
+    let growth = app.growth_since().is_some();
+    let header_extra = if growth {
+        format!("{:>12} {:>10}", "Change", "Items")
+    } else {
+        format!("{:>6} {:>8} {:>8}", "Age", "Items", "Owner")
+    };
     let header_area = Rect::new(area.x + 1, area.y + 1, inner_width, 1);
     f.render_widget(
-        Paragraph::new(columns("Name", "Size", "", inner_width)).style(HEADER),
+        Paragraph::new(columns("Name", "Size", &header_extra, inner_width)).style(HEADER),
         header_area,
     );
 
     let visible_rows = usize::from(area.height.saturating_sub(3)).max(1);
     let start = app.selected.saturating_add(1).saturating_sub(visible_rows);
     let end = start.saturating_add(visible_rows).min(app.entries.len());
+    let now = unix_now();
 
     for (row, entry) in app
         .entries
@@ -133,11 +209,46 @@ pub fn render_list(f: &mut Frame, app: &App, area: Rect) {
         .enumerate()
     {
         let index = start + row;
-        let size_str = humansize::format_size(entry.size, humansize::BINARY);
+        let mut size_str =
+            humansize::format_size(entry.size_as(app.apparent_size), humansize::BINARY);
+        if app.is_provisional() && entry.is_dir && entry.name != ".." {
+            size_str.insert(0, '~');
+        }
         let prefix = if entry.is_dir { "▸ " } else { "  " };
         let temp_marker = if entry.is_temp { " [TEMP]" } else { "" };
         let name = format!("{prefix}{}{temp_marker}", entry.name.to_string_lossy());
-        let text = columns(&name, &size_str, "", inner_width);
+        let extra = if entry.name == ".." {
+            " ".repeat(display_width(&header_extra))
+        } else if let Some(change) = app.growth_of(entry) {
+            format!(
+                "{:>12} {:>10}",
+                if change.old.is_none() {
+                    "new".to_string()
+                } else {
+                    signed_size(change.size_delta())
+                },
+                if entry.is_dir {
+                    format!("{:+}", change.items_delta())
+                } else {
+                    String::new()
+                },
+            )
+        } else {
+            format!(
+                "{:>6} {:>8} {:>8}",
+                age(entry.modified, now),
+                if entry.is_dir {
+                    count(entry.items)
+                } else {
+                    String::new()
+                },
+                entry.uid.map_or_else(
+                    || "-".to_string(),
+                    |uid| fit(&app.user_name(uid), OWNER_WIDTH)
+                ),
+            )
+        };
+        let text = columns(&name, &size_str, &extra, inner_width);
         let style = if index == app.selected {
             SELECTED.add_modifier(Modifier::BOLD)
         } else if entry.is_temp {
@@ -165,12 +276,27 @@ pub fn render_status(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(Paragraph::new(format!(" {text}")).style(style), area);
 }
 
-fn columns(name: &str, size: &str, _extra: &str, width: u16) -> String {
+/// Owner names longer than this are cut short.
+const OWNER_WIDTH: usize = 8;
+
+/// Below this width the age, item count and owner columns are left out.
+const EXTRA_COLUMNS_MIN_WIDTH: usize = 64;
+
+fn columns(name: &str, size: &str, extra: &str, width: u16) -> String {
     let width = width as usize;
     if width < 14 {
         return fit(name, width);
     }
     let size_width = 10;
+    if width >= EXTRA_COLUMNS_MIN_WIDTH && !extra.is_empty() {
+        let extra_width = display_width(extra);
+        let name_width = width.saturating_sub(size_width + extra_width + 2);
+        return format!(
+            "{} {} {extra}",
+            pad_right(&fit_filename(name, name_width), name_width),
+            pad_left(size, size_width),
+        );
+    }
     let name_width = width.saturating_sub(size_width + 1);
     format!(
         "{} {}",
@@ -179,6 +305,60 @@ fn columns(name: &str, size: &str, _extra: &str, width: u16) -> String {
     )
 }
 
+/// How long before `now` the Unix time `modified` was, in its largest
+/// whole unit; blank when unknown.
+pub(super) fn age(modified: i64, now: i64) -> String {
+    if modified == 0 {
+        return String::new();
+    }
+    let seconds = now.saturating_sub(modified).max(0);
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 60 * 60, "y"),
+        (30 * 24 * 60 * 60, "mo"),
+        (24 * 60 * 60, "d"),
+        (60 * 60, "h"),
+        (60, "m"),
+        (1, "s"),
+    ];
+    UNITS.iter().find(|(unit, _)| seconds >= *unit).map_or_else(
+        || "now".to_string(),
+        |(unit, suffix)| format!("{}{suffix}", seconds / unit),
+    )
+}
+
+/// [`age`] as a phrase for messages, e.g. `3d ago` or `just now`.
+pub fn ago(then: i64, now: i64) -> String {
+    match age(then, now).as_str() {
+        "" => "unknown".to_string(),
+        "now" => "just now".to_string(),
+        age => format!("{age} ago"),
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// `bytes` with an explicit sign, e.g. `+1.5 GiB`.
+pub fn signed_size(bytes: i64) -> String {
+    let sign = if bytes < 0 { '-' } else { '+' };
+    format!(
+        "{sign}{}",
+        humansize::format_size(bytes.unsigned_abs(), humansize::BINARY)
+    )
+}
+
+/// An entry count that fits eight columns.
+fn count(items: u64) -> String {
+    match items {
+        0..=9_999_999 => items.to_string(),
+        _ => format!("{}M", items / 1_000_000),
+    }
+}
+
 pub fn fit(value: &str, width: usize) -> String {
     if width == 0 {
         return String::new();
@@ -3,14 +3,17 @@
 mod buttons;
 mod deep;
 mod layout;
+mod owners;
 mod progress;
 mod theme;
+mod top;
+mod types;
 
 #[cfg(test)]
 mod tests;
 
 pub use buttons::{ActionButton, BUTTONS};
-pub use layout::status_line;
+pub use layout::{ago, signed_size, status_line, unix_now};
 pub use progress::draw_scan_progress;
 pub use theme::{CONFIRM, CORE, DIR_STYLE, FILE_STYLE, HEADER, SELECTED, TEMP_STYLE};
 
@@ -52,10 +55,12 @@ pub fn render_in(f: &mut Frame, app: &App, area: Rect, chrome: Chrome) {
         Chrome::ContentOnly => (area, None, None),
     };
 
-    // Deep Clean takes over the content area entirely; the status line and
-    // digit bar below it keep rendering.
+    // Deep Clean and the Top panel take over the content area entirely; the
+    // status line and digit bar below them keep rendering.
     if app.in_deep() {
         deep::render(f, app, content);
+    } else if app.in_top() {
+        top::render(f, app, content);
     } else {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -64,6 +69,12 @@ pub fn render_in(f: &mut Frame, app: &App, area: Rect, chrome: Chrome) {
 
         layout::render_header(f, app, chunks[0]);
         layout::render_list(f, app, chunks[1]);
+        if app.in_types() {
+            types::render(f, app, content);
+        }
+        if app.in_owners() {
+            owners::render(f, app, content);
+        }
     }
 
     if let Some(status_area) = status_area {
@@ -0,0 +1,105 @@
+//! Owners overlay rendering.
+//!
+//! A box over the browser listing who owns the files below the current
+//! folder, with the cursor on the one Enter picks.
+
+use super::layout::{fit, pad_left, pad_right};
+use super::theme::{CORE, FILE_STYLE, HEADER, SELECTED};
+use crate::app::App;
+use ratatui::{
+    prelude::*,
+    text::Span,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// Widest the overlay gets.
+const MAX_WIDTH: u16 = 64;
+
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(state) = app.owners.as_ref() else {
+        return;
+    };
+    let apparent = app.apparent_size;
+    let total: u64 = state
+        .owners
+        .iter()
+        .map(|(_, tally)| tally.size_as(apparent))
+        .sum();
+
+    let wanted = state.owners.len().max(1) as u16 + 2;
+    let width = area.width.saturating_sub(4).min(MAX_WIDTH);
+    let height = area.height.saturating_sub(2).min(wanted);
+    if width < 20 || height < 3 {
+        return;
+    }
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(HEADER)
+        .style(CORE)
+        .title(Span::styled(
+            format!(" Owners in {} ", state.dir.display()),
+            HEADER,
+        ));
+    let inner = block.inner(popup);
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+
+    let width = inner.width as usize;
+    let room = inner.height as usize;
+    if state.owners.is_empty() {
+        let line = Line::from(Span::styled(" No files here.", FILE_STYLE));
+        f.render_widget(Paragraph::new(line), inner);
+        return;
+    }
+    let start = state.cursor.saturating_sub(room.saturating_sub(1));
+    let lines: Vec<Line> = state
+        .owners
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(room)
+        .map(|(index, (uid, tally))| {
+            let name = match uid {
+                Some(uid) => app.user_name(*uid),
+                None => "(unknown)".to_string(),
+            };
+            let size = tally.size_as(apparent);
+            let share = if total == 0 {
+                0.0
+            } else {
+                size as f64 * 100.0 / total as f64
+            };
+            let figures = format!(
+                "{} {share:>5.1}% {}",
+                pad_left(&humansize::format_size(size, humansize::BINARY), 10),
+                pad_left(&tally.files.to_string(), 8)
+            );
+            let name_width = width.saturating_sub(figures.len() + 2);
+            let text = format!(
+                " {} {figures}",
+                pad_right(&fit(&name, name_width), name_width)
+            );
+            let style = if index == state.cursor {
+                SELECTED.add_modifier(Modifier::BOLD)
+            } else {
+                FILE_STYLE
+            };
+            Line::from(Span::styled(fit(&text, width), style))
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Status line while the Owners overlay is open. Returned by
+/// [`super::status_line`].
+pub fn status() -> String {
+    "↑↓ select  enter only their files  esc close".to_string()
+}
@@ -1,11 +1,15 @@
 use super::*;
 use crate::app::App;
 use cleaner_core::config::Config;
+use cleaner_core::deleter::Disposal;
 use cleaner_core::patterns::PatternMatcher;
-use cleaner_core::tree::{DirEntry, DirTree};
+use cleaner_core::test_support::TempDir;
+use cleaner_core::trash::Trash;
+use cleaner_core::tree::{DirEntry, DirTree, ScanProgress};
 use foldhash::{HashMap, HashMapExt};
 use ratatui::{backend::TestBackend, Terminal};
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 fn app() -> App {
@@ -13,25 +17,14 @@ fn app() -> App {
     let matcher = Arc::new(PatternMatcher::new(Arc::new(Config {
         directories: vec!["target".into()],
         files: vec![".pyc".into()],
-        days: None,
-        force: false,
+        ..Config::empty()
     })));
     let mut children = HashMap::new();
     children.insert(
         root.clone(),
         vec![
-            DirEntry {
-                name: "target".into(),
-                size: 4096,
-                is_dir: true,
-                is_temp: true,
-            },
-            DirEntry {
-                name: "main.rs".into(),
-                size: 20,
-                is_dir: false,
-                is_temp: false,
-            },
+            DirEntry::new("target", 4096, true, true),
+            DirEntry::new("main.rs", 20, false, false),
         ],
     );
     App::new_with_tree(root, matcher, DirTree::from_children(children), false)
@@ -73,6 +66,11 @@ fn renders_delete_clean_and_status() {
     app.confirm_clean = true;
     assert!(screen(&app).contains("Clean all temp"));
     app.confirm_clean = false;
+    app.disposal = Disposal::Trash(Arc::new(Trash::new(PathBuf::from("/nonexistent/Trash"))));
+    assert!(screen(&app).contains("Moves to the trash"));
+    app.confirm_delete = true;
+    assert!(screen(&app).contains("Move 'target' to the trash?"));
+    app.confirm_delete = false;
     app.status_message = Some("Refreshed".into());
     assert!(screen(&app).contains("Refreshed"));
 }
@@ -109,17 +107,137 @@ fn renders_disk_information_and_name_sort() {
     assert!(output.contains("25.0%"));
 }
 
+#[test]
+fn renders_inode_usage_and_count_sort() {
+    let mut app = app();
+    app.sort_mode = crate::app::SortMode::Items;
+    app.inode_total = 1_000_000;
+    app.inode_free = 100_000;
+    let output = screen(&app);
+    assert!(output.contains("Sort: count"));
+    assert!(output.contains("Inodes: 900000/1000000 (90.0%)"));
+}
+
+#[test]
+fn wide_screens_show_age_file_count_and_owner_columns() {
+    let output = screen(&app());
+    assert!(output.contains("Age"));
+    assert!(output.contains("Items"));
+    assert!(output.contains("Owner"));
+}
+
+#[test]
+fn growth_view_shows_changes_since_the_baseline() {
+    let mut app = app();
+    let mut children = HashMap::new();
+    children.insert(
+        PathBuf::from("test-root"),
+        vec![DirEntry::new("target", 1024, true, true)],
+    );
+    app.compare_with(DirTree::from_children(children), None);
+    app.toggle_growth();
+    let output = screen(&app);
+    assert!(output.contains("Sort: growth"));
+    assert!(output.contains("Change"));
+    assert!(output.contains("+3 KiB"));
+    assert!(output.contains("new"));
+    assert_eq!(signed_size(-1536), "-1.50 KiB");
+}
+
+#[test]
+fn scanning_marks_folder_sizes_provisional() {
+    let temp = TempDir::new("ui-scanning");
+    temp.write("target/debug/app", &[0; 3000]);
+    let matcher = Arc::new(PatternMatcher::new(Arc::new(Config::empty())));
+    let progress = Arc::new(ScanProgress::new());
+    let mut app = App::scanning(
+        temp.path().to_path_buf(),
+        Arc::clone(&matcher),
+        Arc::clone(&progress),
+        false,
+    );
+    assert!(status_line(&app)
+        .unwrap()
+        .starts_with("Scanning: 0 folders"));
+    let tree = DirTree::build_with_progress(
+        temp.path(),
+        &matcher,
+        progress,
+        Arc::new(AtomicBool::new(false)),
+        false,
+    );
+    app.tick();
+    let output = screen(&app);
+    assert!(output.contains("Folder: ~"));
+    // The header total and the folder's own row.
+    assert_eq!(output.matches('~').count(), 2);
+
+    app.finish_scan(tree);
+    assert!(!screen(&app).contains('~'));
+    assert!(status_line(&app).is_none());
+}
+
+#[test]
+fn top_panel_replaces_the_browser_list() {
+    let mut app = app();
+    app.open_top();
+    let output = screen(&app);
+    assert!(output.contains(" Top "));
+    assert!(output.contains("1 largest files under test-root"));
+    assert!(output.contains("main.rs"));
+    assert!(output.contains("enter jump to it"));
+    assert!(!output.contains("Folder:"));
+}
+
+#[test]
+fn types_overlay_sits_over_the_browser() {
+    let mut app = app();
+    app.open_types();
+    let output = screen(&app);
+    assert!(output.contains(" Types in test-root "));
+    assert!(output.contains("1 files, 20 B"));
+    assert!(output.contains("Other"));
+    assert!(output.contains(".rs"));
+    assert!(output.contains("esc close"));
+    assert!(output.contains("Folder:"));
+
+    app.close_types();
+    assert!(!screen(&app).contains("Types in"));
+}
+
+#[test]
+fn owners_overlay_lists_who_owns_the_files() {
+    let mut app = app();
+    app.open_owners();
+    let output = screen(&app);
+    assert!(output.contains(" Owners in test-root "));
+    assert!(output.contains("(unknown)"));
+    assert!(output.contains("100.0%"));
+    assert!(output.contains("enter only their files"));
+
+    app.close_owners();
+    assert!(!screen(&app).contains("Owners in"));
+}
+
+#[test]
+fn ages_use_the_largest_whole_unit() {
+    let now = 1_000_000_000;
+    assert_eq!(layout::age(0, now).trim(), "");
+    assert_eq!(layout::age(now, now).trim(), "now");
+    assert_eq!(layout::age(now - 90, now).trim(), "1m");
+    assert_eq!(layout::age(now - 3 * 86_400, now).trim(), "3d");
+    assert_eq!(layout::age(now - 2 * 365 * 86_400, now).trim(), "2y");
+    assert_eq!(layout::ago(now, now), "just now");
+    assert_eq!(layout::ago(now - 45, now), "45s ago");
+    assert_eq!(layout::ago(now - 3 * 86_400, now), "3d ago");
+}
+
 #[test]
 fn renders_only_rows_visible_near_selection() {
     let mut app = app();
     app.entries = Arc::new(
         (0..50)
-            .map(|index| DirEntry {
-                name: format!("entry-{index:02}").into(),
-                size: index,
-                is_dir: false,
-                is_temp: false,
-            })
+            .map(|index| DirEntry::new(format!("entry-{index:02}"), index, false, false))
             .collect(),
     );
     app.selected = 49;
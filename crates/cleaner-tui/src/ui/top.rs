@@ -0,0 +1,108 @@
+//! Top panel rendering.
+//!
+//! Replaces the browser's header and list while the panel is open, like
+//! Deep Clean does.
+
+use super::layout::{fit, pad_left};
+use super::theme::{CORE, DIR_STYLE, FILE_STYLE, HEADER, SELECTED, TEMP_STYLE};
+use crate::app::{App, TopState};
+use ratatui::{
+    prelude::*,
+    text::Span,
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Width of the left-hand size column.
+const SIZE_WIDTH: usize = 10;
+
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(state) = app.top.as_ref() else {
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(3)])
+        .split(area);
+
+    render_header(f, state, chunks[0]);
+    render_list(f, app, state, chunks[1]);
+}
+
+fn render_header(f: &mut Frame, state: &TopState, area: Rect) {
+    let what = if state.leaf_dirs {
+        "files and leaf folders"
+    } else {
+        "files"
+    };
+    let summary = format!(
+        " {} largest {what} under {}",
+        state.items.len(),
+        state.dir.display()
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(CORE)
+        .title(Span::styled(" Top ", HEADER));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    f.render_widget(
+        Paragraph::new(fit(&summary, inner.width as usize)).style(HEADER),
+        inner,
+    );
+}
+
+fn render_list(f: &mut Frame, app: &App, state: &TopState, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).style(CORE);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    if state.items.is_empty() {
+        f.render_widget(Paragraph::new(" No files here.").style(FILE_STYLE), inner);
+        return;
+    }
+
+    // Keep the cursor in view, centred where the list allows.
+    let height = inner.height as usize;
+    let start = state.cursor.saturating_sub(height.saturating_sub(1) / 2);
+    let start = start.min(state.items.len().saturating_sub(height));
+    let width = inner.width as usize;
+
+    let lines: Vec<Line> = state
+        .items
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(index, (path, entry))| {
+            let size = humansize::format_size(entry.size_as(app.apparent_size), humansize::BINARY);
+            let below = path.strip_prefix(&state.dir).unwrap_or(path);
+            let slash = if entry.is_dir { "/" } else { "" };
+            let text = format!("{} {}{slash}", pad_left(&size, SIZE_WIDTH), below.display());
+            let style = if index == state.cursor {
+                SELECTED.add_modifier(Modifier::BOLD)
+            } else if entry.is_temp {
+                TEMP_STYLE
+            } else if entry.is_dir {
+                DIR_STYLE
+            } else {
+                FILE_STYLE
+            };
+            Line::from(vec![Span::styled(fit(&text, width), style)])
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Status line while the Top panel is open. Returned by
+/// [`super::status_line`].
+pub fn status(state: &TopState) -> String {
+    let what = if state.leaf_dirs {
+        "files only"
+    } else {
+        "leaf folders too"
+    };
+    format!("↑↓ move  enter jump to it  d {what}  esc back")
+}
@@ -0,0 +1,123 @@
+//! Types overlay rendering.
+//!
+//! A box over the browser: the categories first, then the extensions,
+//! which scroll.
+
+use super::layout::{fit, pad_left, pad_right};
+use super::theme::{CORE, FILE_STYLE, HEADER};
+use crate::app::App;
+use cleaner_core::tree::Tally;
+use ratatui::{
+    prelude::*,
+    text::Span,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// Widest the overlay gets.
+const MAX_WIDTH: u16 = 64;
+
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let Some(state) = app.types.as_ref() else {
+        return;
+    };
+    let breakdown = &state.breakdown;
+    let apparent = app.apparent_size;
+    let total = breakdown.total.size_as(apparent);
+
+    // Title and the categories stay put; the extensions below them scroll.
+    let fixed = breakdown.categories.len() + 2;
+    let wanted = (fixed + breakdown.extensions.len()) as u16 + 2;
+    let width = area.width.saturating_sub(4).min(MAX_WIDTH);
+    let height = area.height.saturating_sub(2).min(wanted);
+    if width < 20 || height < 3 {
+        return;
+    }
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(HEADER)
+        .style(CORE)
+        .title(Span::styled(
+            format!(" Types in {} ", state.dir.display()),
+            HEADER,
+        ));
+    let inner = block.inner(popup);
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+
+    let row_width = inner.width as usize;
+    let mut lines = vec![Line::from(Span::styled(
+        fit(
+            &format!(
+                " {} files, {}",
+                breakdown.total.files,
+                humansize::format_size(total, humansize::BINARY)
+            ),
+            row_width,
+        ),
+        HEADER,
+    ))];
+    if breakdown.total.files == 0 {
+        lines.push(Line::from(Span::styled(" No files here.", FILE_STYLE)));
+    }
+    for (category, tally) in &breakdown.categories {
+        lines.push(row(category.label(), tally, total, apparent, row_width));
+    }
+    if !breakdown.extensions.is_empty() {
+        lines.push(Line::from(Span::styled(
+            fit(" Extensions", row_width),
+            HEADER,
+        )));
+    }
+    let room = (inner.height as usize).saturating_sub(lines.len());
+    let start = state
+        .scroll
+        .min(breakdown.extensions.len().saturating_sub(room));
+    for (extension, tally) in breakdown.extensions.iter().skip(start).take(room) {
+        let name = if extension.is_empty() {
+            "(none)".to_string()
+        } else {
+            format!(".{extension}")
+        };
+        lines.push(row(&name, tally, total, apparent, row_width));
+    }
+
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+fn row(name: &str, tally: &Tally, total: u64, apparent: bool, width: usize) -> Line<'static> {
+    let size = tally.size_as(apparent);
+    let share = if total == 0 {
+        0.0
+    } else {
+        size as f64 * 100.0 / total as f64
+    };
+    let figures = format!(
+        "{} {share:>5.1}% {}",
+        pad_left(&humansize::format_size(size, humansize::BINARY), 10),
+        pad_left(&tally.files.to_string(), 8)
+    );
+    let name_width = width.saturating_sub(figures.len() + 2);
+    Line::from(Span::styled(
+        fit(
+            &format!(
+                " {} {figures}",
+                pad_right(&fit(name, name_width), name_width)
+            ),
+            width,
+        ),
+        FILE_STYLE,
+    ))
+}
+
+/// Status line while the Types overlay is open. Returned by
+/// [`super::status_line`].
+pub fn status() -> String {
+    "↑↓ scroll extensions  esc close".to_string()
+}
@@ -39,11 +39,19 @@ impl Drop for TempDir {
 }
 
 fn cleaner(args: &[&str]) -> Output {
+    cleaner_with_env(args, &[])
+}
+
+fn cleaner_with_env(args: &[&str], env: &[(&str, &Path)]) -> Output {
     Command::new(env!("CARGO_BIN_EXE_cleaner"))
         .args(args)
         .env_remove("CLEANER_DIRS")
         .env_remove("CLEANER_FILES")
         .env_remove("CLEANER_DAYS")
+        .env_remove("CLEANER_EXCLUDE")
+        // Keep the developer's own config out of the way.
+        .env("XDG_CONFIG_HOME", "/nonexistent/cleaner-test-config")
+        .envs(env.iter().copied())
         .output()
         .unwrap()
 }
@@ -75,6 +83,7 @@ fn json_errors_have_nonzero_exit_status() {
 #[test]
 fn json_dry_run_reports_matches_without_deleting() {
     let temp = TempDir::new("dry-run");
+    temp.write("Cargo.toml", b"[package]");
     temp.write("target/artifact", b"1234");
     let output = cleaner(&["--json", "--threads", "1", temp.path().to_str().unwrap()]);
     assert!(
@@ -87,12 +96,14 @@ fn json_dry_run_reports_matches_without_deleting() {
     assert!(stdout.contains("\"mode\":\"dry-run\""));
     assert!(stdout.contains("\"deleted_directories\":1"));
     assert!(stdout.contains("\"deleted_files\":1"));
+    assert!(stdout.contains("\"days\":null,\"age_policy\":\"dir-mtime\""));
     assert!(temp.join("target/artifact").exists());
 }
 
 #[test]
 fn confirmed_json_run_deletes_matches_and_preserves_other_files() {
     let temp = TempDir::new("live");
+    temp.write("Cargo.toml", b"[package]");
     temp.write("target/artifact", b"1234");
     temp.write("src/main.rs", b"keep");
     let output = cleaner(&["--json", "--confirm", temp.path().to_str().unwrap()]);
@@ -106,6 +117,278 @@ fn confirmed_json_run_deletes_matches_and_preserves_other_files() {
     assert!(temp.join("src/main.rs").exists());
 }
 
+#[test]
+fn free_space_goal_stops_early_and_is_reported() {
+    let temp = TempDir::new("free-goal");
+    temp.write("a/package.json", b"{}");
+    temp.write("a/node_modules/dep/index.js", &[0u8; 8192]);
+    temp.write("b/package.json", b"{}");
+    temp.write("b/node_modules/dep/index.js", &[0u8; 8192]);
+    let folder = temp.path().to_str().unwrap();
+
+    let output = cleaner(&["--json", "--confirm", "--free-bytes", "1", folder]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("\"deleted_directories\":1,"), "{stdout}");
+    assert!(stdout.contains("\"reached\":true}"), "{stdout}");
+    let remaining = ["a", "b"]
+        .iter()
+        .filter(|dir| temp.join(dir).join("node_modules").exists())
+        .count();
+    assert_eq!(remaining, 1);
+
+    let output = cleaner(&["--json", "--target-free", "1000P", folder]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"mode\":\"dry-run\""), "{stdout}");
+    assert!(stdout.contains("\"deleted_directories\":1,"), "{stdout}");
+    assert!(stdout.contains("\"reached\":false}"), "{stdout}");
+
+    let output = cleaner(&["--json", folder]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"free_goal\":null"));
+}
+
+#[test]
+fn apparent_size_reports_file_lengths() {
+    let temp = TempDir::new("apparent");
+    temp.write("Cargo.toml", b"[package]");
+    let image = temp.write("target/disk.img", b"");
+    std::fs::File::options()
+        .write(true)
+        .open(&image)
+        .unwrap()
+        .set_len(8 << 20)
+        .unwrap();
+    let folder = temp.path().to_str().unwrap();
+
+    let output = cleaner(&["--json", "--apparent-size", folder]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("\"bytes_freed\":8388608,\"sizes\":\"apparent\""),
+        "{stdout}"
+    );
+    let output = cleaner(&["--json", folder]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"sizes\":\"disk\""), "{stdout}");
+    assert!(!stdout.contains("\"bytes_freed\":8388608"), "{stdout}");
+}
+
+#[test]
+fn journal_records_dry_and_live_runs() {
+    let temp = TempDir::new("journal");
+    let logs = TempDir::new("journal-logs");
+    temp.write("Cargo.toml", b"[package]");
+    temp.write("target/artifact", b"1234");
+    let journal = logs.join("audit.jsonl");
+    let folder = temp.path().to_str().unwrap();
+    for args in [vec!["--json"], vec!["--json", "--confirm"]] {
+        let mut args = args;
+        args.extend(["--journal", journal.to_str().unwrap(), folder]);
+        let output = cleaner(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    assert!(!temp.join("target").exists());
+
+    let content = std::fs::read_to_string(&journal).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2, "{content}");
+    for (line, dry_run) in lines.iter().zip([true, false]) {
+        assert!(line.contains("\"type\":\"dir\",\"bytes\":4,\"rule\":\"target\""));
+        assert!(line.contains(&format!("\"dry_run\":{dry_run}")));
+        assert!(line.contains("\"source\":\"scan\""));
+    }
+
+    let output = cleaner(&["--json", "--journal", folder, folder]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Cannot open journal"));
+}
+
+#[cfg(unix)]
+#[test]
+fn trash_mode_moves_matches_into_the_xdg_trash() {
+    let temp = TempDir::new("trash");
+    let data = TempDir::new("trash-data");
+    temp.write("Cargo.toml", b"[package]");
+    temp.write("target/artifact", b"1234");
+    let output = cleaner_with_env(
+        &[
+            "--json",
+            "--confirm",
+            "--trash",
+            temp.path().to_str().unwrap(),
+        ],
+        &[("XDG_DATA_HOME", data.path())],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"disposal\":\"trash\""), "{stdout}");
+    assert!(stdout.contains("\"bytes_freed\":4"), "{stdout}");
+    assert!(!temp.join("target").exists());
+    assert!(data.join("Trash/files/target/artifact").exists());
+    let info = std::fs::read_to_string(data.join("Trash/info/target.trashinfo")).unwrap();
+    assert!(info.starts_with("[Trash Info]\nPath=/"));
+}
+
+#[cfg(unix)]
+#[test]
+fn quarantined_runs_can_be_restored_and_purged() {
+    let temp = TempDir::new("quarantine");
+    let data = TempDir::new("quarantine-data");
+    let env = [("XDG_DATA_HOME", data.path())];
+    temp.write("app/package.json", b"{}");
+    temp.write("app/node_modules/dep/index.js", b"12345");
+    temp.write("lib/cache.pyc", b"12");
+    let folder = temp.path().to_str().unwrap();
+    let output = cleaner_with_env(&["--json", "--confirm", "--quarantine", folder], &env);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"disposal\":\"quarantine\""), "{stdout}");
+    assert!(!temp.join("app/node_modules").exists());
+    assert!(!temp.join("lib/cache.pyc").exists());
+
+    let listing = cleaner_with_env(&["restore"], &env);
+    let listing = String::from_utf8_lossy(&listing.stdout);
+    assert!(
+        listing.contains("node_modules") && listing.contains("2 items"),
+        "{listing}"
+    );
+
+    let app = temp.join("app");
+    let restored = cleaner_with_env(&["restore", app.to_str().unwrap()], &env);
+    assert!(restored.status.success());
+    assert_eq!(
+        std::fs::read(temp.join("app/node_modules/dep/index.js")).unwrap(),
+        b"12345"
+    );
+    let missing = cleaner_with_env(&["restore", "no-such-id"], &env);
+    assert!(!missing.status.success());
+
+    let kept = cleaner_with_env(&["purge", "--older-than", "1d"], &env);
+    assert!(String::from_utf8_lossy(&kept.stdout).contains("Nothing to purge"));
+    let bare = cleaner_with_env(&["purge"], &env);
+    assert!(!bare.status.success());
+    let purged = cleaner_with_env(&["purge", "--all"], &env);
+    assert!(purged.status.success());
+    assert!(String::from_utf8_lossy(&purged.stdout).contains("Purged 1 items"));
+    let empty = cleaner_with_env(&["restore"], &env);
+    assert!(String::from_utf8_lossy(&empty.stdout).contains("Quarantine is empty"));
+    assert!(!temp.join("lib/cache.pyc").exists());
+
+    // With a retention, a run that quarantines first expires older items.
+    let config = temp.write("retention.toml", b"quarantine_days = 0\n");
+    let config = config.to_str().unwrap();
+    for name in ["first.pyc", "second.pyc"] {
+        temp.write(format!("lib/{name}"), b"1");
+        let run = cleaner_with_env(&["--confirm", "--quarantine", "-c", config, folder], &env);
+        assert!(run.status.success());
+    }
+    let listing = cleaner_with_env(&["restore"], &env);
+    let listing = String::from_utf8_lossy(&listing.stdout);
+    assert!(
+        listing.contains("second.pyc") && !listing.contains("first.pyc"),
+        "{listing}"
+    );
+}
+
+#[test]
+fn directories_without_their_marker_are_kept() {
+    let temp = TempDir::new("markers");
+    temp.write("tools/build/deploy.sh", b"#!/bin/sh");
+    temp.write("app/package.json", b"{}");
+    temp.write("app/node_modules/dep/index.js", b"");
+    let output = cleaner(&["--json", "--confirm", temp.path().to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(temp.join("tools/build/deploy.sh").exists());
+    assert!(!temp.join("app/node_modules").exists());
+}
+
+#[test]
+fn excluded_paths_survive_a_live_run() {
+    let temp = TempDir::new("exclude");
+    temp.write("legacy/cache.pyc", b"1");
+    temp.write("app/cache.pyc", b"1");
+    let output = cleaner(&[
+        "--json",
+        "--confirm",
+        "--exclude",
+        "legacy",
+        temp.path().to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(temp.join("legacy/cache.pyc").exists());
+    assert!(!temp.join("app/cache.pyc").exists());
+}
+
+#[test]
+fn relative_excludes_name_paths_below_the_scanned_folder() {
+    let temp = TempDir::new("exclude-relative");
+    temp.write("keep/cache.pyc", b"1");
+    temp.write("app/keep/cache.pyc", b"1");
+    temp.write("legacy/cache.pyc", b"1");
+    let output = cleaner(&[
+        "--json",
+        "--confirm",
+        "--exclude",
+        "./legacy",
+        "--exclude",
+        "keep",
+        temp.path().to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(temp.join("legacy/cache.pyc").exists());
+    assert!(temp.join("keep/cache.pyc").exists());
+    assert!(!temp.join("app/keep/cache.pyc").exists());
+}
+
+#[test]
+fn protect_rules_from_config_are_honored_and_reported() {
+    let temp = TempDir::new("protect");
+    temp.write("data/cache.pyc", b"1");
+    temp.write("work/cache.pyc", b"1");
+    let config = temp.write(
+        "cleaner.toml",
+        format!(
+            "[protect]\nadd = ['{}']\nremove = ['/opt/ci']\n",
+            temp.join("data").display()
+        )
+        .as_bytes(),
+    );
+    let output = cleaner(&[
+        "--json",
+        "--confirm",
+        "-c",
+        config.to_str().unwrap(),
+        temp.path().to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("\"{}\"", temp.join("data").display())));
+    assert!(stdout.contains("\"unprotected\":[\"/opt/ci\"]"));
+    assert!(temp.join("data/cache.pyc").exists());
+    assert!(!temp.join("work/cache.pyc").exists());
+}
+
+#[test]
+fn project_config_adds_patterns_for_its_subtree() {
+    let temp = TempDir::new("project-config");
+    temp.write(
+        "repo/.cleaner.toml",
+        b"[patterns]\ndirectories = { extend = ['generated'] }\n",
+    );
+    temp.write("repo/generated/a.rs", b"");
+    temp.write("elsewhere/generated/a.rs", b"");
+    let output = cleaner(&["--json", "--confirm", temp.path().to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(!temp.join("repo/generated").exists());
+    assert!(temp.join("elsewhere/generated/a.rs").exists());
+}
+
 #[test]
 fn human_readable_live_run_reports_verbose_results() {
     let temp = TempDir::new("human-output");
@@ -134,3 +417,161 @@ fn human_readable_missing_folder_error_goes_to_stderr() {
     assert!(!output.status.success());
     assert!(String::from_utf8_lossy(&output.stderr).contains("Folder does not exist"));
 }
+
+#[test]
+fn diff_lists_directories_by_growth_between_two_indexes() {
+    use cleaner_core::config::Config;
+    use cleaner_core::patterns::PatternMatcher;
+    use cleaner_core::tree::{DirTree, ScanProgress};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    let temp = TempDir::new("diff");
+    let root = temp.join("root");
+    let save = |name: &str| {
+        let (_, index) = DirTree::build_indexed(
+            &root,
+            &PatternMatcher::new(Arc::new(Config::empty())),
+            Arc::new(ScanProgress::new()),
+            Arc::new(AtomicBool::new(false)),
+            false,
+            None,
+        );
+        let path = temp.join(name);
+        index.unwrap().save(&path, true).unwrap();
+        path
+    };
+    temp.write("root/logs/a.log", b"1");
+    temp.write("root/old/x", b"1");
+    let old = save("old.idx");
+    // Incompressible, so its disk usage is its length on any filesystem.
+    let noise: Vec<u8> = (0u32..64 * 1024)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    temp.write("root/logs/b.log", &noise);
+    std::fs::remove_dir_all(root.join("old")).unwrap();
+    let new = save("new.idx");
+
+    let output = cleaner(&["diff", old.to_str().unwrap(), new.to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].contains("Changes in"), "{stdout}");
+    // The root grew by the new log less the removed directory.
+    assert!(lines[1].ends_with("logs"), "{stdout}");
+    assert!(lines[1].contains("+64 KiB"), "{stdout}");
+    assert!(lines[2].ends_with(root.to_str().unwrap()), "{stdout}");
+    assert!(lines[3].ends_with("(removed)"), "{stdout}");
+    assert!(stdout.contains("3 directories changed"), "{stdout}");
+
+    let limited = cleaner(&[
+        "diff",
+        old.to_str().unwrap(),
+        new.to_str().unwrap(),
+        "-n",
+        "1",
+    ]);
+    assert!(String::from_utf8_lossy(&limited.stdout).contains("showing the 1 that grew most"));
+
+    let unrelated = temp.join("other");
+    std::fs::create_dir(&unrelated).unwrap();
+    let missing = cleaner_with_env(
+        &["diff", unrelated.to_str().unwrap()],
+        &[("XDG_CACHE_HOME", temp.path())],
+    );
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("No earlier scan"));
+}
+
+#[test]
+fn top_lists_the_largest_files_at_any_depth() {
+    let temp = TempDir::new("top");
+    // Incompressible, so disk usage follows length on any filesystem.
+    let noise = |len: u32| -> Vec<u8> {
+        (0..len)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect()
+    };
+    temp.write("big.bin", &noise(256 * 1024));
+    temp.write("a/b/deep.bin", &noise(64 * 1024));
+    temp.write("a/small.txt", b"1");
+
+    let output = cleaner(&["top", temp.path().to_str().unwrap(), "-n", "2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].starts_with("Largest files in"), "{stdout}");
+    assert!(lines[1].ends_with("big.bin"), "{stdout}");
+    assert!(lines[2].ends_with("deep.bin"), "{stdout}");
+    assert!(lines[3].contains("3 files in"), "{stdout}");
+
+    let with_dirs = cleaner(&["top", temp.path().to_str().unwrap(), "--dirs"]);
+    let stdout = String::from_utf8_lossy(&with_dirs.stdout);
+    assert!(stdout.lines().any(|line| line.ends_with("b/")), "{stdout}");
+    assert!(!stdout.lines().any(|line| line.ends_with("a/")), "{stdout}");
+}
+
+#[test]
+fn stats_breaks_a_folder_down_by_category_and_extension() {
+    let temp = TempDir::new("stats");
+    temp.write("movie.MKV", &[7; 3000]);
+    temp.write("a/backup.tar.gz", &[7; 2000]);
+    temp.write("a/b/app.log", &[7; 1000]);
+    temp.write("a/b/other.log", &[7; 500]);
+    temp.write("README", b"hi");
+
+    let output = cleaner(&["stats", temp.path().to_str().unwrap(), "--by-ext", "--json"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("\"sizes\":\"disk\",\"files\":5,"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("{\"category\":\"media\",\"files\":1,"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("{\"extension\":\"log\",\"category\":\"logs\",\"files\":2,"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("{\"extension\":\"\",\"category\":\"other\",\"files\":1,"),
+        "{stdout}"
+    );
+
+    let table = cleaner(&["stats", temp.path().to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&table.stdout);
+    assert!(stdout.starts_with("File types in"), "{stdout}");
+    assert!(stdout.contains("Archives"), "{stdout}");
+    assert!(!stdout.contains(".gz"), "{stdout}");
+
+    let missing = cleaner(&["stats", "/definitely/not/here", "--json"]);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stdout).contains("\"success\":false"));
+}
+
+#[cfg(unix)]
+#[test]
+fn owners_add_up_the_files_of_each_user() {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp = TempDir::new("owners");
+    temp.write("a/one.bin", &[7; 3000]);
+    temp.write("two.bin", &[7; 100]);
+    let uid = std::fs::metadata(temp.join("two.bin")).unwrap().uid();
+
+    let output = cleaner(&["owners", temp.path().to_str().unwrap(), "--json"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"files\":2,"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("\"owners\":[{{\"uid\":{uid},\"user\":")),
+        "{stdout}"
+    );
+
+    let table = cleaner(&["owners", temp.path().to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&table.stdout);
+    assert!(stdout.starts_with("Owners in"), "{stdout}");
+    assert!(stdout.contains("2 files"), "{stdout}");
+}